@@ -0,0 +1,400 @@
+//! Generates client-side validators mirroring the server's rules
+//!
+//! Html attributes only cover some of the rules checked by ValidateForm
+//! (`validate_match`, `one_of` and custom messages have no equivalent).
+//! `Form::client_validator_js()` returns a dependency-free ES module that
+//! checks every rule described by [`FormMeta`] in the browser, reporting
+//! failures through the Constraint Validation API (`setCustomValidity`), so
+//! the usual `:invalid` styling and submit blocking apply.
+//!
+//! Messages are the ones supplied with `message = "..."` or `#[html_error]`,
+//! falling back to the same text as `ValidateError`, with `{field}`, `{min}`,
+//! `{max}` and `{value}` filled in.  Patterns are passed to `RegExp` as
+//! written, so they should stick to the syntax shared by the regex crate and
+//! JavaScript.  Lengths are counted in UTF-8 bytes, like `str::len`.
+//!
+//! The module exports:
+//!
+//! * `rules` - the rules for each field, keyed by field name
+//! * `validateField(form, name)` - checks one field, returning its message
+//!   (empty if valid)
+//! * `validate(form)` - checks every field, returning the messages of those
+//!   that failed keyed by field name
+//! * `attach(form)` - validates the form now and whenever it changes
+//!
+//! Requires the `client` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use webforms::client::ClientValidator;
+//! use webforms::validate::{ValidateError, ValidateForm};
+//!
+//! #[derive(ValidateForm)]
+//! struct LoginForm {
+//!     #[validate(min_length = 4, message = "Usernames have at least {min} characters")]
+//!     pub username: String,
+//! }
+//!
+//! let js = LoginForm::client_validator_js();
+//! assert!(js.contains("export function attach(form)"));
+//! ```
+//!
+//! Which is served as a module and attached to the rendered form:
+//!
+//! ```html
+//! <script type="module">
+//!   import { attach } from "/js/login-form.js";
+//!   attach(document.querySelector("#login"));
+//! </script>
+//! ```
+
+use crate::meta::{FormInfo, FormMeta, Rule};
+use crate::validate::{EMAIL_PATTERN, PHONE_PATTERN, URL_PATTERN};
+
+/// Generates a client-side validator for a form.  Implemented for every type
+/// implementing `FormMeta`
+pub trait ClientValidator: FormMeta {
+    /// Returns an ES module validating this form, see the module documentation
+    fn client_validator_js() -> String {
+        client_validator_js(Self::form_info())
+    }
+}
+
+impl<T: FormMeta> ClientValidator for T {}
+
+/// Checks the rules in `rules`, shared by every generated module
+const RUNTIME: &str = r#"
+function element(form, name) {
+  const el = form.elements.namedItem(name);
+  return el instanceof RadioNodeList ? el[0] : el;
+}
+
+function valueOf(form, name) {
+  const el = form.elements.namedItem(name);
+  if (!el) {
+    return "";
+  }
+  if (el.type === "checkbox") {
+    return el.checked ? el.value : "";
+  }
+  return el.value;
+}
+
+// Lengths are counted in UTF-8 bytes, as on the server
+function byteLength(value) {
+  return new TextEncoder().encode(value).length;
+}
+
+function failed(form, rule, value) {
+  switch (rule.rule) {
+    case "minLength":
+      return byteLength(value) < rule.value;
+    case "maxLength":
+      return byteLength(value) > rule.value;
+    case "minValue":
+      return value !== "" && Number(value) < rule.value;
+    case "maxValue":
+      return value !== "" && Number(value) > rule.value;
+    case "pattern":
+    case "email":
+    case "phone":
+    case "url":
+      return !rule.value.test(value);
+    case "matches":
+      return value !== valueOf(form, rule.value);
+    case "required":
+      return value === "";
+    case "notBlank":
+      return value.trim() === "";
+    case "oneOf":
+      return !rule.value.includes(value);
+    default:
+      return false;
+  }
+}
+
+function interpolate(message, name, rule, value) {
+  return message.replace(/\{(field|min|max|value)\}/g, (_, key) => {
+    switch (key) {
+      case "field":
+        return name;
+      case "value":
+        return value;
+      default:
+        return String(rule.value);
+    }
+  });
+}
+
+export function validateField(form, name) {
+  const field = rules[name];
+  const el = element(form, name);
+  if (!field || !el) {
+    return "";
+  }
+
+  // As on the server, an empty optional field is only checked by `required`
+  // and `matches`
+  const value = valueOf(form, name);
+  let message = "";
+  for (const rule of field.rules) {
+    const whole = rule.rule === "required" || rule.rule === "matches";
+    if (field.optional && value === "" && !whole) {
+      continue;
+    }
+    if (failed(form, rule, value)) {
+      message = interpolate(rule.message, name, rule, value);
+      break;
+    }
+  }
+
+  el.setCustomValidity(message);
+  return message;
+}
+
+export function validate(form) {
+  const errors = {};
+  for (const name of Object.keys(rules)) {
+    const message = validateField(form, name);
+    if (message) {
+      errors[name] = message;
+    }
+  }
+  return errors;
+}
+
+export function attach(form) {
+  // Every field is checked on each change so `matches` rules follow the
+  // field they compare against
+  const update = () => validate(form);
+  form.addEventListener("input", update);
+  form.addEventListener("change", update);
+  update();
+}
+"#;
+
+/// Returns an ES module validating a form, see the module documentation.
+/// Fields holding a nested form are skipped, as are repeated names (e.g., the
+/// same field in several enum variants) after the first
+///
+/// # Arguments
+/// * `form` - Form to validate
+pub fn client_validator_js(form: FormInfo) -> String {
+    let mut js = format!(
+        "// Client-side validator for {}, generated by webforms\n\nexport const rules = {{\n",
+        form.name
+    );
+
+    let mut seen = vec![];
+    for field in form.fields {
+        if (field.nested)().is_some() || seen.contains(&field.name) {
+            continue;
+        }
+        seen.push(field.name);
+
+        js.push_str(&format!(
+            "  {}: {{\n    optional: {},\n    rules: [\n",
+            js_string(field.name),
+            field.optional
+        ));
+
+        for constraint in field.constraints {
+            let (rule, value) = rule_js(&constraint.rule);
+            let message = constraint
+                .message
+                .unwrap_or_else(|| default_message(&constraint.rule));
+
+            js.push_str(&format!(
+                "      {{ rule: {}, value: {}, message: {} }},\n",
+                js_string(rule),
+                value,
+                js_string(message)
+            ));
+        }
+
+        js.push_str("    ],\n  },\n");
+    }
+
+    js.push_str("};\n");
+    js.push_str(RUNTIME);
+    js
+}
+
+/// Returns the name the runtime uses for a rule, along with the JavaScript
+/// expression for its value
+///
+/// # Arguments
+/// * `rule` - Rule to convert
+fn rule_js(rule: &Rule) -> (&'static str, String) {
+    match *rule {
+        Rule::MinLength(len) => ("minLength", len.to_string()),
+        Rule::MaxLength(len) => ("maxLength", len.to_string()),
+        Rule::MinValue(value) => ("minValue", value.to_string()),
+        Rule::MaxValue(value) => ("maxValue", value.to_string()),
+        Rule::Pattern(pattern) => ("pattern", regex_js(pattern)),
+        Rule::Email => ("email", regex_js(EMAIL_PATTERN)),
+        Rule::Phone => ("phone", regex_js(PHONE_PATTERN)),
+        Rule::Url => ("url", regex_js(URL_PATTERN)),
+        Rule::Matches(other) => ("matches", js_string(other)),
+        Rule::Required => ("required", "null".to_owned()),
+        Rule::NotBlank => ("notBlank", "null".to_owned()),
+        Rule::OneOf(values) => {
+            let values: Vec<_> = values.iter().map(|v| js_string(v)).collect();
+            ("oneOf", format!("[{}]", values.join(", ")))
+        }
+    }
+}
+
+/// Returns the message reported when a rule without a custom message fails.
+/// Matches the `Display` implementation of the corresponding `ValidateError`
+///
+/// # Arguments
+/// * `rule` - Rule that failed
+pub(crate) fn default_message(rule: &Rule) -> &'static str {
+    match *rule {
+        Rule::MinLength(_) => "{field}: input too short. ({min} min length)",
+        Rule::MaxLength(_) => "{field}: input too long. ({max} max length)",
+        Rule::MinValue(_) => "{field}: input below required minimum. ({min} minimum)",
+        Rule::MaxValue(_) => "{field}: input above maximum allowed. ({max} maximum)",
+        Rule::Pattern(_) => "{field}: does not match required input",
+        Rule::Email => "{field}: not a valid email address",
+        Rule::Phone => "{field}: not a valid U.S. phone number",
+        Rule::Url => "{field}: not a valid url",
+        Rule::Matches(_) => "{field}: does not match other field",
+        Rule::Required => "{field}: is required",
+        Rule::NotBlank => "{field}: must not be blank",
+        Rule::OneOf(_) => "{field}: not one of the allowed values",
+    }
+}
+
+/// Returns a JavaScript expression compiling a regular expression
+///
+/// # Arguments
+/// * `pattern` - Pattern to compile
+pub(crate) fn regex_js(pattern: &str) -> String {
+    format!("new RegExp({})", js_string(pattern))
+}
+
+/// Quotes and escapes a string as a JavaScript string literal
+///
+/// # Arguments
+/// * `s` - String to quote
+pub(crate) fn js_string(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\u{2028}' | '\u{2029}' => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(all(test, feature = "html"))]
+mod tests {
+    use crate::client::{default_message, js_string, ClientValidator};
+    use crate::html::HtmlForm;
+    use crate::meta::Rule;
+    use crate::validate::{interpolate, ValidateError, ValidateForm};
+    use lazy_static::lazy_static;
+    use regex::Regex;
+
+    #[derive(ValidateForm, HtmlForm)]
+    struct SignupForm {
+        #[validate(min_length = 3)]
+        #[html_validate(maxlength = 16)]
+        #[html_error(maxlength = "Keep it under {max} characters")]
+        pub username: String,
+
+        #[validate(email)]
+        pub email: String,
+
+        pub password: String,
+
+        #[validate_match(password, message = "Passwords do not match")]
+        pub confirm: String,
+
+        #[validate(one_of("S", "M", "L"))]
+        pub size: Option<String>,
+    }
+
+    #[test]
+    fn test_client_validator_js() {
+        let js = SignupForm::client_validator_js();
+
+        assert!(js.starts_with("// Client-side validator for SignupForm"));
+        assert!(js.contains(
+            r#"{ rule: "minLength", value: 3, message: "{field}: input too short. ({min} min length)" }"#
+        ));
+        assert!(js.contains(
+            r#"{ rule: "maxLength", value: 16, message: "Keep it under {max} characters" }"#
+        ));
+        assert!(js.contains(r#"{ rule: "email", value: new RegExp("^[a-zA-Z0-9_.+-]+@"#));
+        assert!(js.contains(
+            r#"{ rule: "matches", value: "password", message: "Passwords do not match" }"#
+        ));
+        assert!(js.contains(
+            "  \"size\": {\n    optional: true,\n    rules: [\n      { rule: \"oneOf\", value: [\"S\", \"M\", \"L\"]"
+        ));
+        assert!(js.contains("export function validate(form)"));
+        assert!(js.contains("return byteLength(value) < rule.value;"));
+    }
+
+    #[test]
+    fn test_default_messages() {
+        let errors = [
+            (
+                Rule::MinLength(3),
+                ValidateError::InputTooShort { field: "f", min: 3 },
+            ),
+            (
+                Rule::MaxLength(3),
+                ValidateError::InputTooLong { field: "f", max: 3 },
+            ),
+            (
+                Rule::MinValue(3.0),
+                ValidateError::TooSmall { field: "f", min: 3 },
+            ),
+            (
+                Rule::MaxValue(3.0),
+                ValidateError::TooLarge { field: "f", max: 3 },
+            ),
+            (
+                Rule::Pattern(""),
+                ValidateError::InvalidRegex { field: "f" },
+            ),
+            (Rule::Email, ValidateError::InvalidEmail { field: "f" }),
+            (
+                Rule::Phone,
+                ValidateError::InvalidPhoneNumber { field: "f" },
+            ),
+            (Rule::Url, ValidateError::InvalidUrl { field: "f" }),
+            (
+                Rule::Matches(""),
+                ValidateError::FieldMismatch { field: "f" },
+            ),
+            (Rule::Required, ValidateError::Required { field: "f" }),
+            (Rule::NotBlank, ValidateError::Blank { field: "f" }),
+            (Rule::OneOf(&[]), ValidateError::NotOneOf { field: "f" }),
+        ];
+
+        for (rule, error) in &errors {
+            let message = interpolate(default_message(rule), &error.params());
+            assert_eq!(message, error.to_string());
+        }
+    }
+
+    #[test]
+    fn test_js_string() {
+        assert_eq!(js_string("a\"b\\c\nd\u{2028}"), r#""a\"b\\c\nd\u2028""#);
+    }
+}
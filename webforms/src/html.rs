@@ -35,7 +35,7 @@ mod html_validate;
 pub use self::html_attribute::HtmlAttribute;
 pub use self::html_field::{HtmlField, HtmlFieldBuilder};
 pub use self::html_form_builder::HtmlFormBuilder;
-pub use self::html_validate::FieldValidator;
+pub use self::html_validate::{FieldError, FieldValidator};
 
 /// HtmlForm provides two methods, render_field and render_form. Both provide
 /// different ways to accomplish the same goal, rendering a form as valid and safe
@@ -47,3 +47,474 @@ pub trait HtmlForm {
     /// Returns a blank form that hasn't been validated
     fn blank_form(&self) -> HtmlFormBuilder;
 }
+
+/// Support code for the derive macro, not public API.  Adds the fields of a
+/// nested form (a field whose type implements `HtmlForm`, or a `Vec` or
+/// `Option` of one) in place of an input, named after the field holding it,
+/// and reads the current value of a field as it is re-rendered
+#[doc(hidden)]
+pub mod __private {
+    use super::{HtmlFieldBuilder, HtmlForm, HtmlFormBuilder};
+    use std::fmt::Display;
+
+    pub struct Nested<'a, T: ?Sized>(pub &'a T);
+
+    /// Renders a nested form, filled in (`form`) when `fill` is set or blank
+    /// (`blank_form`) otherwise
+    fn nested_form<T: HtmlForm>(nested: &T, fill: bool) -> HtmlFormBuilder<'_> {
+        match fill {
+            true => nested.form(),
+            false => nested.blank_form(),
+        }
+    }
+
+    pub trait ViaHtmlForm {
+        fn webforms_add_field(
+            &self,
+            form: &mut HtmlFormBuilder,
+            name: &str,
+            field: HtmlFieldBuilder,
+            fill: bool,
+        );
+    }
+
+    impl<'a, T: HtmlForm> ViaHtmlForm for &&Nested<'a, T> {
+        fn webforms_add_field(
+            &self,
+            form: &mut HtmlFormBuilder,
+            name: &str,
+            _: HtmlFieldBuilder,
+            fill: bool,
+        ) {
+            form.add_nested(name, nested_form(self.0, fill));
+        }
+    }
+
+    pub trait ViaHtmlForms {
+        fn webforms_add_field(
+            &self,
+            form: &mut HtmlFormBuilder,
+            name: &str,
+            field: HtmlFieldBuilder,
+            fill: bool,
+        );
+    }
+
+    impl<'a, T: HtmlForm> ViaHtmlForms for &Nested<'a, Vec<T>> {
+        fn webforms_add_field(
+            &self,
+            form: &mut HtmlFormBuilder,
+            name: &str,
+            _: HtmlFieldBuilder,
+            fill: bool,
+        ) {
+            for (idx, nested) in self.0.iter().enumerate() {
+                form.add_nested(format!("{}[{}]", name, idx), nested_form(nested, fill));
+            }
+        }
+    }
+
+    impl<'a, T: HtmlForm> ViaHtmlForms for &Nested<'a, Option<T>> {
+        fn webforms_add_field(
+            &self,
+            form: &mut HtmlFormBuilder,
+            name: &str,
+            _: HtmlFieldBuilder,
+            fill: bool,
+        ) {
+            if let Some(nested) = self.0 {
+                form.add_nested(name, nested_form(nested, fill));
+            }
+        }
+    }
+
+    pub trait ViaInput {
+        fn webforms_add_field(
+            &self,
+            form: &mut HtmlFormBuilder,
+            name: &str,
+            field: HtmlFieldBuilder,
+            fill: bool,
+        );
+    }
+
+    impl<'a, T: ?Sized> ViaInput for Nested<'a, T> {
+        fn webforms_add_field(
+            &self,
+            form: &mut HtmlFormBuilder,
+            name: &str,
+            field: HtmlFieldBuilder,
+            _: bool,
+        ) {
+            form.add_field(name, field);
+        }
+    }
+
+    /// Current value of a field: its `Display` form, that of the value in an
+    /// `Option` (None if empty), or None for types that can't be displayed
+    pub struct Value<'a, T: ?Sized>(pub &'a T);
+
+    pub trait ViaDisplay {
+        fn webforms_value(&self) -> Option<String>;
+    }
+
+    impl<'a, T: Display + ?Sized> ViaDisplay for &&Value<'a, T> {
+        fn webforms_value(&self) -> Option<String> {
+            Some(self.0.to_string())
+        }
+    }
+
+    pub trait ViaOption {
+        fn webforms_value(&self) -> Option<String>;
+    }
+
+    impl<'a, T: Display> ViaOption for &Value<'a, Option<T>> {
+        fn webforms_value(&self) -> Option<String> {
+            self.0.as_ref().map(|value| value.to_string())
+        }
+    }
+
+    pub trait ViaNoValue {
+        fn webforms_value(&self) -> Option<String>;
+    }
+
+    impl<'a, T: ?Sized> ViaNoValue for Value<'a, T> {
+        fn webforms_value(&self) -> Option<String> {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html::HtmlForm;
+
+    #[derive(HtmlForm)]
+    struct GenericForm<'a, T, N, const LEN: usize>
+    where
+        T: std::fmt::Display,
+    {
+        #[html_validate(minlength = 3)]
+        pub name: T,
+
+        #[html_validate(min = 1, max = 10)]
+        pub count: Option<N>,
+
+        pub label: &'a str,
+
+        pub codes: [u8; LEN],
+    }
+
+    #[test]
+    fn test_generic_form() {
+        let form: GenericForm<String, u8, 1> = GenericForm {
+            name: "mike".to_owned(),
+            count: Some(3),
+            label: "label",
+            codes: [0],
+        };
+        assert!(form.form().errs().is_empty());
+
+        let form: GenericForm<&str, i64, 0> = GenericForm {
+            name: "mike",
+            count: Some(30),
+            label: "label",
+            codes: [],
+        };
+        let form = form.form();
+        assert_eq!(form.errs().len(), 1);
+        assert_eq!(form.field_errors()["count"].code, "too_large");
+
+        let form: GenericForm<&str, i8, 0> = GenericForm {
+            name: "mike",
+            count: Some(-3),
+            label: "label",
+            codes: [],
+        };
+        let form = form.form();
+        assert_eq!(form.field_errors()["count"].code, "too_small");
+    }
+
+    #[derive(HtmlForm)]
+    #[form(rename_all = "camelCase")]
+    struct RenameForm {
+        #[form(min_length = 2, message = "Too short")]
+        pub first_name: String,
+
+        #[form(rename = "user-name", max_value = 99)]
+        pub age: u8,
+
+        #[form(email)]
+        pub email: String,
+    }
+
+    #[test]
+    fn test_rename_form() {
+        let form = RenameForm {
+            first_name: "m".to_owned(),
+            age: 100,
+            email: "mike@test.com".to_owned(),
+        };
+        let form = form.form();
+
+        assert_eq!(form.errs().len(), 2);
+        assert_eq!(form.field_errors()["firstName"].message, "Too short");
+        assert_eq!(form.errs()["firstName"], "Too short");
+        assert_eq!(form.field_errors()["user-name"].code, "too_large");
+
+        let first = form.builder("firstName").finish().to_string();
+        assert!(first.contains("name='firstName'"));
+        assert!(first.contains("minlength='2'"));
+
+        let email = form.builder("email").finish().to_string();
+        assert!(email.contains("type='email'"));
+    }
+
+    #[cfg(feature = "types")]
+    #[derive(HtmlForm)]
+    struct ContactForm {
+        pub email: crate::types::Email,
+        pub phone: Option<crate::types::PhoneNumber>,
+        pub site: crate::types::Url,
+    }
+
+    #[cfg(feature = "types")]
+    #[test]
+    fn test_types_input_type() {
+        use std::convert::TryFrom;
+
+        let form = ContactForm {
+            email: crate::types::Email::try_from("mike@test.com").unwrap(),
+            phone: None,
+            site: crate::types::Url::try_from("https://test.com").unwrap(),
+        };
+        let form = form.form();
+
+        assert!(form
+            .builder("email")
+            .finish()
+            .to_string()
+            .contains("type='email'"));
+        assert!(form
+            .builder("phone")
+            .finish()
+            .to_string()
+            .contains("type='tel'"));
+        assert!(form
+            .builder("site")
+            .finish()
+            .to_string()
+            .contains("type='url'"));
+    }
+
+    #[cfg(feature = "types")]
+    #[derive(HtmlForm)]
+    struct BoundedForm {
+        pub name: crate::types::BoundedString<3, 32>,
+        pub quantity: Option<crate::types::Ranged<i64, { -5 }, 100>>,
+    }
+
+    #[cfg(feature = "types")]
+    #[test]
+    fn test_bounded_constraints() {
+        let form = BoundedForm {
+            name: crate::types::BoundedString::new("mike").unwrap(),
+            quantity: None,
+        };
+        let form = form.form();
+
+        let name = form.builder("name").finish().to_string();
+        assert!(name.contains("minlength='3'"));
+        assert!(name.contains("maxlength='32'"));
+
+        let quantity = form.builder("quantity").finish().to_string();
+        assert!(quantity.contains("type='number'"));
+        assert!(quantity.contains("min='-5'"));
+        assert!(quantity.contains("max='100'"));
+    }
+
+    #[derive(HtmlForm)]
+    struct Address {
+        pub street: String,
+        pub city: Option<String>,
+    }
+
+    #[derive(HtmlForm)]
+    struct LineItem {
+        #[form(min_length = 2)]
+        pub sku: String,
+        pub qty: u32,
+    }
+
+    #[derive(HtmlForm)]
+    struct OrderForm {
+        pub customer: String,
+        pub address: Address,
+        pub billing: Option<Address>,
+        pub items: Vec<LineItem>,
+    }
+
+    #[test]
+    fn test_nested_form() {
+        let item = |sku: &str| LineItem {
+            sku: sku.to_owned(),
+            qty: 1,
+        };
+        let form = OrderForm {
+            customer: "mike".to_owned(),
+            address: Address {
+                street: "1 Main St".to_owned(),
+                city: None,
+            },
+            billing: None,
+            items: vec![item("A1"), item("B2")],
+        };
+        let form = form.form();
+
+        let mut names: Vec<String> = form
+            .to_string()
+            .lines()
+            .map(|line| line.split("name='").nth(1).unwrap())
+            .map(|rest| rest[..rest.find('\'').unwrap()].to_owned())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "address[city]",
+                "address[street]",
+                "customer",
+                "items[0][qty]",
+                "items[0][sku]",
+                "items[1][qty]",
+                "items[1][sku]",
+            ]
+        );
+
+        let qty = form.builder("items[1][qty]").finish().to_string();
+        assert!(qty.contains("type='number'"));
+        assert!(qty.contains("required"));
+        assert!(form.validated());
+    }
+
+    #[test]
+    fn test_nested_form_errors() {
+        let item = |sku: &str| LineItem {
+            sku: sku.to_owned(),
+            qty: 1,
+        };
+        let form = OrderForm {
+            customer: "mike".to_owned(),
+            address: Address {
+                street: "1 Main St".to_owned(),
+                city: None,
+            },
+            billing: None,
+            items: vec![item("A1"), item("B")],
+        };
+        let form = form.form();
+
+        // Nested errors are keyed by the name the field is rendered with
+        assert!(!form.validated());
+        assert_eq!(form.field_errors().len(), 1);
+        assert_eq!(form.field_errors()["items[1][sku]"].code, "input_too_short");
+        assert_eq!(form.errs().len(), 1);
+        assert!(form.errs().contains_key("items[1][sku]"));
+    }
+
+    #[derive(HtmlForm)]
+    struct ProfileForm {
+        #[form(min_length = 3)]
+        pub name: String,
+
+        #[html_input(password)]
+        pub password: String,
+
+        pub newsletter: bool,
+
+        #[form(one_of("S", "M", "L"))]
+        pub size: String,
+
+        #[form(one_of("red", "blue"))]
+        pub color: Option<String>,
+
+        #[form(max_value = 120)]
+        pub age: Option<u8>,
+    }
+
+    #[test]
+    fn test_sticky_form() {
+        let profile = ProfileForm {
+            name: "<b>'Mo' & co</b>".to_owned(),
+            password: "hunter2".to_owned(),
+            newsletter: true,
+            size: "M".to_owned(),
+            color: None,
+            age: Some(130),
+        };
+        let form = profile.form();
+
+        let name = form.builder("name").finish().to_string();
+        assert!(name.contains("value='&lt;b&gt;&#39;Mo&#39; &amp; co&lt;/b&gt;'"));
+
+        let password = form.builder("password").finish().to_string();
+        assert!(password.contains("type='password'"));
+        assert!(!password.contains("hunter2"));
+        assert!(!password.contains("value="));
+
+        let newsletter = form.builder("newsletter").finish().to_string();
+        assert!(newsletter.contains("type='checkbox'"));
+        assert!(newsletter.contains("checked"));
+        assert!(!newsletter.contains("required"));
+
+        let size = form.builder("size").finish().to_string();
+        assert!(size.starts_with("<select"));
+        assert!(!size.contains("type="));
+        assert!(size.contains(
+            "<option value='S'>S</option><option value='M' selected>M</option><option value='L'>L</option></select>"
+        ));
+
+        let color = form.builder("color").finish().to_string();
+        assert!(color.contains("<option value=''></option><option value='red'>red</option>"));
+        assert!(!color.contains("selected"));
+
+        let age = form.builder("age").finish().to_string();
+        assert!(age.contains("value='130'"));
+
+        assert_eq!(form.errs().len(), 1);
+        assert!(!form.validated());
+
+        let profile = ProfileForm {
+            newsletter: false,
+            age: Some(30),
+            ..profile
+        };
+        let form = profile.form();
+        assert!(form.validated());
+        let newsletter = form.builder("newsletter").finish().to_string();
+        assert!(!newsletter.contains("checked"));
+
+        let blank = profile.blank_form();
+        assert!(!blank.validated());
+        let name = blank.builder("name").finish().to_string();
+        assert!(!name.contains("value="));
+        let size = blank.builder("size").finish().to_string();
+        assert!(!size.contains("selected"));
+    }
+
+    #[test]
+    fn test_validated_after_failure() {
+        // Only the first field fails, so later fields passing mustn't hide it
+        let profile = ProfileForm {
+            name: "Mo".to_owned(),
+            password: "hunter2".to_owned(),
+            newsletter: false,
+            size: "M".to_owned(),
+            color: Some("red".to_owned()),
+            age: Some(30),
+        };
+        let form = profile.form();
+        assert_eq!(form.field_errors()["name"].code, "input_too_short");
+        assert!(!form.validated());
+    }
+}
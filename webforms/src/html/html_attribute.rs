@@ -50,7 +50,7 @@ impl HtmlAttribute {
     pub fn render(&self) -> String {
         match &self {
             HtmlAttribute::Single(ref val) => format!(" {}", val),
-            HtmlAttribute::Pair(ref name, ref val) => format!(" {}='{}'", name, val),
+            HtmlAttribute::Pair(ref name, ref val) => format!(" {}='{}'", name, escape(val)),
         }
     }
 
@@ -68,6 +68,27 @@ impl HtmlAttribute {
     }
 }
 
+/// Escapes a value for use in an attribute or element body, so submitted
+/// values can't close the attribute (or tag) they are rendered into
+///
+/// # Arguments
+///
+/// * `value` - Value to escape
+pub(crate) fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '\'' => escaped.push_str("&#39;"),
+            '"' => escaped.push_str("&quot;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 impl PartialEq for HtmlAttribute {
     fn eq(&self, other: &HtmlAttribute) -> bool {
         match &self {
@@ -96,7 +117,7 @@ impl std::fmt::Display for HtmlAttribute {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             HtmlAttribute::Single(ref value) => write!(f, "{}", value),
-            HtmlAttribute::Pair(ref attr, ref value) => write!(f, "{}='{}'", attr, value),
+            HtmlAttribute::Pair(ref attr, ref value) => write!(f, "{}='{}'", attr, escape(value)),
         }
     }
 }
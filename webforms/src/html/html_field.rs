@@ -1,5 +1,6 @@
 //! Represents an Html Tag/Field
 
+use crate::html::html_attribute::escape;
 use crate::html::HtmlAttribute;
 use std::collections::HashSet;
 
@@ -8,6 +9,7 @@ pub struct HtmlFieldBuilder {
     pub tag: String,
     pub name: Option<String>,
     pub attrs: HashSet<HtmlAttribute>,
+    pub options: Vec<(String, bool)>,
     pub replace: bool,
 }
 
@@ -15,6 +17,7 @@ pub struct HtmlField {
     pub tag: String,
     pub name: Option<String>,
     pub attrs: HashSet<HtmlAttribute>,
+    pub options: Vec<(String, bool)>,
 }
 
 impl HtmlFieldBuilder {
@@ -30,6 +33,7 @@ impl HtmlFieldBuilder {
             tag: tag.into(),
             name: name.map(|s| s.into()),
             attrs: HashSet::new(),
+            options: Vec::new(),
             replace: false,
         };
 
@@ -58,6 +62,7 @@ impl HtmlFieldBuilder {
             tag: tag.into(),
             name: name.map(|s| s.into()),
             attrs: attrs,
+            options: Vec::new(),
             replace: false,
         };
 
@@ -70,6 +75,19 @@ impl HtmlFieldBuilder {
         field
     }
 
+    /// Changes the name of this field, updating its `name` attribute
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - New name of the field
+    pub fn rename<S: Into<String>>(mut self, name: S) -> Self {
+        let name = name.into();
+        self.attrs
+            .replace(HtmlAttribute::new_pair("name", name.clone()));
+        self.name = Some(name);
+        self
+    }
+
     /// Adds `attr` to this fields attribute set, either updating the existing
     /// value (in append mode), or replacing it completely (in replace mode)
     ///
@@ -94,6 +112,7 @@ impl HtmlFieldBuilder {
             tag: self.tag,
             name: None,
             attrs: self.attrs,
+            options: self.options,
         }
     }
 
@@ -157,6 +176,56 @@ impl HtmlFieldBuilder {
         self
     }
 
+    /// Adds an option to this field, rendered as an `<option>` when the
+    /// field's tag is `select`
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Value (and label) of the option
+    pub fn option<S: Into<String>>(mut self, value: S) -> Self {
+        self.options.push((value.into(), false));
+        self
+    }
+
+    /// Fills in the field with a submitted (or current) value, so a form can
+    /// be re-rendered as the user left it.  Selects the matching option of a
+    /// `select`, checks a checkbox unless the value is empty, `0`, `false`
+    /// or `off`, and otherwise replaces the `value` attribute.  Password and
+    /// file inputs are never filled
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Value to fill in
+    pub fn set_value<S: Into<String>>(mut self, value: S) -> Self {
+        let value = value.into();
+        if self.tag == "select" {
+            for option in self.options.iter_mut() {
+                option.1 = option.0 == value;
+            }
+            return self;
+        }
+
+        let input_type = match self.attrs.get(&HtmlAttribute::new_pair("type", "")) {
+            Some(HtmlAttribute::Pair(_, ref t)) => t.clone(),
+            _ => String::new(),
+        };
+        match input_type.as_str() {
+            "password" | "file" => {}
+            "checkbox" => match value.as_str() {
+                "" | "0" | "false" | "off" => {
+                    self.attrs.remove(&HtmlAttribute::new_single("checked"));
+                }
+                _ => {
+                    self.attrs.insert(HtmlAttribute::new_single("checked"));
+                }
+            },
+            _ => {
+                self.attrs.replace(HtmlAttribute::new_pair("value", value));
+            }
+        }
+        self
+    }
+
     /// Helper method to set class attribute
     ///
     /// # Arguments
@@ -182,12 +251,29 @@ impl HtmlFieldBuilder {
 
 impl std::fmt::Display for HtmlField {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "<input")?;
+        if self.tag != "select" {
+            write!(f, "<input")?;
+            for attr in &self.attrs {
+                write!(f, " {}", attr)?;
+            }
+            return write!(f, ">");
+        }
 
+        write!(f, "<select")?;
         for attr in &self.attrs {
             write!(f, " {}", attr)?;
         }
+        write!(f, ">")?;
+
+        for (value, selected) in &self.options {
+            let value = escape(value);
+            if *selected {
+                write!(f, "<option value='{}' selected>{}</option>", value, value)?;
+            } else {
+                write!(f, "<option value='{}'>{}</option>", value, value)?;
+            }
+        }
 
-        write!(f, ">")
+        write!(f, "</select>")
     }
 }
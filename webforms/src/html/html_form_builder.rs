@@ -1,14 +1,16 @@
 //! Module to build HtmlForms
 
-use crate::html::{FieldValidator, HtmlFieldBuilder};
+use crate::html::{FieldError, FieldValidator, HtmlAttribute, HtmlFieldBuilder};
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::marker::PhantomData;
 
 pub struct HtmlFormBuilder<'a> {
-    fields: HashMap<&'static str, HtmlFieldBuilder>,
-    errors: HashMap<&'static str, String>,
+    fields: HashMap<String, HtmlFieldBuilder>,
+    errors: HashMap<String, FieldError>,
+    messages: HashMap<String, String>,
     validated: bool,
+    nested_validated: bool,
     phantom: PhantomData<&'a i32>,
 }
 
@@ -19,7 +21,9 @@ impl<'a> HtmlFormBuilder<'a> {
         HtmlFormBuilder {
             fields: HashMap::new(),
             errors: HashMap::new(),
+            messages: HashMap::new(),
             validated: false,
+            nested_validated: true,
             phantom: PhantomData,
         }
     }
@@ -39,13 +43,14 @@ impl<'a> HtmlFormBuilder<'a> {
 
     /// Returns true if this form has been sucessfully validated,
     /// false if validation failed or it never occured (i.e., called
-    /// `blank_form`)
+    /// `blank_form`).  Nested forms must have been validated as well
     pub fn validated(&self) -> bool {
-        self.validated
+        self.validated && self.nested_validated
     }
 
     /// Validates a field's value against a list of closures, setting the
-    /// validated field appropriately
+    /// validated field appropriately.  The form stays invalid once any field
+    /// has failed
     ///
     /// # Arguments
     ///
@@ -54,7 +59,15 @@ impl<'a> HtmlFormBuilder<'a> {
     //pub fn validate_field<T: Debug>(&mut self, value: &T, validators: Vec<Box<&Fn(&T) -> bool>>) {
     pub fn validate_field<T>(&mut self, value: &T, validator: FieldValidator<'a, T>) {
         //self.validated = validators.iter().all(|x| x(value));
-        self.validated = validator.validate(value, &mut self.errors);
+        // A form is only valid if every field is, so a later field passing
+        // mustn't hide an earlier failure
+        validator.validate(value, &mut self.errors);
+        self.validated = self.errors.is_empty();
+        if let Some(error) = self.errors.get(validator.field()) {
+            self.messages
+                .entry(validator.field().to_owned())
+                .or_insert_with(|| error.to_string());
+        }
     }
 
     /// Returns all errors that occured during form validation, or
@@ -67,13 +80,70 @@ impl<'a> HtmlFormBuilder<'a> {
         None
     }
 
-    pub fn errs(&self) -> &HashMap<&'static str, String> {
+    pub fn errs(&self) -> &HashMap<String, String> {
+        &self.messages
+    }
+
+    /// Returns the error (code, message and parameters) of each field that
+    /// failed validation, keyed by field name.  Errors of nested forms are
+    /// keyed by the names their fields are rendered with (e.g.,
+    /// `address[city]`).  Use `locale::Localize` to translate them
+    pub fn field_errors(&self) -> &HashMap<String, FieldError> {
         &self.errors
     }
 
+    /// Returns the `enctype` the form must be submitted with:
+    /// `multipart/form-data` if any field is a file input (e.g., an
+    /// `UploadedFile` field or one with a `file` rule), otherwise
+    /// `application/x-www-form-urlencoded`
+    pub fn enctype(&self) -> &'static str {
+        // Attributes are compared by name, so this finds each field's type
+        let ty = HtmlAttribute::new_pair("type", "");
+        let has_file = self
+            .fields
+            .values()
+            .any(|field| match field.attrs.get(&ty) {
+                Some(HtmlAttribute::Pair(_, ref value)) => value == "file",
+                _ => false,
+            });
+
+        match has_file {
+            true => "multipart/form-data",
+            false => "application/x-www-form-urlencoded",
+        }
+    }
+
     /// Adds a new field builder (and thus field) to this form builder
-    pub fn add_field(&mut self, name: &'static str, field: HtmlFieldBuilder) {
-        self.fields.insert(name, field);
+    pub fn add_field<S: Into<String>>(&mut self, name: S, field: HtmlFieldBuilder) {
+        self.fields.insert(name.into(), field);
+    }
+
+    /// Adds the fields and errors of a nested form, naming each one after
+    /// the field holding the form (e.g., `city` becomes `address[city]` and,
+    /// in a repeated form, `items[0][sku]`).  This form is only validated if
+    /// the nested form is
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - Name of the field holding the form (e.g., `address`)
+    /// * `form` - Nested form
+    pub fn add_nested<S: AsRef<str>>(&mut self, prefix: S, form: HtmlFormBuilder) {
+        let nested_name = |name: &str| match name.find('[') {
+            Some(idx) => format!("{}[{}]{}", prefix.as_ref(), &name[..idx], &name[idx..]),
+            None => format!("{}[{}]", prefix.as_ref(), name),
+        };
+
+        self.nested_validated &= form.validated();
+        for (name, field) in form.fields {
+            let name = nested_name(&name);
+            self.fields.insert(name.clone(), field.rename(name));
+        }
+        for (name, error) in form.errors {
+            self.errors.entry(nested_name(&name)).or_insert(error);
+        }
+        for (name, message) in form.messages {
+            self.messages.entry(nested_name(&name)).or_insert(message);
+        }
     }
 }
 
@@ -1,10 +1,50 @@
 //! Common attribute validation critera
 
 use std::collections::HashMap;
+use std::fmt;
 
-type CheckFn<T> = Fn(&T) -> std::result::Result<(), &'static str>;
+type CheckFn<T> = Fn(&T) -> std::result::Result<(), FieldError>;
 //type CheckFn<T> = Fn(T) -> bool;
 
+/// Describes why a field failed validation.  The `code` matches the codes
+/// used by `ValidateError` so both can share a message catalog
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FieldError {
+    pub code: &'static str,
+    pub message: &'static str,
+    pub params: &'static [(&'static str, &'static str)],
+}
+
+impl FieldError {
+    /// Creates a new FieldError
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - Machine-readable code (e.g., "too_small")
+    /// * `message` - Default (or `#[html_error]`) message to display
+    /// * `params` - Parameters describing the failed check (e.g., `("min", "5")`)
+    pub fn new(
+        code: &'static str,
+        message: &'static str,
+        params: &'static [(&'static str, &'static str)],
+    ) -> FieldError {
+        FieldError {
+            code,
+            message,
+            params,
+        }
+    }
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for FieldError {}
+
 pub struct FieldValidator<'a, T> {
     field: &'static str,
     validators: Vec<Box<&'a CheckFn<T>>>,
@@ -18,15 +58,15 @@ impl<'a, T> FieldValidator<'a, T> {
         }
     }
 
-    pub fn field(&self) -> &str {
-        &self.field
+    pub fn field(&self) -> &'static str {
+        self.field
     }
 
-    pub fn validate(&self, value: &T, errors: &mut HashMap<&'static str, String>) -> bool {
+    pub fn validate(&self, value: &T, errors: &mut HashMap<String, FieldError>) -> bool {
         self.validators.iter().all(|x| match x(value) {
             Ok(_) => true,
             Err(e) => {
-                errors.entry(self.field).or_insert(e.to_string());
+                errors.entry(self.field.to_owned()).or_insert(e);
                 false
             }
         })
@@ -0,0 +1,393 @@
+//! Exports forms as JSON Schema (draft 2020-12) documents
+//!
+//! Built on [`FormMeta`], so any form deriving ValidateForm or HtmlForm can
+//! be exported with `Form::json_schema()`.  Rules map onto their JSON Schema
+//! keywords:
+//!
+//! | rule | keyword |
+//! | ---- | ------- |
+//! | min_length / max_length | `minLength` / `maxLength` |
+//! | min_value / max_value | `minimum` / `maximum` |
+//! | regex | `pattern` |
+//! | email / url | `format: email` / `format: uri` |
+//! | required | listed in `required`, `minLength: 1` for strings |
+//! | not_blank | `pattern: \S` |
+//! | one_of | `enum` |
+//! | phone | `x-webforms-phone` |
+//! | validate_match | `x-webforms-match` |
+//!
+//! Rules without a JSON Schema equivalent are exported as `x-webforms-*`
+//! extension keywords rather than dropped.  Fields that aren't `Option<T>`
+//! are required, and fields whose type is itself a form are exported as a
+//! `$ref` to an entry in `$defs`.  Enums are exported as a `oneOf` listing an
+//! object for each variant.
+//!
+//! Requires the `json_schema` feature.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "validate")]
+//! # fn main() {
+//! use webforms::json_schema::JsonSchema;
+//! use webforms::validate::{ValidateError, ValidateForm};
+//!
+//! #[derive(ValidateForm)]
+//! struct LoginForm {
+//!     #[validate(min_length = 4)]
+//!     pub username: String,
+//!
+//!     pub remember: Option<bool>,
+//! }
+//!
+//! let schema = LoginForm::json_schema();
+//! assert_eq!(schema["properties"]["username"]["minLength"], 4);
+//! assert_eq!(schema["properties"]["remember"]["type"], "boolean");
+//! assert_eq!(schema["required"], serde_json::json!(["username"]));
+//! # }
+//! # #[cfg(not(feature = "validate"))]
+//! # fn main() {}
+//! ```
+
+use crate::meta::{FieldMeta, FormInfo, FormMeta, Rule};
+use serde_json::{json, Map, Value};
+
+/// Draft of JSON Schema the exported documents conform to
+pub const SCHEMA_DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Exports a form as a JSON Schema document.  Implemented for every type
+/// implementing `FormMeta`
+pub trait JsonSchema: FormMeta {
+    /// Returns the JSON Schema describing this form, including the
+    /// definitions of any nested forms under `$defs`
+    fn json_schema() -> Value {
+        json_schema(Self::form_info())
+    }
+}
+
+impl<T: FormMeta> JsonSchema for T {}
+
+/// Returns the JSON Schema describing a form
+///
+/// # Arguments
+/// * `form` - Form to describe
+pub fn json_schema(form: FormInfo) -> Value {
+    let mut defs = schemas(form, "#/$defs/");
+    let mut schema = match defs.remove(form.name) {
+        Some(Value::Object(schema)) => schema,
+        _ => unreachable!(),
+    };
+
+    let mut root = Map::new();
+    root.insert("$schema".to_owned(), json!(SCHEMA_DRAFT));
+    root.insert("title".to_owned(), json!(form.name));
+    root.append(&mut schema);
+    if !defs.is_empty() {
+        root.insert("$defs".to_owned(), Value::Object(defs));
+    }
+
+    Value::Object(root)
+}
+
+/// Returns the schemas of a form and of every form nested in it, keyed by
+/// name.  References to nested forms point to `{prefix}{name}`, so the schemas
+/// can be placed in another document (e.g., `#/components/schemas/`)
+///
+/// # Arguments
+/// * `form` - Form to describe
+/// * `prefix` - Location the schemas will be placed at
+pub fn schemas(form: FormInfo, prefix: &str) -> Map<String, Value> {
+    let mut defs = Map::new();
+    let schema = form_schema(form, &mut defs, prefix);
+    defs.insert(form.name.to_owned(), schema);
+    defs
+}
+
+/// Returns the schema of a form (without `$schema` or `$defs`), adding the
+/// definitions of nested forms to `defs`
+///
+/// # Arguments
+/// * `form` - Form to describe
+/// * `defs` - Definitions of the nested forms found so far
+/// * `prefix` - Location the definitions will be placed at
+fn form_schema(form: FormInfo, defs: &mut Map<String, Value>, prefix: &str) -> Value {
+    let mut variants: Vec<Option<&'static str>> = vec![];
+    for field in form.fields {
+        if !variants.contains(&field.variant) {
+            variants.push(field.variant);
+        }
+    }
+
+    match variants.as_slice() {
+        [] | [None] => object_schema(form.fields, defs, prefix),
+        _ => {
+            let one_of: Vec<Value> = variants
+                .iter()
+                .map(|variant| {
+                    let fields: Vec<FieldMeta> = form
+                        .fields
+                        .iter()
+                        .filter(|f| f.variant == *variant)
+                        .cloned()
+                        .collect();
+                    let mut schema = object_schema(&fields, defs, prefix);
+                    if let (Some(variant), Value::Object(ref mut schema)) = (variant, &mut schema) {
+                        schema.insert("title".to_owned(), json!(variant));
+                    }
+                    schema
+                })
+                .collect();
+
+            json!({ "oneOf": one_of })
+        }
+    }
+}
+
+/// Returns the schema of an object holding the given fields
+///
+/// # Arguments
+/// * `fields` - Fields of the object
+/// * `defs` - Definitions of the nested forms found so far
+/// * `prefix` - Location the definitions will be placed at
+fn object_schema(fields: &[FieldMeta], defs: &mut Map<String, Value>, prefix: &str) -> Value {
+    let mut properties = Map::new();
+    let mut required = vec![];
+
+    for field in fields {
+        properties.insert(field.name.to_owned(), field_schema(field, defs, prefix));
+        if field.is_required() {
+            required.push(json!(field.name));
+        }
+    }
+
+    let mut schema = Map::new();
+    schema.insert("type".to_owned(), json!("object"));
+    schema.insert("properties".to_owned(), Value::Object(properties));
+    if !required.is_empty() {
+        schema.insert("required".to_owned(), Value::Array(required));
+    }
+
+    Value::Object(schema)
+}
+
+/// Returns the schema of a single field
+///
+/// # Arguments
+/// * `field` - Field to describe
+/// * `defs` - Definitions of the nested forms found so far
+/// * `prefix` - Location the definitions will be placed at
+fn field_schema(field: &FieldMeta, defs: &mut Map<String, Value>, prefix: &str) -> Value {
+    if let Some(form) = (field.nested)() {
+        if !defs.contains_key(form.name) {
+            let schema = form_schema(form, defs, prefix);
+            defs.insert(form.name.to_owned(), schema);
+        }
+
+        return json!({ "$ref": format!("{}{}", prefix, form.name) });
+    }
+
+    let ty = value_type(field);
+    let mut schema = Map::new();
+    schema.insert("type".to_owned(), json!(ty));
+
+    let format = match field.input_type {
+        "email" => Some("email"),
+        "url" => Some("uri"),
+        "date" => Some("date"),
+        "time" => Some("time"),
+        "datetime-local" => Some("date-time"),
+        _ => None,
+    };
+    if let Some(format) = format {
+        schema.insert("format".to_owned(), json!(format));
+    }
+
+    for rule in field.rules() {
+        let (keyword, value) = match *rule {
+            Rule::MinLength(len) => ("minLength", json!(len)),
+            Rule::MaxLength(len) => ("maxLength", json!(len)),
+            Rule::MinValue(value) => ("minimum", number(value)),
+            Rule::MaxValue(value) => ("maximum", number(value)),
+            Rule::Pattern(pattern) => ("pattern", json!(pattern)),
+            Rule::Email => ("format", json!("email")),
+            Rule::Url => ("format", json!("uri")),
+            Rule::NotBlank => ("pattern", json!(r"\S")),
+            Rule::OneOf(values) => ("enum", json!(values)),
+            Rule::Required if ty == "string" => ("minLength", json!(1)),
+            Rule::Required => continue,
+            Rule::Phone => ("x-webforms-phone", json!(true)),
+            Rule::Matches(other) => ("x-webforms-match", json!(other)),
+        };
+
+        add_keyword(&mut schema, keyword, value);
+    }
+
+    Value::Object(schema)
+}
+
+/// Adds a keyword to a schema.  If the keyword is already present (e.g., two
+/// patterns), the new value is added under `allOf` so both apply.  The larger
+/// `minLength` wins, as when `required` is combined with `min_length`
+///
+/// # Arguments
+/// * `schema` - Schema to add the keyword to
+/// * `keyword` - Name of the keyword
+/// * `value` - Value of the keyword
+fn add_keyword(schema: &mut Map<String, Value>, keyword: &str, value: Value) {
+    match schema.get(keyword) {
+        None => {
+            schema.insert(keyword.to_owned(), value);
+        }
+        Some(existing) if *existing == value => {}
+        Some(existing) if keyword == "minLength" => {
+            if existing.as_u64() < value.as_u64() {
+                schema.insert(keyword.to_owned(), value);
+            }
+        }
+        Some(_) => {
+            let all_of = schema
+                .entry("allOf")
+                .or_insert_with(|| Value::Array(vec![]));
+            if let Value::Array(ref mut all_of) = all_of {
+                let mut extra = Map::new();
+                extra.insert(keyword.to_owned(), value);
+                all_of.push(Value::Object(extra));
+            }
+        }
+    }
+}
+
+/// Returns the JSON type of a field's value, based on its Rust type
+///
+/// # Arguments
+/// * `field` - Field to inspect
+pub(crate) fn value_type(field: &FieldMeta) -> &'static str {
+    let ty = field.rust_type.trim();
+    let ty = match ty.starts_with("Option<") && ty.ends_with('>') {
+        true => &ty["Option<".len()..ty.len() - 1],
+        false => ty,
+    };
+    let ty = ty.trim_start_matches('&');
+
+    match ty {
+        _ if ty.starts_with("Vec<") || ty.starts_with('[') => "array",
+        "bool" => "boolean",
+        "f32" | "f64" => "number",
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128"
+        | "usize" => "integer",
+        _ if field.input_type == "number" => "integer",
+        _ => "string",
+    }
+}
+
+/// Converts a bound to a JSON number, without a fraction if it is a whole number
+fn number(value: f64) -> Value {
+    match value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        true => json!(value as i64),
+        false => json!(value),
+    }
+}
+
+#[cfg(all(test, feature = "validate"))]
+mod tests {
+    use crate::json_schema::JsonSchema;
+    use crate::validate::{ValidateError, ValidateForm};
+    use lazy_static::lazy_static;
+    use regex::Regex;
+    use serde_json::json;
+
+    #[derive(ValidateForm)]
+    struct Address {
+        #[validate(min_length = 1, max_length = 64)]
+        pub street: String,
+
+        #[validate(regex = r"^\d{5}$", not_blank)]
+        pub zip: String,
+    }
+
+    #[derive(ValidateForm)]
+    struct OrderForm {
+        #[validate(required, email)]
+        pub email: Option<String>,
+
+        #[validate(phone)]
+        pub phone: Option<String>,
+
+        #[validate(min_value = 1, max_value = 10)]
+        pub quantity: u8,
+
+        pub price: f64,
+
+        pub gift: bool,
+
+        #[validate_match(email)]
+        pub confirm_email: Option<String>,
+
+        pub shipping: Address,
+
+        pub billing: Option<Address>,
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema = OrderForm::json_schema();
+
+        assert_eq!(schema["$schema"], crate::json_schema::SCHEMA_DRAFT);
+        assert_eq!(schema["title"], "OrderForm");
+        assert_eq!(schema["type"], "object");
+        assert_eq!(
+            schema["required"],
+            json!(["email", "quantity", "price", "gift", "shipping"])
+        );
+
+        let properties = &schema["properties"];
+        assert_eq!(
+            properties["email"],
+            json!({ "type": "string", "format": "email", "minLength": 1 })
+        );
+        assert_eq!(
+            properties["phone"],
+            json!({ "type": "string", "x-webforms-phone": true })
+        );
+        assert_eq!(
+            properties["quantity"],
+            json!({ "type": "integer", "minimum": 1, "maximum": 10 })
+        );
+        assert_eq!(properties["price"], json!({ "type": "number" }));
+        assert_eq!(properties["gift"], json!({ "type": "boolean" }));
+        assert_eq!(properties["confirm_email"]["x-webforms-match"], "email");
+
+        assert_eq!(properties["shipping"], json!({ "$ref": "#/$defs/Address" }));
+        assert_eq!(properties["billing"], json!({ "$ref": "#/$defs/Address" }));
+        assert_eq!(
+            schema["$defs"]["Address"],
+            json!({
+                "type": "object",
+                "properties": {
+                    "street": { "type": "string", "minLength": 1, "maxLength": 64 },
+                    "zip": { "type": "string", "pattern": r"^\d{5}$", "allOf": [{ "pattern": r"\S" }] },
+                },
+                "required": ["street", "zip"],
+            })
+        );
+    }
+
+    #[allow(dead_code)]
+    #[derive(ValidateForm)]
+    enum Payment {
+        Card {
+            #[validate(min_length = 16)]
+            number: String,
+        },
+        Invoice(#[validate(email)] String),
+    }
+
+    #[test]
+    fn test_enum_json_schema() {
+        let schema = Payment::json_schema();
+        assert_eq!(schema["oneOf"][0]["title"], "Card");
+        assert_eq!(schema["oneOf"][0]["properties"]["number"]["minLength"], 16);
+        assert_eq!(schema["oneOf"][1]["title"], "Invoice");
+        assert_eq!(schema["oneOf"][1]["properties"]["0"]["format"], "email");
+    }
+}
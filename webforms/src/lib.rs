@@ -3,15 +3,65 @@
 //! Currently impleted traits:
 //! * `ValidateForm` - Checks each annotated field for requirement list in the field attributes.
 //! * `HtmlForm` - Produces valid html input fields for each field in a form
+//!
+//! Errors from either trait can be translated with a message catalog, see the
+//! `locale` module.  Both derives also implement `meta::FormMeta`, a static
+//! description of the form's fields
 //! 
 //! See each module for examples
 //! 
 //! # Features
 //! * `validate` - Enables the ValidateForm trait and derive macro
 //! * `html` - Enables the HtmlForm trait and derive macro
+//! * `serde` - Serializes validation errors and builds RFC 7807 problem documents
+//! * `types` - Enables the `types` module (Email, PhoneNumber, Url, ...)
+//! * `schema` - Enables the `schema` module for forms defined at runtime
+//! * `json_schema` - Exports forms as JSON Schema documents
+//! * `openapi` - Generates OpenAPI 3.1 components describing forms
+//! * `client` - Generates client-side JavaScript validators for forms
+//! * `typescript` - Generates TypeScript interfaces and validators for forms
+//! * `parse` - Parses submitted forms into structs with the FromForm derive
+//! * `testing` - Assertions, submission builders and proptest strategies for
+//!   testing forms
+
+// Allows the derive macros (which emit `::webforms::...` paths) to be used
+// within this crate's own tests
+#[cfg(test)]
+extern crate self as webforms;
 
 #[cfg(feature = "validate")]
 pub mod validate;
 
 #[cfg(feature = "html")]
 pub mod html;
+
+pub mod locale;
+
+pub mod meta;
+
+#[cfg(feature = "types")]
+pub mod types;
+
+#[cfg(feature = "schema")]
+pub mod schema;
+
+#[cfg(feature = "json_schema")]
+pub mod json_schema;
+
+#[cfg(feature = "openapi")]
+pub mod openapi;
+
+#[cfg(feature = "client")]
+pub mod client;
+
+#[cfg(feature = "typescript")]
+pub mod typescript;
+
+#[cfg(feature = "parse")]
+pub mod parse;
+
+#[cfg(any(feature = "testing", all(test, feature = "validate")))]
+pub mod testing;
+
+#[cfg(all(feature = "serde", feature = "validate"))]
+pub mod problem;
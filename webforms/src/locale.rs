@@ -0,0 +1,279 @@
+//! Localizes validation errors using message catalogs
+//!
+//! Every error produced by `ValidateForm` or `HtmlForm` carries a code (e.g.,
+//! `input_too_short`) and a set of parameters (e.g., `field` and `min`).  A
+//! `MessageCatalog` maps a code and locale to a translated message, allowing
+//! errors to be displayed in the user's language.
+//!
+//! # Example
+//!
+//! ```
+//! use webforms::locale::{FluentCatalog, MessageCatalog};
+//!
+//! let mut catalog = FluentCatalog::new();
+//! catalog
+//!     .add_resource("de", "input_too_short = { $field } ist zu kurz (mindestens { $min })")
+//!     .unwrap();
+//!
+//! let params = [("field", "username".to_owned()), ("min", "3".to_owned())];
+//! let message = catalog.message("de-DE", "input_too_short", &params);
+//! assert_eq!(message.unwrap(), "username ist zu kurz (mindestens 3)");
+//! ```
+//!
+//! Errors are usually translated with `Localize`, see its implementations
+
+mod fluent;
+mod plural;
+
+pub use self::fluent::{CatalogError, FluentCatalog};
+pub use self::plural::plural_category;
+
+#[cfg(feature = "html")]
+use crate::html::FieldError;
+#[cfg(feature = "validate")]
+use crate::validate::ValidateError;
+#[cfg(feature = "html")]
+use std::collections::HashMap;
+
+/// A source of translated messages, keyed by locale and error code
+pub trait MessageCatalog {
+    /// Returns the message for `code` in `locale` with `params` substituted,
+    /// or None if the catalog does not contain the message
+    ///
+    /// # Arguments
+    ///
+    /// * `locale` - Locale identifier (e.g., "de-DE")
+    /// * `code` - Error code to look up (e.g., "input_too_short")
+    /// * `params` - Values available to the message (e.g., `("min", "8")`)
+    fn message(&self, locale: &str, code: &str, params: &[(&str, String)]) -> Option<String>;
+}
+
+/// Translates errors using a message catalog.  Errors without a translation
+/// fall back to their default (English or `message = "..."`) text
+pub trait Localize {
+    type Output;
+
+    /// Returns the localized form of these errors
+    ///
+    /// # Arguments
+    ///
+    /// * `catalog` - Catalog to look up messages in
+    /// * `locale` - Locale identifier (e.g., "de-DE")
+    fn localize<C: MessageCatalog + ?Sized>(&self, catalog: &C, locale: &str) -> Self::Output;
+}
+
+#[cfg(feature = "validate")]
+impl Localize for ValidateError {
+    type Output = String;
+
+    fn localize<C: MessageCatalog + ?Sized>(&self, catalog: &C, locale: &str) -> String {
+        let params = self.params();
+        catalog
+            .message(locale, self.code(), &params)
+            .or_else(|| match self {
+                ValidateError::Custom { error, .. } => {
+                    catalog.message(locale, error.code(), &params)
+                }
+                _ => None,
+            })
+            .unwrap_or_else(|| self.to_string())
+    }
+}
+
+/// Translates every error, pairing each message with its field
+///
+/// ```
+/// use webforms::locale::{FluentCatalog, Localize};
+/// use webforms::validate::ValidateError;
+///
+/// let mut catalog = FluentCatalog::new();
+/// catalog
+///     .add_resource("de", "input_too_short = { $field } ist zu kurz (mindestens { $min })")
+///     .unwrap();
+///
+/// let errors = vec![ValidateError::InputTooShort { field: "username", min: 3 }];
+/// let localized = errors.localize(&catalog, "de-DE");
+/// assert_eq!(localized[0].0, "username");
+/// assert_eq!(localized[0].1, "username ist zu kurz (mindestens 3)");
+/// ```
+#[cfg(feature = "validate")]
+impl Localize for [ValidateError] {
+    type Output = Vec<(String, String)>;
+
+    fn localize<C: MessageCatalog + ?Sized>(
+        &self,
+        catalog: &C,
+        locale: &str,
+    ) -> Vec<(String, String)> {
+        self.iter()
+            .map(|e| (e.field().to_owned(), e.localize(catalog, locale)))
+            .collect()
+    }
+}
+
+#[cfg(feature = "html")]
+impl Localize for HashMap<String, FieldError> {
+    type Output = HashMap<String, String>;
+
+    fn localize<C: MessageCatalog + ?Sized>(
+        &self,
+        catalog: &C,
+        locale: &str,
+    ) -> HashMap<String, String> {
+        self.iter()
+            .map(|(field, e)| {
+                let mut params = vec![("field", field.to_string())];
+                params.extend(e.params.iter().map(|(k, v)| (*k, v.to_string())));
+
+                let message = catalog
+                    .message(locale, e.code, &params)
+                    .unwrap_or_else(|| e.message.to_owned());
+                (field.clone(), message)
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "validate"))]
+mod tests {
+    use crate::locale::{plural_category, FluentCatalog, Localize, MessageCatalog};
+    use crate::validate::{ValidateError, ValidateForm};
+
+    const DE: &str = r#"
+# German messages
+input_too_short = { $field } muss mindestens { $min ->
+    [one] ein Zeichen
+   *[other] { $min } Zeichen
+} lang sein
+too_small = { $field } muss mindestens { $min } sein
+pw_short = Das Passwort ist zu kurz
+"#;
+
+    const JA: &str = "input_too_short = { $field }は{ $min }文字以上で入力してください";
+
+    fn catalog() -> FluentCatalog {
+        let mut catalog = FluentCatalog::new();
+        catalog.add_resource("de", DE).unwrap();
+        catalog.add_resource("ja", JA).unwrap();
+        catalog
+    }
+
+    #[derive(ValidateForm)]
+    struct SignupForm<'a> {
+        #[validate(min_length = 3)]
+        pub username: &'a str,
+
+        #[validate(
+            min_length = 8,
+            message = "Password needs {min} characters",
+            code = "pw_short"
+        )]
+        pub password: &'a str,
+
+        #[validate(min_length = 1, code = "nick_short")]
+        pub nickname: &'a str,
+    }
+
+    #[test]
+    fn test_plural_categories() {
+        assert_eq!(plural_category("en-US", 1.0), "one");
+        assert_eq!(plural_category("de", 2.0), "other");
+        assert_eq!(plural_category("fr", 0.0), "one");
+        assert_eq!(plural_category("ja", 1.0), "other");
+        assert_eq!(plural_category("ru", 3.0), "few");
+        assert_eq!(plural_category("ru", 11.0), "many");
+        assert_eq!(plural_category("pl", 22.0), "few");
+    }
+
+    #[test]
+    fn test_select_plural() {
+        let catalog = catalog();
+        let one = catalog.message(
+            "de",
+            "input_too_short",
+            &[("field", "name".to_owned()), ("min", "1".to_owned())],
+        );
+        let many = catalog.message(
+            "de",
+            "input_too_short",
+            &[("field", "name".to_owned()), ("min", "8".to_owned())],
+        );
+
+        assert_eq!(one.unwrap(), "name muss mindestens ein Zeichen lang sein");
+        assert_eq!(many.unwrap(), "name muss mindestens 8 Zeichen lang sein");
+    }
+
+    #[test]
+    fn test_localize_validate_errors() {
+        let form = SignupForm {
+            username: "ab",
+            password: "short",
+            nickname: "",
+        };
+
+        let errs = form.validate().unwrap_err();
+        let de = errs.localize(&catalog(), "de-DE");
+        let de: Vec<_> = de.iter().map(|(f, m)| (f.as_str(), m.as_str())).collect();
+        assert_eq!(
+            de[0],
+            ("username", "username muss mindestens 3 Zeichen lang sein")
+        );
+        assert_eq!(de[1], ("password", "Das Passwort ist zu kurz"));
+        // Unknown custom code falls back to the default code's message
+        assert_eq!(
+            de[2],
+            ("nickname", "nickname muss mindestens ein Zeichen lang sein")
+        );
+
+        let ja = errs.localize(&catalog(), "ja");
+        assert_eq!(ja[0].1, "usernameは3文字以上で入力してください");
+
+        // No catalog entry: fall back to the custom message, then the default text
+        let en = errs.localize(&catalog(), "en");
+        assert_eq!(en[1].1, "Password needs 8 characters");
+        assert_eq!(en[0].1, errs[0].to_string());
+    }
+
+    #[test]
+    fn test_localize_single_error() {
+        let err = ValidateError::TooSmall {
+            field: "age",
+            min: 18,
+        };
+        assert_eq!(
+            err.localize(&catalog(), "de"),
+            "age muss mindestens 18 sein"
+        );
+    }
+
+    #[cfg(feature = "html")]
+    #[test]
+    fn test_localize_html_errors() {
+        use crate::html::HtmlForm;
+
+        #[derive(HtmlForm)]
+        struct AgeForm {
+            #[html_validate(min = 18)]
+            pub age: i32,
+        }
+
+        let form = AgeForm { age: 12 }.form();
+        assert!(!form.validated());
+
+        let de = form.field_errors().localize(&catalog(), "de-DE");
+        assert_eq!(de["age"], "age muss mindestens 18 sein");
+
+        let en = form.field_errors().localize(&catalog(), "en");
+        assert_eq!(en["age"], "Minimum value is 18");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let mut catalog = FluentCatalog::new();
+        assert!(catalog.add_resource("en", "no equals sign").is_err());
+        assert!(catalog.add_resource("en", "a = { $x").is_err());
+        assert!(catalog
+            .add_resource("en", "a = { $x ->\n  [one] one\n}")
+            .is_err());
+    }
+}
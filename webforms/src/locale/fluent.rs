@@ -0,0 +1,474 @@
+//! A file-backed message catalog using a subset of the Fluent syntax
+//!
+//! Each message is a `code = pattern` line.  Patterns may reference parameters
+//! with `{ $name }` and pick between variants with a select expression.  Variant
+//! keys are matched against the exact parameter value first, then against the
+//! parameter's plural category in the requested locale:
+//!
+//! ```text
+//! # de.ftl
+//! input_too_short = { $field } muss mindestens { $min ->
+//!     [one] ein Zeichen
+//!    *[other] { $min } Zeichen
+//! } lang sein
+//! ```
+
+use crate::locale::{plural::language, plural_category, MessageCatalog};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Errors that can occur while loading a catalog
+#[derive(Debug)]
+pub enum CatalogError {
+    /// A catalog file could not be read
+    Io(std::io::Error),
+
+    /// A catalog contained invalid syntax
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatalogError::Io(e) => write!(f, "failed to read catalog: {}", e),
+            CatalogError::Parse { line, message } => {
+                write!(f, "invalid catalog syntax on line {}: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CatalogError {}
+
+impl From<std::io::Error> for CatalogError {
+    fn from(e: std::io::Error) -> CatalogError {
+        CatalogError::Io(e)
+    }
+}
+
+/// A single piece of a parsed message
+#[derive(Clone, Debug)]
+enum Element {
+    Text(String),
+    Variable(String),
+    Select {
+        variable: String,
+        variants: Vec<Variant>,
+        default: usize,
+    },
+}
+
+/// One branch of a select expression
+#[derive(Clone, Debug)]
+struct Variant {
+    key: String,
+    pattern: Vec<Element>,
+}
+
+/// Message catalog loaded from Fluent-style (`.ftl`) resources, keyed
+/// by locale and then by error code
+#[derive(Debug, Default)]
+pub struct FluentCatalog {
+    locales: HashMap<String, HashMap<String, Vec<Element>>>,
+}
+
+impl FluentCatalog {
+    /// Creates a new, empty catalog
+    pub fn new() -> FluentCatalog {
+        FluentCatalog {
+            locales: HashMap::new(),
+        }
+    }
+
+    /// Loads a catalog from a directory containing one `<locale>.ftl`
+    /// file per locale (e.g., `en.ftl`, `de-DE.ftl`)
+    ///
+    /// # Arguments
+    ///
+    /// * `dir` - Directory to load resources from
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<FluentCatalog, CatalogError> {
+        let mut catalog = FluentCatalog::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "ftl") {
+                if let Some(locale) = path.file_stem().and_then(|s| s.to_str()) {
+                    catalog.add_file(locale.to_owned(), &path)?;
+                }
+            }
+        }
+
+        Ok(catalog)
+    }
+
+    /// Parses and adds the messages in the file at `path` to `locale`
+    ///
+    /// # Arguments
+    ///
+    /// * `locale` - Locale the messages are written in (e.g., "de")
+    /// * `path` - Location of the `.ftl` file to load
+    pub fn add_file<S: Into<String>, P: AsRef<Path>>(
+        &mut self,
+        locale: S,
+        path: P,
+    ) -> Result<(), CatalogError> {
+        let source = fs::read_to_string(path)?;
+        self.add_resource(locale, &source)
+    }
+
+    /// Parses and adds the messages in `source` to `locale`.  Messages
+    /// already present for the locale are replaced
+    ///
+    /// # Arguments
+    ///
+    /// * `locale` - Locale the messages are written in (e.g., "de")
+    /// * `source` - Contents of a `.ftl` resource
+    pub fn add_resource<S: Into<String>>(
+        &mut self,
+        locale: S,
+        source: &str,
+    ) -> Result<(), CatalogError> {
+        let messages = parse_resource(source)?;
+        self.locales
+            .entry(locale.into())
+            .or_default()
+            .extend(messages);
+        Ok(())
+    }
+
+    /// Returns true if any messages have been loaded for `locale`
+    pub fn has_locale(&self, locale: &str) -> bool {
+        self.locales.contains_key(locale)
+    }
+
+    /// Finds the message for `code`, falling back from the full locale
+    /// (e.g., "de-DE") to its language (e.g., "de")
+    fn lookup(&self, locale: &str, code: &str) -> Option<&Vec<Element>> {
+        self.locales
+            .get(locale)
+            .and_then(|m| m.get(code))
+            .or_else(|| {
+                self.locales
+                    .get(&language(locale))
+                    .and_then(|m| m.get(code))
+            })
+    }
+}
+
+impl MessageCatalog for FluentCatalog {
+    fn message(&self, locale: &str, code: &str, params: &[(&str, String)]) -> Option<String> {
+        let pattern = self.lookup(locale, code)?;
+        let mut message = String::new();
+        format_pattern(pattern, locale, params, &mut message);
+        Some(message)
+    }
+}
+
+/// Writes a parsed pattern to `out`, substituting parameters and resolving
+/// select expressions.  Unknown parameters are written back as `{$name}`
+fn format_pattern(pattern: &[Element], locale: &str, params: &[(&str, String)], out: &mut String) {
+    let param = |name: &str| params.iter().find(|(k, _)| *k == name).map(|(_, v)| v);
+
+    for element in pattern {
+        match element {
+            Element::Text(text) => out.push_str(text),
+            Element::Variable(name) => match param(name) {
+                Some(value) => out.push_str(value),
+                None => out.push_str(&format!("{{${}}}", name)),
+            },
+            Element::Select {
+                variable,
+                variants,
+                default,
+            } => {
+                let variant = param(variable)
+                    .and_then(|value| {
+                        variants.iter().find(|v| v.key == *value).or_else(|| {
+                            let n = value.parse::<f64>().ok()?;
+                            let category = plural_category(locale, n);
+                            variants.iter().find(|v| v.key == category)
+                        })
+                    })
+                    .unwrap_or(&variants[*default]);
+
+                format_pattern(&variant.pattern, locale, params, out);
+            }
+        }
+    }
+}
+
+/// Parses a complete `.ftl` resource into a map of message id to pattern
+fn parse_resource(source: &str) -> Result<HashMap<String, Vec<Element>>, CatalogError> {
+    let mut messages = HashMap::new();
+    let mut current: Option<(String, usize, String)> = None;
+
+    for (idx, line) in source.lines().enumerate() {
+        let lineno = idx + 1;
+
+        // Indented lines (and lines closing a select expression) continue
+        // the previous message
+        let continues = line.starts_with(char::is_whitespace) || line.starts_with('}');
+        if continues && !line.trim().is_empty() {
+            match current {
+                Some((_, _, ref mut raw)) => {
+                    raw.push('\n');
+                    raw.push_str(line.trim());
+                    continue;
+                }
+                None => {
+                    return Err(CatalogError::Parse {
+                        line: lineno,
+                        message: "indented line does not belong to a message".to_owned(),
+                    })
+                }
+            }
+        }
+
+        if let Some((id, start, raw)) = current.take() {
+            messages.insert(id, Parser::new(raw.trim(), start).parse()?);
+        }
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let eq = line.find('=').ok_or_else(|| CatalogError::Parse {
+            line: lineno,
+            message: "expected `code = message`".to_owned(),
+        })?;
+
+        let id = line[..eq].trim();
+        if id.is_empty()
+            || !id
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(CatalogError::Parse {
+                line: lineno,
+                message: format!("invalid message id `{}`", id),
+            });
+        }
+
+        current = Some((id.to_owned(), lineno, line[eq + 1..].to_owned()));
+    }
+
+    if let Some((id, start, raw)) = current.take() {
+        messages.insert(id, Parser::new(raw.trim(), start).parse()?);
+    }
+
+    Ok(messages)
+}
+
+/// Parses the pattern (value) of a single message
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn new(source: &str, line: usize) -> Parser {
+        Parser {
+            chars: source.chars().collect(),
+            pos: 0,
+            line,
+        }
+    }
+
+    fn parse(mut self) -> Result<Vec<Element>, CatalogError> {
+        self.parse_pattern(false)
+    }
+
+    fn error<S: Into<String>>(&self, message: S) -> CatalogError {
+        CatalogError::Parse {
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).cloned()
+    }
+
+    /// Consumes `c` if it is the next character
+    fn eat(&mut self, c: char) -> bool {
+        match self.peek() {
+            Some(next) if next == c => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Parses text and placeables until the end of input or, inside a select
+    /// expression, the end of the current variant
+    fn parse_pattern(&mut self, in_variant: bool) -> Result<Vec<Element>, CatalogError> {
+        let mut elements = vec![];
+        let mut text = String::new();
+
+        while let Some(c) = self.peek() {
+            match c {
+                '{' => {
+                    if !text.is_empty() {
+                        elements.push(Element::Text(text.split_off(0)));
+                    }
+                    self.pos += 1;
+                    elements.push(self.parse_placeable()?);
+                }
+                '}' if in_variant => break,
+                '}' => return Err(self.error("unbalanced `}`")),
+                '\n' if in_variant && self.variant_ends() => break,
+                _ => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        if !text.is_empty() {
+            elements.push(Element::Text(text));
+        }
+
+        Ok(elements)
+    }
+
+    /// True if the line after the current newline starts a new variant
+    /// or closes the select expression
+    fn variant_ends(&self) -> bool {
+        self.chars[self.pos..]
+            .iter()
+            .find(|c| !c.is_whitespace())
+            .is_none_or(|c| matches!(c, '[' | '*' | '}'))
+    }
+
+    fn parse_identifier(&mut self) -> String {
+        let mut ident = String::new();
+        while let Some(c) = self.peek() {
+            if !(c.is_alphanumeric() || c == '_' || c == '-')
+                || self.chars[self.pos..].starts_with(&['-', '>'])
+            {
+                break;
+            }
+            ident.push(c);
+            self.pos += 1;
+        }
+        ident
+    }
+
+    /// Parses the contents of `{ ... }`, having already consumed the `{`
+    fn parse_placeable(&mut self) -> Result<Element, CatalogError> {
+        self.skip_whitespace();
+
+        let element = if self.eat('$') {
+            let variable = self.parse_identifier();
+            if variable.is_empty() {
+                return Err(self.error("expected a variable name after `$`"));
+            }
+
+            self.skip_whitespace();
+            if self.eat('-') {
+                if !self.eat('>') {
+                    return Err(self.error("expected `->`"));
+                }
+                self.parse_select(variable)?
+            } else {
+                Element::Variable(variable)
+            }
+        } else if self.eat('"') {
+            let mut literal = String::new();
+            loop {
+                match self.peek() {
+                    Some('"') => break,
+                    Some(c) => literal.push(c),
+                    None => return Err(self.error("unterminated string literal")),
+                }
+                self.pos += 1;
+            }
+            self.pos += 1;
+            Element::Text(literal)
+        } else {
+            return Err(self.error("expected a variable or string literal"));
+        };
+
+        self.skip_whitespace();
+        if !self.eat('}') {
+            return Err(self.error("expected `}`"));
+        }
+
+        Ok(element)
+    }
+
+    /// Parses the variants of a select expression, having already consumed `->`
+    fn parse_select(&mut self, variable: String) -> Result<Element, CatalogError> {
+        let mut variants = vec![];
+        let mut default = None;
+
+        loop {
+            self.skip_whitespace();
+            let is_default = self.eat('*');
+            if !self.eat('[') {
+                if is_default {
+                    return Err(self.error("expected `[` after `*`"));
+                }
+                break;
+            }
+
+            let mut key = String::new();
+            loop {
+                match self.peek() {
+                    Some(']') => break,
+                    Some(c) => key.push(c),
+                    None => return Err(self.error("unterminated variant key")),
+                }
+                self.pos += 1;
+            }
+            self.pos += 1;
+
+            if is_default {
+                if default.is_some() {
+                    return Err(self.error("select expression has multiple default variants"));
+                }
+                default = Some(variants.len());
+            }
+
+            let mut pattern = self.parse_pattern(true)?;
+            trim_pattern(&mut pattern);
+            variants.push(Variant {
+                key: key.trim().to_owned(),
+                pattern,
+            });
+        }
+
+        match default {
+            Some(default) => Ok(Element::Select {
+                variable,
+                variants,
+                default,
+            }),
+            None => Err(self.error("select expression requires a default `*[...]` variant")),
+        }
+    }
+}
+
+/// Removes leading and trailing whitespace from a variant's pattern
+fn trim_pattern(pattern: &mut Vec<Element>) {
+    if let Some(Element::Text(ref mut text)) = pattern.first_mut() {
+        *text = text.trim_start().to_owned();
+    }
+    if let Some(Element::Text(ref mut text)) = pattern.last_mut() {
+        *text = text.trim_end().to_owned();
+    }
+    pattern.retain(|e| match e {
+        Element::Text(text) => !text.is_empty(),
+        _ => true,
+    });
+}
@@ -0,0 +1,57 @@
+//! CLDR plural categories for the languages we ship catalogs for
+
+/// Returns the CLDR plural category (`zero`, `one`, `two`, `few`, `many` or
+/// `other`) for the number `n` in the given locale.  Only the language portion
+/// of the locale (e.g., `de` in `de-DE`) is considered.  Unknown languages use
+/// the English rules
+///
+/// # Arguments
+///
+/// * `locale` - Locale identifier (e.g., "en-US")
+/// * `n` - Number to categorize
+pub fn plural_category(locale: &str, n: f64) -> &'static str {
+    let lang = language(locale);
+    let integer = n.fract() == 0.0;
+    let i = n.abs().trunc() as u64;
+
+    match lang.as_str() {
+        // No plural forms
+        "ja" | "zh" | "ko" | "vi" | "th" | "id" => "other",
+
+        // 0 and 1 are singular
+        "fr" | "pt" => match i {
+            0 | 1 => "one",
+            _ => "other",
+        },
+
+        // East Slavic rules
+        "ru" | "uk" | "be" if integer => match (i % 10, i % 100) {
+            (1, r) if r != 11 => "one",
+            (2..=4, r) if !(12..=14).contains(&r) => "few",
+            _ => "many",
+        },
+
+        "pl" if integer => match (i, i % 10, i % 100) {
+            (1, _, _) => "one",
+            (_, 2..=4, r) if !(12..=14).contains(&r) => "few",
+            _ => "many",
+        },
+
+        "ru" | "uk" | "be" | "pl" => "other",
+
+        // Germanic / Romance default: only exactly 1 is singular
+        _ => match integer && i == 1 {
+            true => "one",
+            false => "other",
+        },
+    }
+}
+
+/// Extracts the lower-cased language subtag from a locale identifier
+///
+/// # Arguments
+///
+/// * `locale` - Locale identifier (e.g., "de-DE" or "de_DE")
+pub(crate) fn language(locale: &str) -> String {
+    locale.split(['-', '_']).next().unwrap_or("").to_lowercase()
+}
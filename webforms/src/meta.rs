@@ -0,0 +1,380 @@
+//! Static descriptions of forms, for tooling built on top of the derives
+//!
+//! Both `#[derive(ValidateForm)]` and `#[derive(HtmlForm)]` implement
+//! [`FormMeta`], describing every field of the form: the name it is
+//! submitted under, its Rust type, html input type, whether it is optional
+//! and the constraints applied to it (along with any custom message or code).
+//! Documentation, client-side validators or schema generators can read this
+//! instead of re-parsing attributes.
+//!
+//! A struct deriving both traits implements `FormMeta` once, describing the
+//! rules from every attribute (`#[validate]`, `#[form]`, `#[html_validate]`,
+//! ...).  Neither derive can see the other, so they go by the attributes the
+//! struct uses: ValidateForm implements it unless the struct only uses
+//! HtmlForm attributes (`#[html_validate]`, `#[html_input]`, ...), in which
+//! case HtmlForm does.  A struct deriving both that uses no attribute specific
+//! to either (e.g., only `#[form]`) adds a bare `#[validate_config]` to pick
+//! ValidateForm.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "validate")]
+//! # fn main() {
+//! use webforms::meta::{FormMeta, Rule};
+//! use webforms::validate::{ValidateError, ValidateForm};
+//!
+//! #[derive(ValidateForm)]
+//! struct LoginForm {
+//!     #[validate(min_length = 4, message = "Username is too short")]
+//!     pub username: String,
+//!
+//!     pub remember: Option<bool>,
+//! }
+//!
+//! let username = LoginForm::field("username").unwrap();
+//! assert_eq!(username.rust_type, "String");
+//! assert_eq!(username.input_type, "text");
+//! assert!(!username.optional);
+//! assert_eq!(username.constraints[0].rule, Rule::MinLength(4));
+//! assert_eq!(username.constraints[0].message, Some("Username is too short"));
+//!
+//! assert!(LoginForm::field("remember").unwrap().optional);
+//! # }
+//! # #[cfg(not(feature = "validate"))]
+//! # fn main() {}
+//! ```
+
+/// Describes a form and its fields.  Implemented by the ValidateForm and
+/// HtmlForm derives
+pub trait FormMeta {
+    /// Name of the struct (or enum) describing the form
+    fn form_name() -> &'static str;
+
+    /// Describes every field of the form, in declaration order.  Enums list
+    /// the fields of every variant
+    fn fields() -> &'static [FieldMeta];
+
+    /// Returns the field submitted under `name`, if any
+    ///
+    /// # Arguments
+    /// * `name` - Name of the field (after any `rename`)
+    fn field(name: &str) -> Option<&'static FieldMeta> {
+        Self::fields().iter().find(|f| f.name == name)
+    }
+
+    /// Returns the name and fields of the form together
+    fn form_info() -> FormInfo {
+        FormInfo {
+            name: Self::form_name(),
+            fields: Self::fields(),
+        }
+    }
+}
+
+/// Name and fields of a form, see [`FormMeta`]
+#[derive(Clone, Copy, Debug)]
+pub struct FormInfo {
+    /// Name of the struct (or enum) describing the form
+    pub name: &'static str,
+
+    /// Fields of the form, in declaration order
+    pub fields: &'static [FieldMeta],
+}
+
+/// Static description of a single field
+#[derive(Clone, Copy, Debug)]
+pub struct FieldMeta {
+    /// Name the field is submitted, rendered and reported under
+    pub name: &'static str,
+
+    /// Enum variant containing this field, None for structs
+    pub variant: Option<&'static str>,
+
+    /// Rust type of the field, as written in the struct
+    pub rust_type: &'static str,
+
+    /// Html input type used to render the field (e.g., `text`, `number`)
+    pub input_type: &'static str,
+
+    /// True if the field is an `Option<T>` and may be omitted
+    pub optional: bool,
+
+    /// Constraints applied to the field
+    pub constraints: &'static [Constraint],
+
+    /// Describes the field's type (or the `T` in `Option<T>`) if it is itself
+    /// a form, i.e. implements `FormMeta`.  Always returns None for types that
+    /// refer to the form's generic parameters
+    pub nested: fn() -> Option<FormInfo>,
+}
+
+impl FieldMeta {
+    /// Returns an iterator over the rules applied to this field
+    pub fn rules(&self) -> impl Iterator<Item = &'static Rule> {
+        self.constraints.iter().map(|c| &c.rule)
+    }
+
+    /// Returns true if the field must be submitted: it is not optional, or
+    /// it has the `required` rule
+    pub fn is_required(&self) -> bool {
+        !self.optional || self.rules().any(|r| *r == Rule::Required)
+    }
+}
+
+/// A rule applied to a field, along with the message and/or code supplied
+/// in the same attribute
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Constraint {
+    /// Rule the field must satisfy
+    pub rule: Rule,
+
+    /// Custom error message, if any
+    pub message: Option<&'static str>,
+
+    /// Custom error code, if any
+    pub code: Option<&'static str>,
+}
+
+impl Constraint {
+    /// Returns the error code reported when this constraint fails: the custom
+    /// code if one was supplied, otherwise the rule's default
+    pub fn code(&self) -> &'static str {
+        self.code.unwrap_or_else(|| self.rule.code())
+    }
+}
+
+/// Rules supported by the derives
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rule {
+    /// Minimum length of a string (`min_length`, `minlength`)
+    MinLength(usize),
+
+    /// Maximum length of a string (`max_length`, `maxlength`)
+    MaxLength(usize),
+
+    /// Minimum value of a number (`min_value`, `min`)
+    MinValue(f64),
+
+    /// Maximum value of a number (`max_value`, `max`)
+    MaxValue(f64),
+
+    /// Input must match a regular expression (`regex`, `pattern`)
+    Pattern(&'static str),
+
+    /// Input must be an email address
+    Email,
+
+    /// Input must be a (US) phone number
+    Phone,
+
+    /// Input must be an http(s) url
+    Url,
+
+    /// Input must equal the field with the given name (`validate_match`)
+    Matches(&'static str),
+
+    /// Field must be present and not empty
+    Required,
+
+    /// Input must contain something other than whitespace
+    NotBlank,
+
+    /// Input must be one of the listed values (`one_of`)
+    OneOf(&'static [&'static str]),
+}
+
+impl Rule {
+    /// Returns the error code reported when this rule fails.  These match the
+    /// codes used by `ValidateError`
+    pub fn code(&self) -> &'static str {
+        match self {
+            Rule::MinLength(_) => "input_too_short",
+            Rule::MaxLength(_) => "input_too_long",
+            Rule::MinValue(_) => "too_small",
+            Rule::MaxValue(_) => "too_large",
+            Rule::Pattern(_) => "invalid_regex",
+            Rule::Email => "invalid_email",
+            Rule::Phone => "invalid_phone_number",
+            Rule::Url => "invalid_url",
+            Rule::Matches(_) => "field_mismatch",
+            Rule::Required => "required",
+            Rule::NotBlank => "blank",
+            Rule::OneOf(_) => "not_one_of",
+        }
+    }
+}
+
+/// Support code for the derive macros, not public API.  Lets the derives
+/// describe fields whose type implements `FormMeta` without knowing the field
+/// types up front
+#[doc(hidden)]
+pub mod __private {
+    use super::{FormInfo, FormMeta};
+    use std::marker::PhantomData;
+
+    pub struct Probe<T: ?Sized>(pub PhantomData<T>);
+
+    pub trait ViaFormMeta {
+        fn webforms_form_info(&self) -> Option<FormInfo>;
+    }
+
+    impl<T: FormMeta + ?Sized> ViaFormMeta for &Probe<T> {
+        fn webforms_form_info(&self) -> Option<FormInfo> {
+            Some(T::form_info())
+        }
+    }
+
+    pub trait ViaNothing {
+        fn webforms_form_info(&self) -> Option<FormInfo>;
+    }
+
+    impl<T: ?Sized> ViaNothing for Probe<T> {
+        fn webforms_form_info(&self) -> Option<FormInfo> {
+            None
+        }
+    }
+}
+
+#[cfg(all(test, feature = "validate", feature = "html"))]
+mod tests {
+    use crate::html::HtmlForm;
+    use crate::meta::{FormMeta, Rule};
+    use crate::validate::{ValidateError, ValidateForm};
+    use lazy_static::lazy_static;
+    use regex::Regex;
+
+    #[derive(ValidateForm)]
+    #[form(rename_all = "camelCase")]
+    struct SignupForm {
+        #[validate(min_length = 3, max_length = 16)]
+        #[validate(not_blank, message = "Pick a username", code = "username")]
+        pub user_name: String,
+
+        #[form(rename = "mail", required, email)]
+        pub email: Option<String>,
+
+        #[validate(min_value = 18)]
+        pub age: u8,
+
+        #[validate_match(password)]
+        pub confirm: String,
+
+        pub password: String,
+    }
+
+    #[test]
+    fn test_validate_meta() {
+        assert_eq!(SignupForm::form_name(), "SignupForm");
+
+        let names: Vec<_> = SignupForm::fields().iter().map(|f| f.name).collect();
+        assert_eq!(names, ["userName", "mail", "age", "confirm", "password"]);
+
+        let user_name = SignupForm::field("userName").unwrap();
+        let rules: Vec<_> = user_name.rules().cloned().collect();
+        assert_eq!(
+            rules,
+            [Rule::MinLength(3), Rule::MaxLength(16), Rule::NotBlank]
+        );
+        assert_eq!(user_name.constraints[0].message, None);
+        assert_eq!(user_name.constraints[2].message, Some("Pick a username"));
+        assert_eq!(user_name.constraints[2].code(), "username");
+        assert_eq!(user_name.constraints[1].code(), "input_too_long");
+
+        let email = SignupForm::field("mail").unwrap();
+        assert_eq!(email.rust_type, "Option<String>");
+        assert_eq!(email.input_type, "email");
+        assert!(email.optional);
+        assert!(email.is_required());
+
+        let age = SignupForm::field("age").unwrap();
+        assert_eq!(age.input_type, "number");
+        assert_eq!(age.constraints[0].rule, Rule::MinValue(18.0));
+
+        let confirm = SignupForm::field("confirm").unwrap();
+        assert_eq!(confirm.constraints[0].rule, Rule::Matches("password"));
+
+        assert!(SignupForm::field("user_name").is_none());
+    }
+
+    #[derive(HtmlForm)]
+    struct SearchForm {
+        #[html_validate(minlength = 2, pattern = "[a-z]+")]
+        #[html_error(minlength = "Type some more")]
+        pub query: String,
+
+        #[html_input(search)]
+        pub filter: Option<String>,
+
+        #[html_validate(min = 0.5)]
+        pub boost: f64,
+    }
+
+    #[test]
+    fn test_html_meta() {
+        let query = SearchForm::field("query").unwrap();
+        assert_eq!(query.constraints[0].rule, Rule::MinLength(2));
+        assert_eq!(query.constraints[0].message, Some("Type some more"));
+        assert_eq!(query.constraints[1].rule, Rule::Pattern("[a-z]+"));
+
+        assert_eq!(SearchForm::field("filter").unwrap().input_type, "search");
+        assert!(!SearchForm::field("filter").unwrap().is_required());
+
+        let boost = SearchForm::field("boost").unwrap();
+        assert_eq!(boost.rust_type, "f64");
+        assert_eq!(boost.constraints[0].rule, Rule::MinValue(0.5));
+    }
+
+    // Deriving both traits implements FormMeta once, combining their rules
+    #[derive(ValidateForm, HtmlForm)]
+    struct ProfileForm {
+        #[form(min_length = 2)]
+        #[html_validate(minlength = 2, maxlength = 32)]
+        pub name: String,
+    }
+
+    #[test]
+    fn test_combined_meta() {
+        let name = ProfileForm::field("name").unwrap();
+        let rules: Vec<_> = name.rules().cloned().collect();
+        assert_eq!(rules, [Rule::MinLength(2), Rule::MaxLength(32)]);
+    }
+
+    // Shared rules only, so a bare #[validate_config] picks the derive
+    // implementing FormMeta
+    #[derive(ValidateForm, HtmlForm)]
+    #[validate_config]
+    struct ContactForm {
+        #[form(email)]
+        pub email: String,
+    }
+
+    #[test]
+    fn test_marked_meta() {
+        let email = ContactForm::field("email").unwrap();
+        assert_eq!(email.input_type, "email");
+        assert_eq!(email.constraints[0].rule, Rule::Email);
+    }
+
+    #[allow(dead_code)]
+    #[derive(ValidateForm)]
+    enum Payment {
+        Card {
+            #[validate(min_length = 16)]
+            number: String,
+        },
+        Invoice(#[validate(email)] String),
+    }
+
+    #[test]
+    fn test_enum_meta() {
+        let fields = Payment::fields();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].name, "number");
+        assert_eq!(fields[0].variant, Some("Card"));
+        assert_eq!(fields[1].name, "0");
+        assert_eq!(fields[1].variant, Some("Invoice"));
+        assert_eq!(fields[1].constraints[0].rule, Rule::Email);
+        assert_eq!(fields[1].input_type, "email");
+    }
+}
@@ -0,0 +1,254 @@
+//! Generates OpenAPI 3.1 components describing forms
+//!
+//! OpenAPI 3.1 schemas are JSON Schema (draft 2020-12), so each form is
+//! described with the same schema as `json_schema::JsonSchema`, including the
+//! constraints from `#[validate]`, `#[form]` and `#[html_validate]`.  For
+//! every form, the generated components contain:
+//!
+//! * `schemas` - the form and every form nested in it
+//! * `requestBodies` - an `application/x-www-form-urlencoded` body referencing
+//!   the form's schema, with an encoding object for each field (nested forms
+//!   use the `deepObject` style)
+//!
+//! Along with a standard validation error response, `responses.ValidationError`,
+//! describing the RFC 7807 documents built by `webforms::problem`
+//! (`schemas.ValidationProblem` and `schemas.InvalidParam`).
+//!
+//! Requires the `openapi` feature.
+//!
+//! # Example
+//!
+//! ```
+//! # #[cfg(feature = "validate")]
+//! # fn main() {
+//! use webforms::openapi::OpenApi;
+//! use webforms::validate::{ValidateError, ValidateForm};
+//!
+//! #[derive(ValidateForm)]
+//! struct LoginForm {
+//!     #[validate(min_length = 4)]
+//!     pub username: String,
+//! }
+//!
+//! let components = LoginForm::openapi_components();
+//! let body = &components["requestBodies"]["LoginForm"];
+//! assert_eq!(
+//!     body["content"]["application/x-www-form-urlencoded"]["schema"]["$ref"],
+//!     "#/components/schemas/LoginForm"
+//! );
+//! assert_eq!(components["schemas"]["LoginForm"]["properties"]["username"]["minLength"], 4);
+//! # }
+//! # #[cfg(not(feature = "validate"))]
+//! # fn main() {}
+//! ```
+//!
+//! Components for several forms are generated together with [`components`]:
+//!
+//! ```ignore
+//! let components = webforms::openapi::components(&[LoginForm::form_info(), SignupForm::form_info()]);
+//! ```
+
+use crate::json_schema::{schemas, value_type};
+use crate::meta::{FormInfo, FormMeta};
+use serde_json::{json, Map, Value};
+
+/// Content type of the generated request bodies
+pub const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+/// Location of the schemas in an OpenAPI document
+const SCHEMA_PREFIX: &str = "#/components/schemas/";
+
+/// Generates the OpenAPI components describing a form.  Implemented for every
+/// type implementing `FormMeta`
+pub trait OpenApi: FormMeta {
+    /// Returns the `components` object describing this form, see the module
+    /// documentation
+    fn openapi_components() -> Value {
+        components(&[Self::form_info()])
+    }
+}
+
+impl<T: FormMeta> OpenApi for T {}
+
+/// Returns the `components` object describing each of the given forms
+///
+/// # Arguments
+/// * `forms` - Forms to describe
+pub fn components(forms: &[FormInfo]) -> Value {
+    let mut schema_map = Map::new();
+    let mut bodies = Map::new();
+
+    for form in forms {
+        schema_map.append(&mut schemas(*form, SCHEMA_PREFIX));
+        bodies.insert(form.name.to_owned(), request_body(*form));
+    }
+
+    schema_map.insert("ValidationProblem".to_owned(), problem_schema());
+    schema_map.insert("InvalidParam".to_owned(), invalid_param_schema());
+
+    json!({
+        "schemas": schema_map,
+        "requestBodies": bodies,
+        "responses": {
+            "ValidationError": validation_error_response(),
+        },
+    })
+}
+
+/// Returns the urlencoded request body submitting a form
+///
+/// # Arguments
+/// * `form` - Form to describe
+pub fn request_body(form: FormInfo) -> Value {
+    let mut encoding = Map::new();
+    for field in form.fields {
+        let (style, explode) = match (field.nested)() {
+            Some(_) => ("deepObject", true),
+            None => ("form", value_type(field) == "array"),
+        };
+
+        encoding.insert(
+            field.name.to_owned(),
+            json!({ "style": style, "explode": explode }),
+        );
+    }
+
+    json!({
+        "required": true,
+        "content": {
+            FORM_CONTENT_TYPE: {
+                "schema": { "$ref": format!("{}{}", SCHEMA_PREFIX, form.name) },
+                "encoding": encoding,
+            },
+        },
+    })
+}
+
+/// Returns the `400 Bad Request` response returned when a form fails
+/// validation
+pub fn validation_error_response() -> Value {
+    json!({
+        "description": "The submitted form failed validation",
+        "content": {
+            "application/problem+json": {
+                "schema": { "$ref": format!("{}ValidationProblem", SCHEMA_PREFIX) },
+            },
+        },
+    })
+}
+
+/// Schema of the RFC 7807 document built by `problem::Problem::validation`
+fn problem_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "type": { "type": "string", "format": "uri-reference" },
+            "title": { "type": "string" },
+            "status": { "type": "integer" },
+            "detail": { "type": "string" },
+            "instance": { "type": "string", "format": "uri-reference" },
+            "invalid-params": {
+                "type": "array",
+                "items": { "$ref": format!("{}InvalidParam", SCHEMA_PREFIX) },
+            },
+        },
+        "required": ["type", "title", "status", "invalid-params"],
+    })
+}
+
+/// Schema of a single entry of `invalid-params`, see `problem::InvalidParam`
+fn invalid_param_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string" },
+            "reason": { "type": "string" },
+            "code": { "type": "string" },
+        },
+        "required": ["name", "reason", "code"],
+    })
+}
+
+#[cfg(all(test, feature = "validate", feature = "html"))]
+mod tests {
+    use crate::html::HtmlForm;
+    use crate::meta::FormMeta;
+    use crate::openapi::{components, OpenApi, FORM_CONTENT_TYPE};
+    use crate::validate::{ValidateError, ValidateForm};
+    use serde_json::json;
+
+    #[derive(ValidateForm)]
+    struct Address {
+        #[validate(max_length = 64)]
+        pub street: String,
+    }
+
+    #[derive(ValidateForm)]
+    struct CheckoutForm {
+        #[validate(min_value = 1)]
+        pub quantity: u32,
+
+        pub tags: Vec<String>,
+
+        pub shipping: Address,
+    }
+
+    #[test]
+    fn test_openapi_components() {
+        let components = CheckoutForm::openapi_components();
+
+        let schemas = &components["schemas"];
+        assert_eq!(
+            schemas["CheckoutForm"]["properties"]["quantity"]["minimum"],
+            1
+        );
+        assert_eq!(
+            schemas["CheckoutForm"]["properties"]["shipping"]["$ref"],
+            "#/components/schemas/Address"
+        );
+        assert_eq!(schemas["Address"]["properties"]["street"]["maxLength"], 64);
+        assert_eq!(
+            schemas["ValidationProblem"]["properties"]["invalid-params"]["items"]["$ref"],
+            "#/components/schemas/InvalidParam"
+        );
+
+        let content = &components["requestBodies"]["CheckoutForm"]["content"][FORM_CONTENT_TYPE];
+        assert_eq!(
+            content["schema"]["$ref"],
+            "#/components/schemas/CheckoutForm"
+        );
+        assert_eq!(
+            content["encoding"],
+            json!({
+                "quantity": { "style": "form", "explode": false },
+                "tags": { "style": "form", "explode": true },
+                "shipping": { "style": "deepObject", "explode": true },
+            })
+        );
+
+        assert_eq!(
+            components["responses"]["ValidationError"]["content"]["application/problem+json"]
+                ["schema"]["$ref"],
+            "#/components/schemas/ValidationProblem"
+        );
+    }
+
+    #[derive(HtmlForm)]
+    struct SearchForm {
+        #[html_validate(minlength = 2)]
+        pub query: String,
+    }
+
+    #[test]
+    fn test_multiple_forms() {
+        let components = components(&[CheckoutForm::form_info(), SearchForm::form_info()]);
+
+        assert_eq!(
+            components["schemas"]["SearchForm"]["properties"]["query"]["minLength"],
+            2
+        );
+        assert!(components["schemas"]["Address"].is_object());
+        assert!(components["requestBodies"]["CheckoutForm"].is_object());
+        assert!(components["requestBodies"]["SearchForm"].is_object());
+    }
+}
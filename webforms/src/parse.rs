@@ -0,0 +1,1302 @@
+//! Parses submitted forms into typed structs
+//!
+//! `#[derive(FromForm)]` implements [`FromForm`], reading each field from the
+//! submitted values with the same name (the field's ident, or the name set by
+//! `#[form(rename = "...")]` / `#[form(rename_all = "...")]`).  Fields are
+//! converted with [`FromFormField`], implemented for:
+//!
+//! | type | when not submitted | notes |
+//! | ---- | ------------------ | ----- |
+//! | String | `missing` error | |
+//! | Integers and floats | `missing` error | Blank values are missing, invalid values report `invalid_value` |
+//! | bool | `false` | Checkboxes: any value other than `false`, `off`, `0` or an empty string is `true` |
+//! | char | `missing` error | |
+//! | `Option<T>` | `None` | Empty values are `None`, see below |
+//! | `Vec<T>` | empty | One element per submitted value |
+//! | [`UploadedFile`] | `missing` error | Requires a `multipart/form-data` body |
+//!
+//! The types in `webforms::types` implement `FromFormField` as well,
+//! reporting their validation error under the field's name.  Other types
+//! implementing `FromStr` are parsed from the value when used directly (not
+//! inside an `Option` or `Vec`): when `FromStr::Err` is a `ValidateError` that
+//! error is reported under the field's name, otherwise `invalid_value`.
+//! When a field is submitted more than once, fields that hold a single value
+//! use the last one, so a checkbox can follow a hidden field holding its
+//! unchecked value.
+//!
+//! Browsers submit empty text inputs as empty strings.  With
+//! `#[form(empty_as_none)]` empty values are treated as if the field was not
+//! submitted at all, and with `#[form(keep_empty)]` they are parsed like any
+//! other value.  `Option<T>` fields default to `empty_as_none` (an empty input
+//! becomes `None`) and all other fields to `keep_empty` (an empty input
+//! becomes an empty `String`).  Either attribute may be applied to the struct,
+//! changing the default for every field.
+//!
+//! Structs deriving `FromForm` can be nested: an `address: Address` field is
+//! read from `address[street]`, `address[city]` and so on, and an
+//! `items: Vec<LineItem>` field from `items[0][sku]`, `items[1][sku]`, in
+//! order of index.  Dotted names (`items.0.sku`) are read the same way, and
+//! `Vec` fields of other types also accept `tags[]` and `tags[0]`.  Errors in
+//! a nested form are reported under their path from the field holding it
+//! (e.g., `address.city` or `items.0.sku`).
+//!
+//! `FormData::from_urlencoded` trusts the body it is given.  Submissions from
+//! the internet should be decoded with [`FormData::from_urlencoded_with`] (as
+//! `FromForm::from_urlencoded` does with the default limits) or checked with
+//! [`FormData::enforce`] after `from_multipart`, which reject those exceeding
+//! [`ParseLimits`]:
+//! too many fields, names or values that are too long, names nested too deep
+//! or with too large an index, and, depending on the [`Duplicates`] policy,
+//! names submitted more than once.
+//!
+//! Forms that upload files are submitted as `multipart/form-data`.
+//! [`FormData::from_multipart`] reads such a body in chunks, enforcing the
+//! part count and size limits in [`MultipartLimits`], and keeps large files in
+//! temporary files rather than memory.
+//!
+//! [`FromForm::from_urlencoded`] and [`parse_valid`] enforce the default
+//! limits, and their `_with` variants the limits given.  Both return a
+//! [`ParseError`]: a violated limit, or `ParseError::Invalid` holding a
+//! `ValidateError` for each field that is missing or can't be parsed, so they
+//! can be handled (and serialized or localized) like validation errors.
+//! `parse_valid` also validates the result, returning its errors the same
+//! way.
+//!
+//! # Example
+//!
+//! ```
+//! use lazy_static::lazy_static;
+//! use regex::Regex;
+//! use webforms::parse::FromForm;
+//! use webforms::validate::{ValidateError, ValidateForm};
+//!
+//! #[derive(Debug, FromForm, ValidateForm)]
+//! struct SignupForm {
+//!     #[validate(email)]
+//!     pub email: String,
+//!
+//!     #[validate(min_value = 18)]
+//!     pub age: u8,
+//!
+//!     pub nickname: Option<String>,
+//!
+//!     pub newsletter: bool,
+//! }
+//!
+//! let form = SignupForm::from_urlencoded("email=mike%40test.com&age=30&nickname=").unwrap();
+//! assert_eq!(form.email, "mike@test.com");
+//! assert_eq!(form.nickname, None);
+//! assert!(!form.newsletter);
+//!
+//! let error = SignupForm::from_urlencoded("email=mike%40test.com&age=old").unwrap_err();
+//! assert_eq!(error.errors()[0].code(), "invalid_value");
+//!
+//! let error = webforms::parse::parse_valid::<SignupForm, _>("email=mike&age=12").unwrap_err();
+//! assert_eq!(error.errors().len(), 2);
+//! ```
+
+use crate::validate::{ValidateError, ValidateForm};
+use std::iter::FromIterator;
+use std::str::FromStr;
+
+mod file;
+mod limits;
+mod multipart;
+
+pub use self::file::FileRule;
+pub use self::limits::{Duplicates, ParseError, ParseLimits};
+pub use self::multipart::{MultipartError, MultipartLimits, UploadedFile};
+
+// Import and re-export the macro
+pub use webforms_derive::FromForm;
+
+/// Builds a struct from a submitted form.  Usually derived
+pub trait FromForm: Sized {
+    /// Reads every field from the submitted values, returning an error for
+    /// each field that is missing or can't be parsed
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Submitted values
+    fn from_form(data: &FormData) -> Result<Self, Vec<ValidateError>>;
+
+    /// Parses an `application/x-www-form-urlencoded` body, enforcing the
+    /// default `ParseLimits`
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - Request body (or query string, without the leading `?`)
+    fn from_urlencoded<B: AsRef<[u8]>>(body: B) -> Result<Self, ParseError> {
+        Self::from_urlencoded_with(body, &ParseLimits::default())
+    }
+
+    /// Parses an `application/x-www-form-urlencoded` body, enforcing limits
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - Request body (or query string, without the leading `?`)
+    /// * `limits` - Limits to enforce
+    fn from_urlencoded_with<B: AsRef<[u8]>>(
+        body: B,
+        limits: &ParseLimits,
+    ) -> Result<Self, ParseError> {
+        let data = FormData::from_urlencoded_with(body, limits)?;
+        Ok(Self::from_form(&data)?)
+    }
+}
+
+/// Parses an `application/x-www-form-urlencoded` body, enforcing the default
+/// `ParseLimits`, and validates the result.  Returns the parse errors if the
+/// body can't be parsed, otherwise the validation errors
+///
+/// # Arguments
+///
+/// * `body` - Request body
+pub fn parse_valid<F, B>(body: B) -> Result<F, ParseError>
+where
+    F: FromForm + ValidateForm,
+    B: AsRef<[u8]>,
+{
+    parse_valid_with(body, &ParseLimits::default())
+}
+
+/// Parses an `application/x-www-form-urlencoded` body, enforcing limits, and
+/// validates the result (see `parse_valid`)
+///
+/// # Arguments
+///
+/// * `body` - Request body
+/// * `limits` - Limits to enforce
+pub fn parse_valid_with<F, B>(body: B, limits: &ParseLimits) -> Result<F, ParseError>
+where
+    F: FromForm + ValidateForm,
+    B: AsRef<[u8]>,
+{
+    let form = F::from_urlencoded_with(body, limits)?;
+    form.validate()?;
+    Ok(form)
+}
+
+/// Names and values of a submitted form, in the order they were submitted,
+/// along with any uploaded files
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FormData {
+    fields: Vec<(String, String)>,
+    files: Vec<(String, UploadedFile)>,
+
+    // Path of each field and file (see `key_path`), parsed once when it is
+    // added since every lookup compares them
+    field_paths: Vec<Vec<String>>,
+    file_paths: Vec<Vec<String>>,
+}
+
+impl FormData {
+    /// Creates an empty submission
+    pub fn new() -> FormData {
+        FormData {
+            fields: vec![],
+            files: vec![],
+            field_paths: vec![],
+            file_paths: vec![],
+        }
+    }
+
+    /// Decodes an `application/x-www-form-urlencoded` body.  `+` is decoded
+    /// as a space, malformed percent escapes are kept as is and invalid UTF-8
+    /// is replaced with U+FFFD
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - Request body (or query string, without the leading `?`)
+    pub fn from_urlencoded<B: AsRef<[u8]>>(body: B) -> FormData {
+        urlencoded_pairs(body.as_ref()).collect()
+    }
+
+    /// Adds a value for a field
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the field
+    /// * `value` - Submitted value
+    pub fn push<S: Into<String>, V: Into<String>>(&mut self, name: S, value: V) {
+        let name = name.into();
+        self.field_paths.push(owned_path(&name));
+        self.fields.push((name, value.into()));
+    }
+
+    /// Adds an uploaded file for a field
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the field
+    /// * `file` - Uploaded file
+    pub fn push_file<S: Into<String>>(&mut self, name: S, file: UploadedFile) {
+        let name = name.into();
+        self.file_paths.push(owned_path(&name));
+        self.files.push((name, file));
+    }
+
+    /// Returns the first value submitted for a field
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the field
+    pub fn get<S: AsRef<str>>(&self, name: S) -> Option<&str> {
+        self.get_all(name).into_iter().next()
+    }
+
+    /// Returns every value submitted for a field, in order.  Names are
+    /// compared after parsing (`a[b]` and `a.b` are the same field) and
+    /// values submitted as `name[]` are included
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the field
+    pub fn get_all<S: AsRef<str>>(&self, name: S) -> Vec<&str> {
+        let path = key_path(name.as_ref());
+        self.fields
+            .iter()
+            .zip(&self.field_paths)
+            .filter(|(_, other)| names_field(&path, other))
+            .map(|((_, v), _)| v.as_str())
+            .collect()
+    }
+
+    /// Returns the first file uploaded for a field
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the field
+    pub fn get_file<S: AsRef<str>>(&self, name: S) -> Option<&UploadedFile> {
+        self.get_all_files(name).into_iter().next()
+    }
+
+    /// Returns every file uploaded for a field, in order
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the field
+    pub fn get_all_files<S: AsRef<str>>(&self, name: S) -> Vec<&UploadedFile> {
+        let path = key_path(name.as_ref());
+        self.files
+            .iter()
+            .zip(&self.file_paths)
+            .filter(|(_, other)| names_field(&path, other))
+            .map(|((_, f), _)| f)
+            .collect()
+    }
+
+    /// Returns the values and files submitted under a prefix, named relative
+    /// to it.  For example, with the prefix `items[0]`, `items[0][sku]` and
+    /// `items.0.sku` are both returned as `sku`
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - Name of the nested form (e.g., `address` or `items[0]`)
+    pub fn nested<S: AsRef<str>>(&self, prefix: S) -> FormData {
+        let prefix = key_path(prefix.as_ref());
+        let mut data = FormData::new();
+
+        for ((_, value), path) in self.fields.iter().zip(&self.field_paths) {
+            if let Some(path) = relative_path(&prefix, path) {
+                data.fields.push((key_name(path), value.clone()));
+                data.field_paths.push(path.to_vec());
+            }
+        }
+        for ((_, file), path) in self.files.iter().zip(&self.file_paths) {
+            if let Some(path) = relative_path(&prefix, path) {
+                data.files.push((key_name(path), file.clone()));
+                data.file_paths.push(path.to_vec());
+            }
+        }
+
+        data
+    }
+
+    /// Returns the indices submitted under a prefix (e.g., 0 and 2 for
+    /// `items[0][sku]` and `items.2.sku` with the prefix `items`), in order
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - Name of the repeated field
+    pub fn indices<S: AsRef<str>>(&self, prefix: S) -> Vec<usize> {
+        let prefix = key_path(prefix.as_ref());
+        let mut indices: Vec<usize> = self
+            .paths()
+            .filter_map(|path| relative_path(&prefix, path))
+            .filter_map(|path| parse_index(&path[0]))
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    /// Returns true if any value or file was submitted under a prefix (see
+    /// `nested`)
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - Name of the nested form
+    pub fn has_nested<S: AsRef<str>>(&self, prefix: S) -> bool {
+        let prefix = key_path(prefix.as_ref());
+        self.paths()
+            .any(|path| relative_path(&prefix, path).is_some())
+    }
+
+    /// Returns the path of every value and file
+    fn paths(&self) -> impl Iterator<Item = &Vec<String>> {
+        self.field_paths.iter().chain(&self.file_paths)
+    }
+
+    /// Returns every name and value, in the order they were submitted
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+
+    /// Returns every name and uploaded file, in the order they were submitted
+    pub fn files(&self) -> &[(String, UploadedFile)] {
+        &self.files
+    }
+
+    /// Returns the number of values and files submitted
+    pub fn len(&self) -> usize {
+        self.fields.len() + self.files.len()
+    }
+
+    /// Returns true if nothing was submitted
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.files.is_empty()
+    }
+}
+
+impl<S: Into<String>, V: Into<String>> FromIterator<(S, V)> for FormData {
+    fn from_iter<I: IntoIterator<Item = (S, V)>>(iter: I) -> FormData {
+        let mut data = FormData::new();
+        for (name, value) in iter {
+            data.push(name, value);
+        }
+        data
+    }
+}
+
+/// How empty submitted values are treated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Empty {
+    /// Empty values are treated as if the field was not submitted
+    /// (`#[form(empty_as_none)]`)
+    AsNone,
+
+    /// Empty values are parsed like any other value (`#[form(keep_empty)]`)
+    Keep,
+}
+
+/// The values submitted for a single field, passed to [`FromFormField`]
+#[derive(Clone, Debug)]
+pub struct Field<'a> {
+    name: &'static str,
+    data: &'a FormData,
+    key: Option<String>,
+    values: Vec<&'a str>,
+    files: Vec<&'a UploadedFile>,
+    empty: Option<Empty>,
+}
+
+impl<'a> Field<'a> {
+    /// Collects the values and files submitted for a field
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Submitted values
+    /// * `name` - Name of the field
+    /// * `empty` - How empty values are treated, or None for the type's default
+    pub fn new(data: &'a FormData, name: &'static str, empty: Option<Empty>) -> Field<'a> {
+        Field::at(data, name, name.to_owned(), empty)
+    }
+
+    /// Collects the values and files submitted under a key, reporting errors
+    /// under `name`
+    fn at(data: &'a FormData, name: &'static str, key: String, empty: Option<Empty>) -> Field<'a> {
+        Field {
+            name,
+            data,
+            values: data.get_all(&key),
+            files: data.get_all_files(&key),
+            key: Some(key),
+            empty,
+        }
+    }
+
+    /// Returns the name of the field
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns how empty values are treated, or None if the field didn't set
+    /// a policy
+    pub fn empty(&self) -> Option<Empty> {
+        self.empty
+    }
+
+    /// Returns a copy of this field with a different empty value policy
+    ///
+    /// # Arguments
+    ///
+    /// * `empty` - How empty values are treated
+    pub fn with_empty(&self, empty: Empty) -> Field<'a> {
+        Field {
+            empty: Some(empty),
+            ..self.clone()
+        }
+    }
+
+    /// Returns a field holding a single one of this field's values
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Value held by the new field
+    pub fn with_value(&self, value: &'a str) -> Field<'a> {
+        Field {
+            key: None,
+            values: vec![value],
+            files: vec![],
+            ..self.clone()
+        }
+    }
+
+    /// Returns a field holding a single one of this field's files
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - File held by the new field
+    pub fn with_file(&self, file: &'a UploadedFile) -> Field<'a> {
+        Field {
+            key: None,
+            values: vec![],
+            files: vec![file],
+            ..self.clone()
+        }
+    }
+
+    /// Returns every value submitted, without empty values if they are
+    /// treated as missing.  Empty values are kept unless the field's policy
+    /// is `Empty::AsNone`
+    pub fn values(&self) -> Vec<&'a str> {
+        match self.empty {
+            Some(Empty::AsNone) => self
+                .values
+                .iter()
+                .cloned()
+                .filter(|v| !v.is_empty())
+                .collect(),
+            _ => self.values.clone(),
+        }
+    }
+
+    /// Returns the last value submitted (see `values`)
+    pub fn value(&self) -> Option<&'a str> {
+        self.values().pop()
+    }
+
+    /// Returns every file uploaded.  Browsers send an empty file when none
+    /// was chosen, which is dropped while parsing
+    pub fn files(&self) -> Vec<&'a UploadedFile> {
+        self.files.clone()
+    }
+
+    /// Returns the last file uploaded
+    pub fn file(&self) -> Option<&'a UploadedFile> {
+        self.files.last().cloned()
+    }
+
+    /// Returns the indices submitted for this field (e.g., `items[0]` and
+    /// `items[1]`), in order
+    pub fn indices(&self) -> Vec<usize> {
+        match self.key {
+            Some(ref key) => self.data.indices(key),
+            None => vec![],
+        }
+    }
+
+    /// Returns the field holding the values submitted under an index.  Its
+    /// errors are reported under this field's name
+    ///
+    /// # Arguments
+    ///
+    /// * `idx` - Index of the value (see `indices`)
+    pub fn index(&self, idx: usize) -> Field<'a> {
+        match self.key {
+            Some(ref key) => Field::at(
+                self.data,
+                self.name,
+                format!("{}[{}]", key, idx),
+                self.empty,
+            ),
+            None => Field {
+                values: vec![],
+                files: vec![],
+                ..self.clone()
+            },
+        }
+    }
+
+    /// Returns the values and files submitted under this field (e.g.,
+    /// `address[city]`), named relative to it.  Used to parse nested forms
+    pub fn nested(&self) -> FormData {
+        match self.key {
+            Some(ref key) => self.data.nested(key),
+            None => FormData::new(),
+        }
+    }
+
+    /// Returns the path errors of a form nested in this field are reported
+    /// under (e.g., `items.0` for `items[0]`)
+    pub fn path(&self) -> String {
+        match self.key {
+            Some(ref key) => key_path(key).join("."),
+            None => self.name.to_owned(),
+        }
+    }
+
+    /// Returns the first of the errors reported for this field, or `missing`
+    /// if there are none
+    ///
+    /// # Arguments
+    ///
+    /// * `errors` - Errors reported while parsing the field
+    pub fn first_error(&self, errors: Vec<ValidateError>) -> ValidateError {
+        errors.into_iter().next().unwrap_or_else(|| self.missing())
+    }
+
+    /// Returns true if any value or file was submitted under this field
+    pub fn has_nested(&self) -> bool {
+        match self.key {
+            Some(ref key) => self.data.has_nested(key),
+            None => false,
+        }
+    }
+
+    /// Returns the error reported when this field wasn't submitted
+    pub fn missing(&self) -> ValidateError {
+        ValidateError::Missing { field: self.name }
+    }
+
+    /// Returns the error reported when this field's value can't be parsed
+    ///
+    /// # Arguments
+    ///
+    /// * `expected` - Description of the value expected (e.g., "an integer")
+    pub fn invalid(&self, expected: &'static str) -> ValidateError {
+        ValidateError::InvalidValue {
+            field: self.name,
+            expected,
+        }
+    }
+
+    /// Parses the field's value with `FromStr`, reporting the parse error
+    /// under this field's name.  Useful to implement `FromFormField` for types
+    /// that check their input in `FromStr`
+    pub fn parse<T: FromStr<Err = ValidateError>>(&self) -> Result<T, ValidateError> {
+        let value = self.value().ok_or_else(|| self.missing())?;
+        value
+            .parse()
+            .map_err(|e: ValidateError| e.with_field(self.name))
+    }
+}
+
+/// A type a submitted field can be parsed into
+pub trait FromFormField: Sized {
+    /// Parses the values submitted for a field
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - Values submitted for the field
+    fn from_field(field: &Field) -> Result<Self, ValidateError>;
+
+    /// Parses the values submitted for a field, returning every error found.
+    /// Nested forms report each of their errors under its path from the
+    /// field (e.g., `address.city` or `items.0.sku`).  Defaults to the error
+    /// returned by `from_field`
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - Values submitted for the field
+    fn from_field_all(field: &Field) -> Result<Self, Vec<ValidateError>> {
+        Self::from_field(field).map_err(|error| vec![error])
+    }
+}
+
+impl FromFormField for String {
+    fn from_field(field: &Field) -> Result<Self, ValidateError> {
+        match field.value() {
+            Some(value) => Ok(value.to_owned()),
+            None => Err(field.missing()),
+        }
+    }
+}
+
+impl FromFormField for bool {
+    fn from_field(field: &Field) -> Result<Self, ValidateError> {
+        Ok(match field.value() {
+            Some(value) => !["", "0", "false", "off"]
+                .iter()
+                .any(|v| value.eq_ignore_ascii_case(v)),
+            None => false,
+        })
+    }
+}
+
+impl FromFormField for char {
+    fn from_field(field: &Field) -> Result<Self, ValidateError> {
+        let value = field.value().ok_or_else(|| field.missing())?;
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            (None, _) => Err(field.missing()),
+            _ => Err(field.invalid("a single character")),
+        }
+    }
+}
+
+/// Implements FromFormField for numbers.  `$finite` rejects values `FromStr`
+/// accepts but no number input submits (e.g., `NaN`)
+macro_rules! number_from_field {
+    ($expected:expr, $finite:expr, $($ty:ty),*) => {
+        $(
+            impl FromFormField for $ty {
+                fn from_field(field: &Field) -> Result<Self, ValidateError> {
+                    match field.value().map(str::trim) {
+                        None | Some("") => Err(field.missing()),
+                        Some(value) => value
+                            .parse::<$ty>()
+                            .ok()
+                            .filter($finite)
+                            .ok_or_else(|| field.invalid($expected)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+number_from_field!("an integer", |_| true, i8, i16, i32, i64, i128, isize);
+number_from_field!(
+    "a non-negative integer",
+    |_| true,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize
+);
+number_from_field!("a number", |v| v.is_finite(), f32, f64);
+
+impl<T: FromFormField> FromFormField for Option<T> {
+    fn from_field(field: &Field) -> Result<Self, ValidateError> {
+        Self::from_field_all(field).map_err(|errors| field.first_error(errors))
+    }
+
+    fn from_field_all(field: &Field) -> Result<Self, Vec<ValidateError>> {
+        let field = field.with_empty(field.empty().unwrap_or(Empty::AsNone));
+        match field.values().is_empty() && field.files().is_empty() && !field.has_nested() {
+            true => Ok(None),
+            false => T::from_field_all(&field.with_empty(Empty::Keep)).map(Some),
+        }
+    }
+}
+
+impl<T: FromFormField> FromFormField for Vec<T> {
+    fn from_field(field: &Field) -> Result<Self, ValidateError> {
+        Self::from_field_all(field).map_err(|errors| field.first_error(errors))
+    }
+
+    /// Parses every element, reporting the errors of all of them
+    fn from_field_all(field: &Field) -> Result<Self, Vec<ValidateError>> {
+        let values = field.values().into_iter().map(|v| field.with_value(v));
+        let files = field.files().into_iter().map(|f| field.with_file(f));
+        let indexed = field.indices().into_iter().map(|idx| field.index(idx));
+
+        let mut elements = vec![];
+        let mut errors = vec![];
+        for field in values.chain(files).chain(indexed) {
+            match T::from_field_all(&field) {
+                Ok(element) => elements.push(element),
+                Err(e) => errors.extend(e),
+            }
+        }
+
+        match errors.is_empty() {
+            true => Ok(elements),
+            false => Err(errors),
+        }
+    }
+}
+
+impl FromFormField for UploadedFile {
+    fn from_field(field: &Field) -> Result<Self, ValidateError> {
+        match field.file() {
+            Some(file) => Ok(file.clone()),
+            None if field.values().is_empty() => Err(field.missing()),
+            None => Err(field.invalid("a file")),
+        }
+    }
+}
+
+/// Decodes the names and values of an `application/x-www-form-urlencoded`
+/// body, one pair at a time
+///
+/// # Arguments
+///
+/// * `body` - Request body
+fn urlencoded_pairs(body: &[u8]) -> impl Iterator<Item = (String, String)> + '_ {
+    body.split(|b| *b == b'&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.iter().position(|b| *b == b'=') {
+            Some(idx) => (decode(&pair[..idx]), decode(&pair[idx + 1..])),
+            None => (decode(pair), String::new()),
+        })
+}
+
+/// Splits a field's name into its segments, accepting brackets and dots
+/// (`items[0][sku]` and `items.0.sku` are both `items`, `0`, `sku`).  Names
+/// that don't follow either notation are a single segment
+///
+/// # Arguments
+///
+/// * `key` - Name of the field
+fn key_path(key: &str) -> Vec<&str> {
+    let split = |s: &str| s.find(['[', '.']).unwrap_or(s.len());
+
+    let end = split(key);
+    if end == 0 {
+        return vec![key];
+    }
+
+    let mut path = vec![&key[..end]];
+    let mut rest = &key[end..];
+    while !rest.is_empty() {
+        if rest.starts_with('[') {
+            match rest.find(']') {
+                Some(close) => {
+                    path.push(&rest[1..close]);
+                    rest = &rest[close + 1..];
+                }
+                None => return vec![key],
+            }
+        } else if rest.starts_with('.') {
+            let end = split(&rest[1..]) + 1;
+            if end == 1 {
+                return vec![key];
+            }
+            path.push(&rest[1..end]);
+            rest = &rest[end..];
+        } else {
+            return vec![key];
+        }
+    }
+    path
+}
+
+/// Splits a field's name into owned segments (see `key_path`)
+///
+/// # Arguments
+///
+/// * `key` - Name of the field
+fn owned_path(key: &str) -> Vec<String> {
+    key_path(key).into_iter().map(str::to_owned).collect()
+}
+
+/// Joins segments into a name using brackets (e.g., `items[0][sku]`)
+///
+/// # Arguments
+///
+/// * `path` - Segments of the name
+fn key_name<S: AsRef<str>>(path: &[S]) -> String {
+    let mut name = path[0].as_ref().to_owned();
+    for segment in &path[1..] {
+        name.push('[');
+        name.push_str(segment.as_ref());
+        name.push(']');
+    }
+    name
+}
+
+/// Returns true if a submitted name holds a value of a field: the same path,
+/// or the path followed by `[]`
+///
+/// # Arguments
+///
+/// * `path` - Path of the field
+/// * `other` - Path of the submitted name
+fn names_field(path: &[&str], other: &[String]) -> bool {
+    *other == *path
+        || (other.len() == path.len() + 1
+            && other[..path.len()] == *path
+            && other[path.len()].is_empty())
+}
+
+/// Returns the segments of a submitted name following a prefix, or None if
+/// the name isn't nested under the prefix.  Values of the prefix itself
+/// (including `prefix[]`) aren't nested under it
+///
+/// # Arguments
+///
+/// * `prefix` - Path of the prefix
+/// * `path` - Path of the submitted name
+fn relative_path<'p>(prefix: &[&str], path: &'p [String]) -> Option<&'p [String]> {
+    match path.len() > prefix.len()
+        && path[..prefix.len()] == *prefix
+        && !path[prefix.len()].is_empty()
+    {
+        true => Some(&path[prefix.len()..]),
+        false => None,
+    }
+}
+
+/// Returns true if a segment of a name is an index (digits only)
+///
+/// # Arguments
+///
+/// * `segment` - Segment of a name
+fn is_index(segment: &str) -> bool {
+    !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Parses a segment holding an index
+///
+/// # Arguments
+///
+/// * `segment` - Segment of a name
+fn parse_index(segment: &str) -> Option<usize> {
+    match is_index(segment) {
+        true => segment.parse().ok(),
+        false => None,
+    }
+}
+
+/// Decodes a urlencoded name or value
+///
+/// # Arguments
+///
+/// * `bytes` - Encoded name or value
+fn decode(bytes: &[u8]) -> String {
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let digit = |idx: usize| bytes.get(idx).and_then(|b| (*b as char).to_digit(16));
+                match (digit(idx + 1), digit(idx + 2)) {
+                    (Some(hi), Some(lo)) => {
+                        decoded.push((hi << 4 | lo) as u8);
+                        idx += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            b => decoded.push(b),
+        }
+        idx += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Support code for the derive macro, not public API.  Picks how a field is
+/// parsed: `FromFormField` if its type implements it, otherwise `FromStr`
+/// (reporting a `ValidateError` returned by `from_str` under the field's name)
+#[doc(hidden)]
+pub mod __private {
+    use super::{Field, FromFormField};
+    use crate::validate::ValidateError;
+    use std::marker::PhantomData;
+    use std::str::FromStr;
+
+    pub struct Probe<T>(pub PhantomData<T>);
+
+    pub trait ViaFromFormField<T> {
+        fn webforms_from_field(&self, field: &Field) -> Result<T, Vec<ValidateError>>;
+    }
+
+    impl<T: FromFormField> ViaFromFormField<T> for &&Probe<T> {
+        fn webforms_from_field(&self, field: &Field) -> Result<T, Vec<ValidateError>> {
+            T::from_field_all(field)
+        }
+    }
+
+    pub trait ViaValidatedFromStr<T> {
+        fn webforms_from_field(&self, field: &Field) -> Result<T, Vec<ValidateError>>;
+    }
+
+    impl<T: FromStr<Err = ValidateError>> ViaValidatedFromStr<T> for &Probe<T> {
+        fn webforms_from_field(&self, field: &Field) -> Result<T, Vec<ValidateError>> {
+            field.parse().map_err(|error| vec![error])
+        }
+    }
+
+    pub trait ViaFromStr<T> {
+        fn webforms_from_field(&self, field: &Field) -> Result<T, Vec<ValidateError>>;
+    }
+
+    impl<T: FromStr> ViaFromStr<T> for Probe<T> {
+        fn webforms_from_field(&self, field: &Field) -> Result<T, Vec<ValidateError>> {
+            let value = field.value().ok_or_else(|| vec![field.missing()])?;
+            value
+                .parse()
+                .map_err(|_| vec![field.invalid("a valid value")])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::{
+        parse_valid, parse_valid_with, FormData, FromForm, ParseError, ParseLimits,
+    };
+    use crate::testing::Submission;
+    use crate::validate::{ValidateError, ValidateForm};
+
+    fn errors(err: &ParseError) -> Vec<(&str, &str)> {
+        err.errors().iter().map(|e| (e.field(), e.code())).collect()
+    }
+
+    #[test]
+    fn test_from_urlencoded() {
+        let data = FormData::from_urlencoded("a=1&b=x+y%20z&&c&d=%zz%4&e=caf%C3%A9&e=%FF&f%5B%5D=");
+        let fields: Vec<(&str, &str)> = data
+            .fields()
+            .iter()
+            .map(|(n, v)| (n.as_str(), v.as_str()))
+            .collect();
+
+        assert_eq!(
+            fields,
+            vec![
+                ("a", "1"),
+                ("b", "x y z"),
+                ("c", ""),
+                ("d", "%zz%4"),
+                ("e", "café"),
+                ("e", "\u{fffd}"),
+                ("f[]", ""),
+            ]
+        );
+        assert_eq!(data.get("e"), Some("café"));
+        assert_eq!(data.get_all("e").len(), 2);
+        assert_eq!(data.get("missing"), None);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Color(String);
+
+    impl std::str::FromStr for Color {
+        type Err = ();
+
+        fn from_str(s: &str) -> Result<Self, ()> {
+            match s.starts_with('#') {
+                true => Ok(Color(s.to_owned())),
+                false => Err(()),
+            }
+        }
+    }
+
+    #[derive(Debug, FromForm, ValidateForm)]
+    #[form(rename_all = "camelCase")]
+    struct ProfileForm {
+        #[validate(min_length = 3)]
+        pub user_name: String,
+
+        #[validate(min_value = 18)]
+        pub age: u8,
+
+        pub height: Option<f64>,
+
+        pub bio: Option<String>,
+
+        #[form(keep_empty)]
+        pub nickname: Option<String>,
+
+        #[form(rename = "tag")]
+        pub tags: Vec<u32>,
+
+        pub newsletter: bool,
+
+        pub color: Color,
+
+        pub initial: Option<char>,
+    }
+
+    #[test]
+    fn test_from_form() {
+        let body = Submission::new()
+            .field("userName", "Mike Smith")
+            .field("age", " 30 ")
+            .field("height", "")
+            .field("bio", "")
+            .field("nickname", "")
+            .field("tag", 1)
+            .field("tag", 2)
+            .field("newsletter", "false")
+            .field("newsletter", "on")
+            .field("color", "#ff0000")
+            .to_urlencoded();
+
+        let form = ProfileForm::from_urlencoded(body).unwrap();
+        assert_eq!(form.user_name, "Mike Smith");
+        assert_eq!(form.age, 30);
+        assert_eq!(form.height, None);
+        assert_eq!(form.bio, None);
+        assert_eq!(form.nickname, Some(String::new()));
+        assert_eq!(form.tags, vec![1, 2]);
+        assert!(form.newsletter);
+        assert_eq!(form.color, Color("#ff0000".to_owned()));
+        assert_eq!(form.initial, None);
+
+        let form = ProfileForm::from_urlencoded("userName=mo&age=18&newsletter=false&color=%23fff")
+            .unwrap();
+        assert!(form.tags.is_empty());
+        assert!(!form.newsletter);
+        assert_eq!(form.nickname, None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let body = "age=&height=tall&tag=1&tag=-2&color=red&initial=ab";
+        let errs = ProfileForm::from_urlencoded(body).unwrap_err();
+        assert_eq!(
+            errors(&errs),
+            vec![
+                ("userName", "missing"),
+                ("age", "missing"),
+                ("height", "invalid_value"),
+                ("tag", "invalid_value"),
+                ("color", "invalid_value"),
+                ("initial", "invalid_value"),
+            ]
+        );
+
+        let errs = ProfileForm::from_urlencoded("userName=mike&age=300").unwrap_err();
+        assert_eq!(
+            errs.errors()[0].to_string(),
+            "age: expected a non-negative integer"
+        );
+    }
+
+    #[test]
+    fn test_parse_valid() {
+        let form: ProfileForm = parse_valid("userName=mike&age=30&color=%23fff").unwrap();
+        assert_eq!(form.age, 30);
+
+        let errs = parse_valid::<ProfileForm, _>("userName=mo&age=12&color=%23fff").unwrap_err();
+        assert_eq!(
+            errors(&errs),
+            vec![("userName", "input_too_short"), ("age", "too_small")]
+        );
+
+        let errs = parse_valid::<ProfileForm, _>("userName=mo").unwrap_err();
+        assert_eq!(
+            errors(&errs),
+            vec![("age", "missing"), ("color", "missing")]
+        );
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        // The default limits apply unless others are given
+        let body = format!(
+            "userName=mike&age=30&color=%23fff&bio={}",
+            "a".repeat(70_000)
+        );
+        let err = ProfileForm::from_urlencoded(&body).unwrap_err();
+        assert_eq!(
+            err,
+            ParseError::ValueTooLong {
+                key: "bio".to_owned(),
+                limit: 64 * 1024
+            }
+        );
+        assert!(errors(&err).is_empty());
+        assert!(parse_valid::<ProfileForm, _>(&body).is_err());
+
+        let limits = ParseLimits {
+            max_fields: 2,
+            ..ParseLimits::default()
+        };
+        let err = parse_valid_with::<ProfileForm, _>("userName=mike&age=30&color=%23fff", &limits)
+            .unwrap_err();
+        assert_eq!(err, ParseError::TooManyFields { limit: 2 });
+        assert!(ProfileForm::from_urlencoded_with("userName=mike&age=30", &limits).is_err());
+    }
+
+    #[derive(Debug, FromForm)]
+    #[form(empty_as_none)]
+    struct EmptyForm<T> {
+        pub name: String,
+
+        #[form(keep_empty)]
+        pub note: String,
+
+        pub count: T,
+    }
+
+    #[test]
+    fn test_empty_policy() {
+        let errs = EmptyForm::<Option<u8>>::from_urlencoded("name=&note=&count=").unwrap_err();
+        assert_eq!(errors(&errs), vec![("name", "missing")]);
+
+        let form = EmptyForm::<Option<u8>>::from_urlencoded("name=a&note=&count=").unwrap();
+        assert_eq!(form.name, "a");
+        assert_eq!(form.note, "");
+        assert_eq!(form.count, None);
+    }
+
+    #[cfg(feature = "types")]
+    #[derive(Debug, FromForm)]
+    struct ContactForm {
+        pub email: crate::types::Email,
+        pub phone: Option<crate::types::PhoneNumber>,
+    }
+
+    #[cfg(feature = "types")]
+    #[test]
+    fn test_validated_types() {
+        let form = ContactForm::from_urlencoded("email=mike%40test.com&phone=").unwrap();
+        assert_eq!(form.email.as_str(), "mike@test.com");
+        assert!(form.phone.is_none());
+
+        let errs = ContactForm::from_urlencoded("email=mike&phone=123").unwrap_err();
+        assert_eq!(
+            errors(&errs),
+            vec![
+                ("email", "invalid_email"),
+                ("phone", "invalid_phone_number")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_key_path() {
+        use super::key_path;
+
+        assert_eq!(key_path("name"), vec!["name"]);
+        assert_eq!(key_path("items[0][sku]"), vec!["items", "0", "sku"]);
+        assert_eq!(key_path("items.0.sku"), vec!["items", "0", "sku"]);
+        assert_eq!(key_path("items[0].sku"), vec!["items", "0", "sku"]);
+        assert_eq!(key_path("tags[]"), vec!["tags", ""]);
+        assert_eq!(key_path("a[b.c]"), vec!["a", "b.c"]);
+
+        // Not nested
+        for key in &["[a]", ".a", "a.", "a..b", "a[b", "a[b]c"] {
+            assert_eq!(key_path(key), vec![*key]);
+        }
+    }
+
+    #[derive(Debug, FromForm, PartialEq)]
+    struct Address {
+        pub street: String,
+        pub city: String,
+    }
+
+    #[derive(Debug, FromForm, PartialEq)]
+    struct LineItem {
+        pub sku: String,
+        pub qty: u32,
+    }
+
+    #[derive(Debug, FromForm)]
+    struct OrderForm {
+        pub customer: String,
+        pub address: Address,
+        pub billing: Option<Address>,
+        pub items: Vec<LineItem>,
+        pub tags: Vec<String>,
+    }
+
+    #[test]
+    fn test_nested_form() {
+        let data = FormData::from_urlencoded(
+            "customer=mike&address[street]=1+Main+St&address.city=Springfield\
+             &items[1][sku]=B2&items[1][qty]=2&items.0.sku=A1&items.0.qty=1\
+             &tags[]=new&tags[]=gift",
+        );
+        assert_eq!(data.indices("items"), vec![0, 1]);
+        assert_eq!(data.nested("items[1]").get("sku"), Some("B2"));
+        assert!(data.has_nested("address"));
+        assert!(!data.has_nested("tags"));
+
+        let form = OrderForm::from_form(&data).unwrap();
+        assert_eq!(form.customer, "mike");
+        assert_eq!(form.address.street, "1 Main St");
+        assert_eq!(form.address.city, "Springfield");
+        assert_eq!(form.billing, None);
+        assert_eq!(
+            form.items,
+            vec![
+                LineItem {
+                    sku: "A1".to_owned(),
+                    qty: 1
+                },
+                LineItem {
+                    sku: "B2".to_owned(),
+                    qty: 2
+                },
+            ]
+        );
+        assert_eq!(form.tags, vec!["new", "gift"]);
+
+        let form = OrderForm::from_urlencoded(
+            "customer=mike&address[street]=a&address[city]=b\
+             &billing[street]=c&billing[city]=d&tags[0]=x&tags[2]=y",
+        )
+        .unwrap();
+        assert_eq!(form.billing.unwrap().city, "d");
+        assert!(form.items.is_empty());
+        assert_eq!(form.tags, vec!["x", "y"]);
+
+        // Every error in a nested form is reported under its path
+        let errs = OrderForm::from_urlencoded(
+            "customer=mike&address[street]=a&billing[city]=d&items[0][sku]=A1&items[0][qty]=x\
+             &items[1][qty]=y",
+        )
+        .unwrap_err();
+        assert_eq!(
+            errors(&errs),
+            vec![
+                ("address.city", "missing"),
+                ("billing.street", "missing"),
+                ("items.0.qty", "invalid_value"),
+                ("items.1.sku", "missing"),
+                ("items.1.qty", "invalid_value"),
+            ]
+        );
+    }
+
+    #[derive(Debug, FromForm)]
+    struct ShipmentForm {
+        pub orders: Vec<OrderForm>,
+    }
+
+    #[test]
+    fn test_deeply_nested_form() {
+        let form = ShipmentForm::from_urlencoded(
+            "orders[0][customer]=mike&orders[0][address][street]=a&orders[0][address][city]=b",
+        )
+        .unwrap();
+        assert_eq!(form.orders[0].address.city, "b");
+
+        let errs = ShipmentForm::from_urlencoded(
+            "orders[0][customer]=mike&orders[0][address][street]=a&orders[0][address][city]=b\
+             &orders[0][items][0][sku]=A1",
+        )
+        .unwrap_err();
+        assert_eq!(errors(&errs), vec![("orders.0.items.0.qty", "missing")]);
+
+        // A nested field parsed on its own reports its first error
+        let data = FormData::from_urlencoded("address[street]=a");
+        let field = crate::parse::Field::new(&data, "address", None);
+        let err = <Address as crate::parse::FromFormField>::from_field(&field).unwrap_err();
+        assert_eq!(err.field(), "address.city");
+    }
+}
@@ -0,0 +1,481 @@
+//! Checks uploaded files against the `file(...)` validation rule
+//!
+//! A file's type is detected from its first bytes ("magic numbers") rather
+//! than the content type sent by the client, which can't be trusted.  Only
+//! the start of a file is read, so checking a large upload is cheap.
+
+use super::UploadedFile;
+use crate::validate::ValidateError;
+use std::io::Read;
+
+/// Number of bytes read from the start of a file to detect its type and
+/// image dimensions.  JPEG dimensions may follow up to 64KB of metadata
+const HEAD_SIZE: u64 = 64 * 1024;
+
+/// Limits placed on an uploaded file by `#[validate(file(...))]`.  Usually
+/// built by the derive macro
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FileRule {
+    /// Maximum size of the file, in bytes
+    pub max_size: Option<u64>,
+
+    /// Allowed extensions (e.g., `png`), compared without case.  Empty to
+    /// allow any extension
+    pub extensions: &'static [&'static str],
+
+    /// Allowed types (e.g., `image/png` or `image/*`), compared with the type
+    /// detected from the file's contents.  Empty to allow any type
+    pub mime: &'static [&'static str],
+
+    /// Minimum width of an image, in pixels
+    pub min_width: Option<u32>,
+
+    /// Maximum width of an image, in pixels
+    pub max_width: Option<u32>,
+
+    /// Minimum height of an image, in pixels
+    pub min_height: Option<u32>,
+
+    /// Maximum height of an image, in pixels
+    pub max_height: Option<u32>,
+}
+
+impl FileRule {
+    /// Checks a file against this rule, in order: size, extension, type and
+    /// image dimensions.  Returns the first failure
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - File to check
+    /// * `field` - Name of the field holding the file, reported in errors
+    pub fn check(&self, file: &UploadedFile, field: &'static str) -> Result<(), ValidateError> {
+        let invalid = || ValidateError::InvalidFileType { field };
+
+        if let Some(max) = self.max_size {
+            if file.size() > max {
+                return Err(ValidateError::FileTooLarge { field, max });
+            }
+        }
+
+        if !self.extensions.is_empty() {
+            let extension = file.extension().ok_or_else(invalid)?;
+            if !self
+                .extensions
+                .iter()
+                .any(|e| e.trim_start_matches('.').eq_ignore_ascii_case(extension))
+            {
+                return Err(invalid());
+            }
+        }
+
+        let limits = [
+            self.min_width,
+            self.max_width,
+            self.min_height,
+            self.max_height,
+        ];
+        let check_dimensions = limits.iter().any(Option::is_some);
+        if self.mime.is_empty() && !check_dimensions {
+            return Ok(());
+        }
+
+        // A file that can't be read is treated as one of an unknown type
+        let head = head(file).ok_or_else(invalid)?;
+
+        if !self.mime.is_empty() {
+            let mime = sniff(&head).ok_or_else(invalid)?;
+            if !self.mime.iter().any(|pattern| mime_matches(pattern, mime)) {
+                return Err(invalid());
+            }
+        }
+
+        if check_dimensions {
+            let (width, height) = dimensions(&head).ok_or_else(invalid)?;
+            let too_small = |value: u32, min: Option<u32>| min.is_some_and(|min| value < min);
+            let too_large = |value: u32, max: Option<u32>| max.is_some_and(|max| value > max);
+
+            if too_small(width, self.min_width)
+                || too_large(width, self.max_width)
+                || too_small(height, self.min_height)
+                || too_large(height, self.max_height)
+            {
+                return Err(ValidateError::InvalidImageDimensions {
+                    field,
+                    width,
+                    height,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl UploadedFile {
+    /// Returns the extension of the file's name (e.g., `png`), if it has one
+    pub fn extension(&self) -> Option<&str> {
+        let filename = self.filename()?;
+        match filename.rfind('.') {
+            Some(idx) if idx > 0 && idx + 1 < filename.len() => Some(&filename[idx + 1..]),
+            _ => None,
+        }
+    }
+
+    /// Detects the type of the file from its contents (e.g., `image/png`).
+    /// Returns None for empty files and types that aren't recognized
+    pub fn sniff_mime(&self) -> Option<&'static str> {
+        head(self).and_then(|head| sniff(&head))
+    }
+
+    /// Returns the width and height of a PNG, JPEG, GIF, BMP or WebP image,
+    /// read from its header
+    pub fn image_dimensions(&self) -> Option<(u32, u32)> {
+        head(self).and_then(|head| dimensions(&head))
+    }
+}
+
+/// Reads the start of a file
+fn head(file: &UploadedFile) -> Option<Vec<u8>> {
+    let mut head = vec![];
+    file.open()
+        .and_then(|f| f.take(HEAD_SIZE).read_to_end(&mut head))
+        .ok()?;
+    Some(head)
+}
+
+/// Returns true if a detected type is matched by an allowed type, which may
+/// end with a wildcard (e.g., `image/*`)
+///
+/// # Arguments
+///
+/// * `pattern` - Allowed type
+/// * `mime` - Detected type
+fn mime_matches(pattern: &str, mime: &str) -> bool {
+    match pattern {
+        "*" | "*/*" => true,
+        _ if pattern.ends_with("/*") => {
+            let prefix = &pattern[..pattern.len() - 1];
+            mime.len() > prefix.len() && mime[..prefix.len()].eq_ignore_ascii_case(prefix)
+        }
+        _ => pattern.eq_ignore_ascii_case(mime),
+    }
+}
+
+/// Detects the type of a file from its first bytes
+///
+/// # Arguments
+///
+/// * `head` - Start of the file
+fn sniff(head: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"II*\0", "image/tiff"),
+        (b"MM\0*", "image/tiff"),
+        (b"\0\0\x01\0", "image/x-icon"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"ID3", "audio/mpeg"),
+        (b"OggS", "audio/ogg"),
+        (b"fLaC", "audio/flac"),
+        (b"\x1a\x45\xdf\xa3", "video/webm"),
+    ];
+
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(sig, _)| head.starts_with(sig)) {
+        return Some(mime);
+    }
+
+    if head.len() >= 12 && head.starts_with(b"RIFF") {
+        return match &head[8..12] {
+            b"WEBP" => Some("image/webp"),
+            b"WAVE" => Some("audio/wav"),
+            b"AVI " => Some("video/x-msvideo"),
+            _ => None,
+        };
+    }
+
+    // ISO media files name their brand after `ftyp`
+    if head.len() >= 12 && &head[4..8] == b"ftyp" {
+        return Some(match &head[8..12] {
+            b"avif" => "image/avif",
+            b"heic" | b"heix" | b"mif1" => "image/heic",
+            b"qt  " => "video/quicktime",
+            _ => "video/mp4",
+        });
+    }
+
+    // "BM" alone is too likely to start a text file, so also require a known
+    // DIB header size
+    if head.len() >= 18 && head.starts_with(b"BM") {
+        if let 12 | 40 | 52 | 56 | 108 | 124 = le32(&head[14..18]) {
+            return Some("image/bmp");
+        }
+    }
+
+    // Anything else that is UTF-8 without control characters is text.  The
+    // head may end part way through a character
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&head[..e.valid_up_to()]).ok()?,
+        Err(_) => return None,
+    };
+    match !text.is_empty()
+        && !text
+            .chars()
+            .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c'))
+    {
+        true => Some("text/plain"),
+        false => None,
+    }
+}
+
+/// Reads the width and height of an image from its first bytes
+///
+/// # Arguments
+///
+/// * `head` - Start of the file
+fn dimensions(head: &[u8]) -> Option<(u32, u32)> {
+    let at = |start: usize, len: usize| head.get(start..start + len);
+
+    match sniff(head)? {
+        "image/png" if at(12, 4)? == b"IHDR" => Some((be32(at(16, 4)?), be32(at(20, 4)?))),
+        "image/gif" => Some((le16(at(6, 2)?), le16(at(8, 2)?))),
+        "image/bmp" => match le32(at(14, 4)?) {
+            12 => Some((le16(at(18, 2)?), le16(at(20, 2)?))),
+            _ => Some((
+                (le32(at(18, 4)?) as i32).unsigned_abs(),
+                (le32(at(22, 4)?) as i32).unsigned_abs(),
+            )),
+        },
+        "image/webp" => match at(12, 4)? {
+            b"VP8 " => Some((le16(at(26, 2)?) & 0x3fff, le16(at(28, 2)?) & 0x3fff)),
+            b"VP8L" => {
+                let b = at(21, 4)?;
+                let bits = le32(b);
+                Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+            }
+            b"VP8X" => {
+                let le24 =
+                    |b: &[u8]| u32::from(b[0]) | u32::from(b[1]) << 8 | u32::from(b[2]) << 16;
+                Some((le24(at(24, 3)?) + 1, le24(at(27, 3)?) + 1))
+            }
+            _ => None,
+        },
+        "image/jpeg" => jpeg_dimensions(head),
+        _ => None,
+    }
+}
+
+/// Reads the dimensions of a JPEG from its first start-of-frame segment
+///
+/// # Arguments
+///
+/// * `head` - Start of the file
+fn jpeg_dimensions(head: &[u8]) -> Option<(u32, u32)> {
+    let mut idx = 2;
+    loop {
+        if *head.get(idx)? != 0xff {
+            return None;
+        }
+        let marker = *head.get(idx + 1)?;
+        match marker {
+            // Fill bytes before a marker
+            0xff => idx += 1,
+            // Markers without a length
+            0x01 | 0xd0..=0xd8 => idx += 2,
+            // Start of frame, other than DHT, JPG and DAC
+            0xc0..=0xcf if marker != 0xc4 && marker != 0xc8 && marker != 0xcc => {
+                let height = be16(head.get(idx + 5..idx + 7)?);
+                let width = be16(head.get(idx + 7..idx + 9)?);
+                return Some((width, height));
+            }
+            _ => idx += 2 + be16(head.get(idx + 2..idx + 4)?) as usize,
+        }
+    }
+}
+
+fn be16(b: &[u8]) -> u32 {
+    u32::from(b[0]) << 8 | u32::from(b[1])
+}
+
+fn be32(b: &[u8]) -> u32 {
+    u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+}
+
+fn le16(b: &[u8]) -> u32 {
+    u32::from(b[0]) | u32::from(b[1]) << 8
+}
+
+fn le32(b: &[u8]) -> u32 {
+    u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dimensions, mime_matches, sniff};
+    use crate::parse::UploadedFile;
+    use crate::validate::{Code, ValidateError, ValidateForm};
+    use crate::{assert_invalid, assert_valid};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&width.to_be_bytes());
+        png.extend_from_slice(&height.to_be_bytes());
+        png.extend_from_slice(&[8, 6, 0, 0, 0, 0, 0, 0, 0]);
+        png
+    }
+
+    fn file(name: &str, bytes: Vec<u8>) -> UploadedFile {
+        UploadedFile::from_bytes(Some(name), Some("image/png"), bytes)
+    }
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(sniff(&png(1, 1)), Some("image/png"));
+        assert_eq!(sniff(b"\xff\xd8\xff\xe0\0\x10JFIF"), Some("image/jpeg"));
+        assert_eq!(sniff(b"GIF89a\x01\0\x01\0"), Some("image/gif"));
+        assert_eq!(sniff(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(sniff(b"\0\0\0\x1cftypavif"), Some("image/avif"));
+        assert_eq!(sniff(b"%PDF-1.7\n"), Some("application/pdf"));
+        assert_eq!(sniff(b"PK\x03\x04\x14\0"), Some("application/zip"));
+        assert_eq!(
+            sniff("name,email\nmike,mike@test.com\n".as_bytes()),
+            Some("text/plain")
+        );
+        assert_eq!(sniff(b"BMW,X5\n"), Some("text/plain"));
+        assert_eq!(sniff("caf\u{e9}".as_bytes()), Some("text/plain"));
+        assert_eq!(sniff(b"\x7fELF\x02\x01"), None);
+        assert_eq!(sniff(b""), None);
+
+        assert!(mime_matches("image/*", "image/png"));
+        assert!(mime_matches("IMAGE/PNG", "image/png"));
+        assert!(mime_matches("*/*", "application/pdf"));
+        assert!(!mime_matches("image/*", "application/pdf"));
+        assert!(!mime_matches("image/png", "image/jpeg"));
+    }
+
+    #[test]
+    fn test_dimensions() {
+        assert_eq!(dimensions(&png(640, 480)), Some((640, 480)));
+        assert_eq!(dimensions(b"GIF89a\x0a\0\x14\0\0\0"), Some((10, 20)));
+
+        // APP0 segment, then a baseline start of frame (40x30)
+        let jpeg = b"\xff\xd8\xff\xe0\0\x04\0\0\xff\xc0\0\x11\x08\0\x1e\0\x28\x03";
+        assert_eq!(dimensions(jpeg), Some((40, 30)));
+        assert_eq!(dimensions(&jpeg[..12]), None);
+
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8X\x0a\0\0\0\0\0\0\0".to_vec();
+        webp.extend_from_slice(&[0x7f, 0x02, 0x00, 0xdf, 0x01, 0x00]);
+        assert_eq!(dimensions(&webp), Some((640, 480)));
+
+        let mut bmp = b"BM\0\0\0\0\0\0\0\0\0\0\0\0\x28\0\0\0".to_vec();
+        bmp.extend_from_slice(&16i32.to_le_bytes());
+        bmp.extend_from_slice(&(-8i32).to_le_bytes());
+        assert_eq!(dimensions(&bmp), Some((16, 8)));
+
+        assert_eq!(dimensions(b"%PDF-1.7\n"), None);
+    }
+
+    #[derive(ValidateForm)]
+    struct AvatarForm {
+        #[validate(file(
+            max_size = "1KB",
+            extensions("png", ".GIF"),
+            mime("image/*"),
+            min_width = 16,
+            max_width = 100
+        ))]
+        pub avatar: UploadedFile,
+
+        #[validate(file(mime("application/pdf")), code = "not_a_pdf")]
+        pub resume: Option<UploadedFile>,
+    }
+
+    fn errors(form: &AvatarForm) -> Vec<String> {
+        match form.validate() {
+            Ok(()) => vec![],
+            Err(errs) => errs.iter().map(ValidateError::to_string).collect(),
+        }
+    }
+
+    #[test]
+    fn test_file_rule() {
+        let mut form = AvatarForm {
+            avatar: file("me.png", png(64, 64)),
+            resume: None,
+        };
+        assert_valid!(form);
+
+        // Detected from the contents, whatever the extension says
+        form.avatar = file("me.gif", png(64, 64));
+        assert_valid!(form);
+
+        form.avatar = file("me.png", vec![0; 1025]);
+        assert_eq!(errors(&form), ["avatar: file too large. (1024 bytes max)"]);
+
+        form.avatar = file("me.txt", png(64, 64));
+        assert_invalid!(form, "avatar" => Code::InvalidFileType);
+
+        form.avatar = file("me", png(64, 64));
+        assert_invalid!(form, "avatar" => Code::InvalidFileType);
+
+        form.avatar = file("me.png", b"<script>alert(1)</script>".to_vec());
+        assert_invalid!(form, "avatar" => Code::InvalidFileType);
+
+        form.avatar = file("me.png", png(200, 50));
+        assert_eq!(
+            errors(&form),
+            ["avatar: image dimensions not allowed. (200x50)"]
+        );
+
+        form.avatar = file("me.png", png(8, 8));
+        assert_invalid!(form, "avatar" => Code::InvalidImageDimensions);
+
+        form.avatar = file("me.png", png(64, 64));
+        form.resume = Some(file("cv.pdf", png(64, 64)));
+        assert_invalid!(form, "resume" => "not_a_pdf");
+
+        form.resume = Some(file("cv.pdf", b"%PDF-1.7\n".to_vec()));
+        assert_valid!(form);
+    }
+
+    #[cfg(all(feature = "html", feature = "testing"))]
+    #[derive(ValidateForm, crate::html::HtmlForm)]
+    struct UploadForm {
+        pub title: String,
+
+        #[validate(file(extensions("png", "jpg"), mime("image/*")))]
+        pub avatar: UploadedFile,
+
+        pub resume: Option<UploadedFile>,
+    }
+
+    #[cfg(all(feature = "html", feature = "testing"))]
+    #[test]
+    fn test_html_file_inputs() {
+        use crate::html::HtmlForm;
+        use crate::testing::RenderedForm;
+
+        let form = UploadForm {
+            title: "Hello".to_owned(),
+            avatar: file("me.png", png(1, 1)),
+            resume: None,
+        };
+        let builder = form.blank_form();
+        assert_eq!(builder.enctype(), "multipart/form-data");
+
+        let html = RenderedForm::from(&builder);
+        assert_eq!(html.field("title").input_type(), "text");
+        assert_eq!(html.field("avatar").input_type(), "file");
+        assert_eq!(
+            html.field("avatar").attr("accept"),
+            Some(".png,.jpg,image/*")
+        );
+        assert!(html.field("avatar").is_required());
+        assert_eq!(html.field("resume").input_type(), "file");
+        assert!(!html.field("resume").is_required());
+    }
+}
@@ -0,0 +1,418 @@
+//! Limits on the size and structure of a submission
+//!
+//! Checked while a submission is decoded, before any field is parsed, so a
+//! malicious one can't send thousands of fields, huge names or values,
+//! deeply nested names or huge indices.  Every violation is reported as a
+//! [`ParseError`].
+
+use super::{is_index, key_name, key_path, urlencoded_pairs, FormData};
+use crate::validate::ValidateError;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// How a name submitted more than once is handled.  Names ending in `[]`
+/// (e.g., `tags[]`) are always collected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Duplicates {
+    /// Keep the first value
+    First,
+
+    /// Keep the last value
+    Last,
+
+    /// Reject the submission with `ParseError::DuplicateKey`
+    Error,
+
+    /// Keep every value, as multiple selects and checkbox groups submit the
+    /// same name more than once
+    Collect,
+}
+
+/// Limits enforced on a submission by [`FormData::from_urlencoded_with`] and
+/// [`FormData::enforce`].  The defaults are conservative; raise them for
+/// large forms
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseLimits {
+    /// Maximum number of values and files
+    pub max_fields: usize,
+
+    /// Maximum length of a field's name, in bytes
+    pub max_key_length: usize,
+
+    /// Maximum length of a value, in bytes
+    pub max_value_length: usize,
+
+    /// Maximum number of segments following a field's name (e.g., 2 for
+    /// `items[0][sku]`)
+    pub max_depth: usize,
+
+    /// Maximum index of a repeated field (e.g., 3 for `items[3]`)
+    pub max_index: usize,
+
+    /// How names submitted more than once are handled
+    pub duplicates: Duplicates,
+}
+
+impl Default for ParseLimits {
+    fn default() -> ParseLimits {
+        ParseLimits {
+            max_fields: 256,
+            max_key_length: 128,
+            max_value_length: 64 * 1024,
+            max_depth: 4,
+            max_index: 100,
+            duplicates: Duplicates::Collect,
+        }
+    }
+}
+
+impl ParseLimits {
+    /// Checks the name of a submitted field against these limits
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Name of the field (e.g., `items[0][sku]`)
+    pub fn check_key(&self, key: &str) -> Result<(), ParseError> {
+        if key.len() > self.max_key_length {
+            return Err(ParseError::KeyTooLong {
+                limit: self.max_key_length,
+            });
+        }
+
+        let path = key_path(key);
+        if path.len() - 1 > self.max_depth {
+            return Err(ParseError::TooDeep {
+                key: key.to_owned(),
+                limit: self.max_depth,
+            });
+        }
+
+        let index_too_large = path[1..]
+            .iter()
+            .filter(|segment| is_index(segment))
+            .any(|segment| {
+                !segment
+                    .parse()
+                    .is_ok_and(|idx: usize| idx <= self.max_index)
+            });
+        if index_too_large {
+            return Err(ParseError::IndexTooLarge {
+                key: key.to_owned(),
+                limit: self.max_index,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Applies the duplicate key policy to submitted names and values (or
+    /// files), keeping them in order
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - Submitted names and values
+    fn dedup<T>(&self, entries: Vec<(String, T)>) -> Result<Vec<(String, T)>, ParseError> {
+        if self.duplicates == Duplicates::Collect {
+            return Ok(entries);
+        }
+
+        // Names are compared after parsing, so `a[b]` and `a.b` are the same
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        let mut kept: Vec<Option<(String, T)>> = Vec::with_capacity(entries.len());
+        for (name, value) in entries {
+            let path = key_path(&name);
+            if path.len() > 1 && path[path.len() - 1].is_empty() {
+                kept.push(Some((name, value)));
+                continue;
+            }
+
+            let key = key_name(&path);
+            match (seen.get(&key).cloned(), self.duplicates) {
+                (Some(_), Duplicates::First) => {}
+                (Some(_), Duplicates::Error) => return Err(ParseError::DuplicateKey { key: name }),
+                (Some(idx), _) => {
+                    kept[idx] = None;
+                    seen.insert(key, kept.len());
+                    kept.push(Some((name, value)));
+                }
+                (None, _) => {
+                    seen.insert(key, kept.len());
+                    kept.push(Some((name, value)));
+                }
+            }
+        }
+
+        Ok(kept.into_iter().flatten().collect())
+    }
+}
+
+/// Reasons a submission is rejected: it exceeds [`ParseLimits`], or its
+/// fields can't be parsed
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    /// The submission has more values and files than
+    /// `ParseLimits::max_fields`
+    TooManyFields { limit: usize },
+
+    /// A field's name is longer than `ParseLimits::max_key_length`
+    KeyTooLong { limit: usize },
+
+    /// A value is longer than `ParseLimits::max_value_length`
+    ValueTooLong { key: String, limit: usize },
+
+    /// A field's name is nested deeper than `ParseLimits::max_depth`
+    TooDeep { key: String, limit: usize },
+
+    /// A field's name has an index larger than `ParseLimits::max_index`
+    IndexTooLarge { key: String, limit: usize },
+
+    /// A name was submitted more than once with `Duplicates::Error`
+    DuplicateKey { key: String },
+
+    /// Fields were missing or couldn't be parsed, one error per field, or
+    /// (with `parse_valid`) the form failed validation
+    Invalid(Vec<ValidateError>),
+}
+
+impl ParseError {
+    /// Returns the errors of the fields that couldn't be parsed or failed
+    /// validation, or nothing if the submission exceeded its limits
+    pub fn errors(&self) -> &[ValidateError] {
+        match self {
+            ParseError::Invalid(errors) => errors,
+            _ => &[],
+        }
+    }
+}
+
+impl From<Vec<ValidateError>> for ParseError {
+    fn from(errors: Vec<ValidateError>) -> ParseError {
+        ParseError::Invalid(errors)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::TooManyFields { limit } => {
+                write!(f, "submission has more than {} fields", limit)
+            }
+            ParseError::KeyTooLong { limit } => {
+                write!(f, "field name is longer than {} bytes", limit)
+            }
+            ParseError::ValueTooLong { key, limit } => {
+                write!(f, "{}: value is longer than {} bytes", key, limit)
+            }
+            ParseError::TooDeep { key, limit } => {
+                write!(f, "{}: nested more than {} levels deep", key, limit)
+            }
+            ParseError::IndexTooLarge { key, limit } => {
+                write!(f, "{}: index is larger than {}", key, limit)
+            }
+            ParseError::DuplicateKey { key } => write!(f, "{}: submitted more than once", key),
+            ParseError::Invalid(errors) => {
+                let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "{}", errors.join("; "))
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+impl FormData {
+    /// Decodes an `application/x-www-form-urlencoded` body (see
+    /// `from_urlencoded`), enforcing limits.  Decoding stops as soon as the
+    /// body has too many fields
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - Request body (or query string, without the leading `?`)
+    /// * `limits` - Limits to enforce
+    pub fn from_urlencoded_with<B: AsRef<[u8]>>(
+        body: B,
+        limits: &ParseLimits,
+    ) -> Result<FormData, ParseError> {
+        let mut data = FormData::new();
+        for (name, value) in urlencoded_pairs(body.as_ref()) {
+            if data.fields.len() == limits.max_fields {
+                return Err(ParseError::TooManyFields {
+                    limit: limits.max_fields,
+                });
+            }
+            data.push(name, value);
+        }
+        data.enforce(limits)
+    }
+
+    /// Checks every submitted name and value against limits and applies the
+    /// duplicate key policy.  Useful after `from_multipart`, whose
+    /// `MultipartLimits` already bound the size of each value and file
+    ///
+    /// # Arguments
+    ///
+    /// * `limits` - Limits to enforce
+    pub fn enforce(self, limits: &ParseLimits) -> Result<FormData, ParseError> {
+        if self.len() > limits.max_fields {
+            return Err(ParseError::TooManyFields {
+                limit: limits.max_fields,
+            });
+        }
+
+        for (name, value) in &self.fields {
+            limits.check_key(name)?;
+            if value.len() > limits.max_value_length {
+                return Err(ParseError::ValueTooLong {
+                    key: name.clone(),
+                    limit: limits.max_value_length,
+                });
+            }
+        }
+        for (name, _) in &self.files {
+            limits.check_key(name)?;
+        }
+
+        if limits.duplicates == Duplicates::Collect {
+            return Ok(self);
+        }
+
+        let mut data = FormData::new();
+        for (name, value) in limits.dedup(self.fields)? {
+            data.push(name, value);
+        }
+        for (name, file) in limits.dedup(self.files)? {
+            data.push_file(name, file);
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::{Duplicates, FormData, ParseError, ParseLimits};
+
+    fn values(data: &FormData) -> Vec<(&str, &str)> {
+        data.fields()
+            .iter()
+            .map(|(n, v)| (n.as_str(), v.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_limits() {
+        let limits = ParseLimits {
+            max_fields: 3,
+            max_key_length: 16,
+            max_value_length: 4,
+            max_depth: 2,
+            max_index: 10,
+            duplicates: Duplicates::Collect,
+        };
+
+        let data = FormData::from_urlencoded_with("a=1&items[10][sku]=x&b.c[]=2", &limits);
+        assert_eq!(data.unwrap().len(), 3);
+
+        let check = |body: &str| FormData::from_urlencoded_with(body, &limits).unwrap_err();
+        assert_eq!(
+            check("a=1&b=2&c=3&d=4"),
+            ParseError::TooManyFields { limit: 3 }
+        );
+        assert_eq!(
+            check("a_very_long_field_name=1"),
+            ParseError::KeyTooLong { limit: 16 }
+        );
+        assert_eq!(
+            check("a=12345"),
+            ParseError::ValueTooLong {
+                key: "a".to_owned(),
+                limit: 4
+            }
+        );
+        assert_eq!(
+            check("a[b][c][d]=1"),
+            ParseError::TooDeep {
+                key: "a[b][c][d]".to_owned(),
+                limit: 2
+            }
+        );
+        assert_eq!(
+            check("items.11.sku=x"),
+            ParseError::IndexTooLarge {
+                key: "items.11.sku".to_owned(),
+                limit: 10
+            }
+        );
+
+        let err = limits.check_key("a[9999999999999]").unwrap_err();
+        assert_eq!(err.to_string(), "a[9999999999999]: index is larger than 10");
+
+        // Data decoded without limits (e.g., multipart) is checked as a whole
+        let mut data = FormData::new();
+        data.push("a[b][c][d]", "1");
+        assert!(data.clone().enforce(&limits).is_err());
+        assert!(data.enforce(&ParseLimits::default()).is_ok());
+
+        // Decoding stops at the first field over the limit
+        let body = "a=1&".repeat(1_000_000);
+        assert_eq!(
+            FormData::from_urlencoded_with(body, &ParseLimits::default()),
+            Err(ParseError::TooManyFields { limit: 256 })
+        );
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let body = "a=1&b=2&a=3&tags[]=x&tags[]=y&c[d]=4&c.d=5";
+        let with = |duplicates| {
+            let limits = ParseLimits {
+                duplicates,
+                ..ParseLimits::default()
+            };
+            FormData::from_urlencoded_with(body, &limits)
+        };
+
+        let data = with(Duplicates::Collect).unwrap();
+        assert_eq!(data.get_all("a"), vec!["1", "3"]);
+        assert_eq!(data.get_all("c[d]"), vec!["4", "5"]);
+
+        let data = with(Duplicates::First).unwrap();
+        assert_eq!(
+            values(&data),
+            vec![
+                ("a", "1"),
+                ("b", "2"),
+                ("tags[]", "x"),
+                ("tags[]", "y"),
+                ("c[d]", "4")
+            ]
+        );
+
+        let data = with(Duplicates::Last).unwrap();
+        assert_eq!(
+            values(&data),
+            vec![
+                ("b", "2"),
+                ("a", "3"),
+                ("tags[]", "x"),
+                ("tags[]", "y"),
+                ("c.d", "5")
+            ]
+        );
+
+        assert_eq!(
+            with(Duplicates::Error),
+            Err(ParseError::DuplicateKey {
+                key: "a".to_owned()
+            })
+        );
+        assert!(FormData::from_urlencoded_with(
+            "tags[]=x&tags[]=y",
+            &ParseLimits {
+                duplicates: Duplicates::Error,
+                ..ParseLimits::default()
+            }
+        )
+        .is_ok());
+    }
+}
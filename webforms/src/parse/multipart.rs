@@ -0,0 +1,919 @@
+//! Streaming `multipart/form-data` parser
+//!
+//! Bodies are read in small chunks: text fields are kept in memory (up to
+//! `MultipartLimits::max_field_size`) and files are kept in memory until they
+//! grow past `MultipartLimits::memory_limit`, then written to a temporary file
+//! that is removed once every `UploadedFile` referring to it is dropped.
+
+use super::FormData;
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Number of bytes requested from the reader at a time
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Limits enforced while parsing a `multipart/form-data` body.  The defaults
+/// are conservative; raise them for forms that accept large uploads
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultipartLimits {
+    /// Maximum number of parts (fields and files) in a body
+    pub max_parts: usize,
+
+    /// Maximum size of a text field, in bytes
+    pub max_field_size: u64,
+
+    /// Maximum size of a single file, in bytes
+    pub max_file_size: u64,
+
+    /// Maximum size of the whole body, in bytes
+    pub max_total_size: u64,
+
+    /// Maximum size of a part's headers, in bytes
+    pub max_header_size: usize,
+
+    /// Files larger than this many bytes are written to a temporary file
+    /// instead of being kept in memory
+    pub memory_limit: usize,
+
+    /// Directory temporary files are created in, or None for
+    /// `std::env::temp_dir()`
+    pub temp_dir: Option<PathBuf>,
+}
+
+impl Default for MultipartLimits {
+    fn default() -> MultipartLimits {
+        MultipartLimits {
+            max_parts: 100,
+            max_field_size: 64 * 1024,
+            max_file_size: 10 * 1024 * 1024,
+            max_total_size: 20 * 1024 * 1024,
+            max_header_size: 8 * 1024,
+            memory_limit: 256 * 1024,
+            temp_dir: None,
+        }
+    }
+}
+
+/// Reasons a `multipart/form-data` body can't be parsed
+#[derive(Debug)]
+pub enum MultipartError {
+    /// The content type isn't `multipart/form-data` or has no valid boundary
+    InvalidContentType,
+
+    /// The body doesn't follow the `multipart/form-data` format
+    Malformed(&'static str),
+
+    /// The body has more parts than `MultipartLimits::max_parts`
+    TooManyParts { limit: usize },
+
+    /// A field or file is larger than `MultipartLimits::max_field_size` or
+    /// `MultipartLimits::max_file_size`
+    PartTooLarge { name: String, limit: u64 },
+
+    /// The body is larger than `MultipartLimits::max_total_size`
+    BodyTooLarge { limit: u64 },
+
+    /// A part's headers are larger than `MultipartLimits::max_header_size`
+    HeadersTooLarge { limit: usize },
+
+    /// Reading the body or writing a temporary file failed
+    Io(io::Error),
+}
+
+impl fmt::Display for MultipartError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MultipartError::InvalidContentType => {
+                write!(f, "content type is not multipart/form-data with a boundary")
+            }
+            MultipartError::Malformed(reason) => write!(f, "malformed multipart body: {}", reason),
+            MultipartError::TooManyParts { limit } => {
+                write!(f, "multipart body has more than {} parts", limit)
+            }
+            MultipartError::PartTooLarge { name, limit } => {
+                write!(f, "{}: part is larger than {} bytes", name, limit)
+            }
+            MultipartError::BodyTooLarge { limit } => {
+                write!(f, "multipart body is larger than {} bytes", limit)
+            }
+            MultipartError::HeadersTooLarge { limit } => {
+                write!(f, "multipart part headers are larger than {} bytes", limit)
+            }
+            MultipartError::Io(e) => write!(f, "failed to read multipart body: {}", e),
+        }
+    }
+}
+
+impl Error for MultipartError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MultipartError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MultipartError {
+    fn from(e: io::Error) -> MultipartError {
+        MultipartError::Io(e)
+    }
+}
+
+/// A file uploaded with a form.  Small files are held in memory and larger
+/// ones in a temporary file, removed when the last clone is dropped
+///
+/// The filename and content type are sent by the client and can't be
+/// trusted; check the contents before relying on either
+#[derive(Clone, Debug, PartialEq)]
+pub struct UploadedFile {
+    filename: Option<String>,
+    content_type: Option<String>,
+    size: u64,
+    body: Body,
+}
+
+#[derive(Clone, PartialEq)]
+enum Body {
+    Memory(Vec<u8>),
+    File(Arc<TempFile>),
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Body::Memory(bytes) => write!(f, "Memory({} bytes)", bytes.len()),
+            Body::File(temp) => f.debug_tuple("File").field(&temp.path).finish(),
+        }
+    }
+}
+
+impl UploadedFile {
+    /// Creates a file held in memory, e.g., to test a form handler
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - Name of the file, as sent by a client
+    /// * `content_type` - Content type of the file, as sent by a client
+    /// * `bytes` - Contents of the file
+    pub fn from_bytes<F, C, B>(
+        filename: Option<F>,
+        content_type: Option<C>,
+        bytes: B,
+    ) -> UploadedFile
+    where
+        F: Into<String>,
+        C: Into<String>,
+        B: Into<Vec<u8>>,
+    {
+        let bytes = bytes.into();
+        UploadedFile {
+            filename: filename.map(Into::into),
+            content_type: content_type.map(Into::into),
+            size: bytes.len() as u64,
+            body: Body::Memory(bytes),
+        }
+    }
+
+    /// Returns the name of the file as sent by the client, without any
+    /// directory it included
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    /// Returns the content type sent by the client
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Returns the size of the file, in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns true if the file is held in memory rather than a temporary file
+    pub fn is_in_memory(&self) -> bool {
+        match self.body {
+            Body::Memory(_) => true,
+            Body::File(_) => false,
+        }
+    }
+
+    /// Returns the path of the temporary file holding the upload, if it
+    /// wasn't kept in memory.  The file is removed when the last clone of
+    /// this `UploadedFile` is dropped; use `save` to keep it
+    pub fn path(&self) -> Option<&Path> {
+        match self.body {
+            Body::Memory(_) => None,
+            Body::File(ref temp) => Some(&temp.path),
+        }
+    }
+
+    /// Opens the contents of the file for reading
+    pub fn open(&self) -> io::Result<Box<dyn Read + '_>> {
+        Ok(match self.body {
+            Body::Memory(ref bytes) => Box::new(Cursor::new(bytes.as_slice())),
+            Body::File(ref temp) => Box::new(File::open(&temp.path)?),
+        })
+    }
+
+    /// Returns the contents of the file, reading it into memory if it was
+    /// written to a temporary file
+    pub fn bytes(&self) -> io::Result<Cow<'_, [u8]>> {
+        Ok(match self.body {
+            Body::Memory(ref bytes) => Cow::Borrowed(bytes.as_slice()),
+            Body::File(ref temp) => Cow::Owned(fs::read(&temp.path)?),
+        })
+    }
+
+    /// Copies the contents of the file to `path`
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Where to save the file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        match self.body {
+            Body::Memory(ref bytes) => fs::write(path, bytes),
+            Body::File(ref temp) => fs::copy(&temp.path, path).map(|_| ()),
+        }
+    }
+}
+
+/// A temporary file, removed when dropped
+#[derive(Debug, PartialEq)]
+struct TempFile {
+    path: PathBuf,
+}
+
+impl TempFile {
+    /// Creates a new, empty temporary file in `dir`
+    fn create(dir: &Path) -> io::Result<(TempFile, File)> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+
+        let mut attempts = 0;
+        loop {
+            let path = dir.join(format!(
+                "webforms-{}-{}-{}.upload",
+                std::process::id(),
+                nanos,
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => return Ok((TempFile { path }, file)),
+                Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists && attempts < 16 => {
+                    attempts += 1
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Holds a file while it is read, moving it from memory to a temporary file
+/// once it grows past the memory limit
+enum Spool {
+    Memory(Vec<u8>),
+    File(TempFile, File),
+}
+
+impl Spool {
+    fn write(&mut self, chunk: &[u8], limits: &MultipartLimits) -> io::Result<()> {
+        let spilled = match self {
+            Spool::Memory(ref mut bytes) if bytes.len() + chunk.len() <= limits.memory_limit => {
+                bytes.extend_from_slice(chunk);
+                None
+            }
+            Spool::Memory(ref bytes) => {
+                let dir = match limits.temp_dir {
+                    Some(ref dir) => dir.clone(),
+                    None => std::env::temp_dir(),
+                };
+                let (temp, mut file) = TempFile::create(&dir)?;
+                file.write_all(bytes)?;
+                file.write_all(chunk)?;
+                Some(Spool::File(temp, file))
+            }
+            Spool::File(_, ref mut file) => {
+                file.write_all(chunk)?;
+                None
+            }
+        };
+
+        if let Some(spool) = spilled {
+            *self = spool;
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> io::Result<Body> {
+        match self {
+            Spool::Memory(bytes) => Ok(Body::Memory(bytes)),
+            Spool::File(temp, mut file) => {
+                file.flush()?;
+                Ok(Body::File(Arc::new(temp)))
+            }
+        }
+    }
+}
+
+/// Headers of a single part
+struct PartHeaders {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+}
+
+/// Reads a body one part at a time
+struct Parser<'l, R> {
+    reader: R,
+    limits: &'l MultipartLimits,
+    delimiter: Vec<u8>,
+    buf: Vec<u8>,
+    read: u64,
+}
+
+impl<'l, R: Read> Parser<'l, R> {
+    fn new(reader: R, boundary: &str, limits: &'l MultipartLimits) -> Parser<'l, R> {
+        let mut delimiter = b"\r\n--".to_vec();
+        delimiter.extend_from_slice(boundary.as_bytes());
+
+        // The first delimiter isn't preceded by a line break.  Adding one
+        // lets every delimiter be found the same way
+        Parser {
+            reader,
+            limits,
+            delimiter,
+            buf: b"\r\n".to_vec(),
+            read: 0,
+        }
+    }
+
+    /// Reads the next chunk of the body into the buffer, returning false at
+    /// the end of the body
+    fn fill(&mut self) -> Result<bool, MultipartError> {
+        let start = self.buf.len();
+        self.buf.resize(start + CHUNK_SIZE, 0);
+
+        let read = loop {
+            match self.reader.read(&mut self.buf[start..]) {
+                Ok(read) => break read,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.buf.truncate(start);
+                    return Err(e.into());
+                }
+            }
+        };
+
+        self.buf.truncate(start + read);
+        self.read += read as u64;
+        if self.read > self.limits.max_total_size {
+            return Err(MultipartError::BodyTooLarge {
+                limit: self.limits.max_total_size,
+            });
+        }
+        Ok(read > 0)
+    }
+
+    /// Reads at least `len` bytes into the buffer
+    fn fill_to(&mut self, len: usize) -> Result<(), MultipartError> {
+        while self.buf.len() < len {
+            if !self.fill()? {
+                return Err(MultipartError::Malformed("unexpected end of body"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Passes everything up to the next delimiter to `sink`, consuming the
+    /// delimiter
+    fn read_to_delimiter<F>(&mut self, mut sink: F) -> Result<(), MultipartError>
+    where
+        F: FnMut(&[u8]) -> Result<(), MultipartError>,
+    {
+        loop {
+            if let Some(idx) = find(&self.buf, &self.delimiter) {
+                sink(&self.buf[..idx])?;
+                self.buf.drain(..idx + self.delimiter.len());
+                return Ok(());
+            }
+
+            // Hold back enough bytes to match a delimiter split across reads
+            let keep = self.delimiter.len() - 1;
+            if self.buf.len() > keep {
+                let len = self.buf.len() - keep;
+                sink(&self.buf[..len])?;
+                self.buf.drain(..len);
+            }
+
+            if !self.fill()? {
+                return Err(MultipartError::Malformed("unexpected end of body"));
+            }
+        }
+    }
+
+    /// Reads the rest of a delimiter line and the headers that follow it,
+    /// returning None after the closing delimiter
+    fn read_headers(&mut self) -> Result<Option<PartHeaders>, MultipartError> {
+        self.fill_to(2)?;
+        if self.buf.starts_with(b"--") {
+            return Ok(None);
+        }
+
+        let limit = self.limits.max_header_size;
+        let end = loop {
+            if let Some(idx) = find(&self.buf, b"\r\n\r\n") {
+                break idx;
+            }
+            if self.buf.len() > limit {
+                return Err(MultipartError::HeadersTooLarge { limit });
+            }
+            let len = self.buf.len() + 1;
+            self.fill_to(len)?;
+        };
+        if end > limit {
+            return Err(MultipartError::HeadersTooLarge { limit });
+        }
+
+        let block = String::from_utf8_lossy(&self.buf[..end]).into_owned();
+        self.buf.drain(..end + 4);
+
+        // The delimiter line may end with whitespace before its line break
+        let mut lines = block.split("\r\n");
+        if lines.next().is_some_and(|l| !l.trim().is_empty()) {
+            return Err(MultipartError::Malformed("text after a boundary"));
+        }
+
+        let mut disposition = None;
+        let mut content_type = None;
+        for line in lines {
+            let idx = line
+                .find(':')
+                .ok_or(MultipartError::Malformed("invalid part header"))?;
+            let (name, value) = (line[..idx].trim(), line[idx + 1..].trim());
+            if name.eq_ignore_ascii_case("content-disposition") {
+                disposition = Some(value.to_owned());
+            } else if name.eq_ignore_ascii_case("content-type") {
+                content_type = Some(value.to_owned());
+            }
+        }
+
+        let disposition = disposition.ok_or(MultipartError::Malformed(
+            "part without a content disposition",
+        ))?;
+        let params = parameters(&disposition);
+        if !params
+            .first()
+            .is_some_and(|(p, _)| p.eq_ignore_ascii_case("form-data"))
+        {
+            return Err(MultipartError::Malformed("part is not form-data"));
+        }
+
+        let param = |name: &str| {
+            params
+                .iter()
+                .skip(1)
+                .find(|(p, _)| p.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.clone())
+        };
+
+        Ok(Some(PartHeaders {
+            name: param("name").ok_or(MultipartError::Malformed("part without a name"))?,
+            filename: param("filename").map(|f| match f.rfind(['/', '\\']) {
+                Some(idx) => f[idx + 1..].to_owned(),
+                None => f,
+            }),
+            content_type,
+        }))
+    }
+
+    fn parse(mut self) -> Result<FormData, MultipartError> {
+        let limits = self.limits;
+        let mut data = FormData::new();
+        let mut parts = 0;
+
+        // Skip the preamble
+        self.read_to_delimiter(|_| Ok(()))?;
+
+        while let Some(headers) = self.read_headers()? {
+            parts += 1;
+            if parts > limits.max_parts {
+                return Err(MultipartError::TooManyParts {
+                    limit: limits.max_parts,
+                });
+            }
+
+            let limit = match headers.filename {
+                Some(_) => limits.max_file_size,
+                None => limits.max_field_size,
+            };
+            let is_file = headers.filename.is_some();
+            let mut size = 0u64;
+            let mut text = vec![];
+            let mut spool = Spool::Memory(vec![]);
+
+            self.read_to_delimiter(|chunk| {
+                size += chunk.len() as u64;
+                if size > limit {
+                    return Err(MultipartError::PartTooLarge {
+                        name: headers.name.clone(),
+                        limit,
+                    });
+                }
+
+                // Text fields are capped by `max_field_size` alone, so they
+                // never spill to disk whatever the memory limit
+                match is_file {
+                    true => spool.write(chunk, limits).map_err(MultipartError::from),
+                    false => {
+                        text.extend_from_slice(chunk);
+                        Ok(())
+                    }
+                }
+            })?;
+
+            match headers.filename {
+                // Browsers send an empty part when no file was chosen
+                Some(ref filename) if filename.is_empty() && size == 0 => {}
+                Some(filename) => data.push_file(
+                    headers.name,
+                    UploadedFile {
+                        filename: Some(filename).filter(|f| !f.is_empty()),
+                        content_type: headers.content_type,
+                        size,
+                        body: spool.finish()?,
+                    },
+                ),
+                None => data.push(headers.name, String::from_utf8_lossy(&text).into_owned()),
+            }
+        }
+
+        Ok(data)
+    }
+}
+
+impl FormData {
+    /// Parses a `multipart/form-data` body, reading it in chunks and enforcing
+    /// `limits`
+    ///
+    /// # Arguments
+    ///
+    /// * `content_type` - Value of the request's `Content-Type` header
+    /// * `body` - Request body
+    /// * `limits` - Limits enforced while parsing
+    pub fn from_multipart<R: Read>(
+        content_type: &str,
+        body: R,
+        limits: &MultipartLimits,
+    ) -> Result<FormData, MultipartError> {
+        let boundary = boundary(content_type).ok_or(MultipartError::InvalidContentType)?;
+        Parser::new(body, &boundary, limits).parse()
+    }
+}
+
+/// Returns the boundary of a `multipart/form-data` content type
+///
+/// # Arguments
+///
+/// * `content_type` - Value of a `Content-Type` header
+fn boundary(content_type: &str) -> Option<String> {
+    let params = parameters(content_type);
+    match params.first() {
+        Some((mime, _)) if mime.eq_ignore_ascii_case("multipart/form-data") => params
+            .iter()
+            .skip(1)
+            .find(|(p, _)| p.eq_ignore_ascii_case("boundary"))
+            .map(|(_, b)| b.clone())
+            .filter(|b| !b.is_empty() && b.len() <= 70),
+        _ => None,
+    }
+}
+
+/// Splits a header value into `;` separated parameters, unquoting quoted
+/// values.  The first entry is the value before any parameter (e.g.,
+/// `form-data`) with an empty value
+///
+/// # Arguments
+///
+/// * `header` - Header value to split
+fn parameters(header: &str) -> Vec<(String, String)> {
+    let mut params = vec![];
+    let mut chars = header.chars().peekable();
+
+    loop {
+        let mut name = String::new();
+        let mut has_value = false;
+        for c in chars.by_ref() {
+            match c {
+                ';' => break,
+                '=' if !params.is_empty() => {
+                    has_value = true;
+                    break;
+                }
+                c => name.push(c),
+            }
+        }
+
+        let mut value = String::new();
+        if has_value {
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+            }
+
+            // Browsers don't escape backslashes in filenames, so only an
+            // escaped quote or backslash is unescaped
+            let mut quoted = chars.peek() == Some(&'"');
+            if quoted {
+                chars.next();
+            }
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' if quoted => quoted = false,
+                    '\\' if quoted && matches!(chars.peek(), Some('"') | Some('\\')) => {
+                        value.extend(chars.next())
+                    }
+                    ';' if !quoted => break,
+                    c => value.push(c),
+                }
+            }
+        }
+
+        params.push((name.trim().to_owned(), value.trim().to_owned()));
+        if chars.peek().is_none() {
+            return params;
+        }
+    }
+}
+
+/// Returns the position of the first occurrence of `needle` in `haystack`
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse::{FormData, FromForm, MultipartError, MultipartLimits, UploadedFile};
+    use std::io::{self, Read};
+
+    const CONTENT_TYPE: &str = "multipart/form-data; boundary=XyZ";
+
+    /// Builds a body from `(name, filename, contents)` parts
+    fn body(parts: &[(&str, Option<&str>, &[u8])]) -> Vec<u8> {
+        let mut body = vec![];
+        for (name, filename, contents) in parts {
+            body.extend_from_slice(b"--XyZ\r\n");
+            match filename {
+                Some(filename) => body.extend_from_slice(
+                    format!(
+                        "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+                         Content-Type: application/octet-stream\r\n\r\n",
+                        name, filename
+                    )
+                    .as_bytes(),
+                ),
+                None => body.extend_from_slice(
+                    format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", name).as_bytes(),
+                ),
+            }
+            body.extend_from_slice(contents);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(b"--XyZ--\r\n");
+        body
+    }
+
+    /// Returns a few bytes at a time, so delimiters are split across reads
+    struct Trickle<'a>(&'a [u8]);
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.0.len()).min(3);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    fn parse(body: &[u8], limits: &MultipartLimits) -> Result<FormData, MultipartError> {
+        FormData::from_multipart(CONTENT_TYPE, Trickle(body), limits)
+    }
+
+    #[test]
+    fn test_from_multipart() {
+        let mut body = b"preamble\r\n".to_vec();
+        body.extend(body_with_parts());
+        body.extend_from_slice(b"epilogue");
+
+        let data = FormData::from_multipart(
+            "Multipart/Form-Data; charset=utf-8; boundary=\"XyZ\"",
+            Trickle(&body),
+            &MultipartLimits::default(),
+        )
+        .unwrap();
+
+        assert_eq!(data.get("name"), Some("Mike Smith"));
+        assert_eq!(data.get_all("tags"), vec!["a", "b"]);
+        assert_eq!(data.get("bio"), Some("line one\r\n--Xy\r\nline two"));
+        assert_eq!(data.len(), 5);
+
+        let file = data.get_file("avatar").unwrap();
+        assert_eq!(file.filename(), Some("me.png"));
+        assert_eq!(file.content_type(), Some("application/octet-stream"));
+        assert_eq!(file.size(), 6);
+        assert!(file.is_in_memory());
+        assert_eq!(&*file.bytes().unwrap(), b"\x89PNG\r\n");
+
+        // The empty part sent for a file input with nothing chosen is dropped
+        assert!(data.get_file("resume").is_none());
+    }
+
+    fn body_with_parts() -> Vec<u8> {
+        body(&[
+            ("name", None, b"Mike Smith"),
+            ("tags", None, b"a"),
+            ("bio", None, b"line one\r\n--Xy\r\nline two"),
+            ("tags", None, b"b"),
+            ("avatar", Some("C:\\Users\\mike\\me.png"), b"\x89PNG\r\n"),
+            ("resume", Some(""), b""),
+        ])
+    }
+
+    #[test]
+    fn test_temp_file() {
+        let limits = MultipartLimits {
+            memory_limit: 4,
+            ..MultipartLimits::default()
+        };
+        let data = parse(&body(&[("doc", Some("a.txt"), b"hello world")]), &limits).unwrap();
+
+        let file = data.get_file("doc").unwrap().clone();
+        assert!(!file.is_in_memory());
+        assert_eq!(file.size(), 11);
+
+        let path = file.path().unwrap().to_owned();
+        let mut contents = String::new();
+        file.open().unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "hello world");
+
+        // Removed once every clone is dropped
+        drop(data);
+        assert!(path.exists());
+        drop(file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_text_fields_stay_in_memory() {
+        // Only files spill to disk, so a text field larger than the memory
+        // limit is still read
+        let limits = MultipartLimits {
+            memory_limit: 16,
+            ..MultipartLimits::default()
+        };
+        let bio = [b'a'; 100];
+        let data = parse(&body(&[("bio", None, &bio)]), &limits).unwrap();
+        assert_eq!(data.get("bio").map(|b| b.len()), Some(100));
+    }
+
+    #[test]
+    fn test_limits() {
+        let body = body_with_parts();
+        let parse_with = |limits: MultipartLimits| parse(&body, &limits).unwrap_err().to_string();
+
+        assert_eq!(
+            parse_with(MultipartLimits {
+                max_parts: 3,
+                ..MultipartLimits::default()
+            }),
+            "multipart body has more than 3 parts"
+        );
+        assert_eq!(
+            parse_with(MultipartLimits {
+                max_field_size: 8,
+                ..MultipartLimits::default()
+            }),
+            "name: part is larger than 8 bytes"
+        );
+        assert_eq!(
+            parse_with(MultipartLimits {
+                max_file_size: 5,
+                ..MultipartLimits::default()
+            }),
+            "avatar: part is larger than 5 bytes"
+        );
+        assert_eq!(
+            parse_with(MultipartLimits {
+                max_total_size: 100,
+                ..MultipartLimits::default()
+            }),
+            "multipart body is larger than 100 bytes"
+        );
+        assert_eq!(
+            parse_with(MultipartLimits {
+                max_header_size: 16,
+                ..MultipartLimits::default()
+            }),
+            "multipart part headers are larger than 16 bytes"
+        );
+    }
+
+    #[test]
+    fn test_malformed() {
+        let limits = MultipartLimits::default();
+
+        let truncated = b"--XyZ\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nvalue";
+        let err = parse(truncated, &limits).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "malformed multipart body: unexpected end of body"
+        );
+
+        let attachment = b"--XyZ\r\nContent-Disposition: attachment; name=\"a\"\r\n\r\n\r\n--XyZ--";
+        let err = parse(attachment, &limits).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "malformed multipart body: part is not form-data"
+        );
+
+        let unnamed = b"--XyZ\r\nContent-Disposition: form-data\r\n\r\n\r\n--XyZ--";
+        let err = parse(unnamed, &limits).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "malformed multipart body: part without a name"
+        );
+
+        for content_type in &[
+            "application/x-www-form-urlencoded",
+            "multipart/form-data",
+            "multipart/form-data; boundary=\"\"",
+        ] {
+            match FormData::from_multipart(content_type, &b""[..], &limits) {
+                Err(MultipartError::InvalidContentType) => {}
+                other => panic!("unexpected result for {}: {:?}", content_type, other),
+            }
+        }
+    }
+
+    #[derive(Debug, FromForm)]
+    struct UploadForm {
+        pub title: String,
+        pub avatar: UploadedFile,
+        pub resume: Option<UploadedFile>,
+        pub attachments: Vec<UploadedFile>,
+    }
+
+    #[test]
+    fn test_from_form() {
+        let body = body(&[
+            ("title", None, b"Hello"),
+            ("avatar", Some("me.png"), b"png"),
+            ("resume", Some(""), b""),
+            ("attachments", Some("a.txt"), b"a"),
+            ("attachments", Some("b.txt"), b"b"),
+        ]);
+        let data = parse(&body, &MultipartLimits::default()).unwrap();
+        let form = UploadForm::from_form(&data).unwrap();
+
+        assert_eq!(form.title, "Hello");
+        assert_eq!(form.avatar.filename(), Some("me.png"));
+        assert!(form.resume.is_none());
+        let names: Vec<_> = form.attachments.iter().map(|f| f.filename()).collect();
+        assert_eq!(names, vec![Some("a.txt"), Some("b.txt")]);
+
+        let errs = UploadForm::from_urlencoded("title=Hello&avatar=me.png").unwrap_err();
+        let errs: Vec<_> = errs
+            .errors()
+            .iter()
+            .map(|e| (e.field(), e.code()))
+            .collect();
+        assert_eq!(errs, vec![("avatar", "invalid_value")]);
+
+        let mut data = FormData::new();
+        data.push("title", "Hello");
+        data.push_file(
+            "avatar",
+            UploadedFile::from_bytes(Some("me.png"), None::<String>, &b"png"[..]),
+        );
+        assert!(UploadForm::from_form(&data).is_ok());
+    }
+}
@@ -0,0 +1,217 @@
+//! Builds RFC 7807 `application/problem+json` documents from validation errors
+//!
+//! Requires the `serde` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use webforms::problem::Problem;
+//! use webforms::validate::ValidateError;
+//!
+//! let errors = vec![ValidateError::InvalidEmail { field: "email" }];
+//! let problem = Problem::validation(&errors).with_instance("/users");
+//!
+//! let body = serde_json::to_string(&problem).unwrap();
+//! assert!(body.contains(r#""invalid-params":[{"name":"email""#));
+//! ```
+
+use crate::locale::{Localize, MessageCatalog};
+use crate::validate::ValidateError;
+use serde::Serialize;
+
+/// Content type to use when returning a `Problem`
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// A single invalid parameter in a problem document
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct InvalidParam {
+    /// Name of the field that failed validation
+    pub name: String,
+
+    /// Human readable explanation of the failure
+    pub reason: String,
+
+    /// Machine-readable error code (e.g., "input_too_short")
+    pub code: String,
+}
+
+/// An RFC 7807 problem details document describing a failed submission
+#[derive(Clone, Debug, Serialize)]
+pub struct Problem {
+    /// URI identifying the problem type
+    #[serde(rename = "type")]
+    pub type_uri: String,
+
+    /// Short, human readable summary of the problem type
+    pub title: String,
+
+    /// HTTP status code
+    pub status: u16,
+
+    /// Explanation specific to this occurrence of the problem
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+
+    /// URI identifying this occurrence of the problem
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+
+    /// Every field that failed validation
+    #[serde(rename = "invalid-params")]
+    pub invalid_params: Vec<InvalidParam>,
+}
+
+impl Problem {
+    /// Creates a `400 Bad Request` problem listing each validation error
+    /// as an invalid parameter
+    ///
+    /// # Arguments
+    ///
+    /// * `errors` - Errors returned from `ValidateForm::validate`
+    pub fn validation(errors: &[ValidateError]) -> Problem {
+        Problem::new(
+            errors
+                .iter()
+                .map(|e| InvalidParam {
+                    name: e.field().to_owned(),
+                    reason: e.to_string(),
+                    code: e.code().to_owned(),
+                })
+                .collect(),
+        )
+    }
+
+    /// Same as `validation`, but each reason is translated using `catalog`
+    ///
+    /// # Arguments
+    ///
+    /// * `errors` - Errors returned from `ValidateForm::validate`
+    /// * `catalog` - Catalog to look up messages in
+    /// * `locale` - Locale identifier (e.g., "de-DE")
+    pub fn localized<C: MessageCatalog + ?Sized>(
+        errors: &[ValidateError],
+        catalog: &C,
+        locale: &str,
+    ) -> Problem {
+        Problem::new(
+            errors
+                .iter()
+                .map(|e| InvalidParam {
+                    name: e.field().to_owned(),
+                    reason: e.localize(catalog, locale),
+                    code: e.code().to_owned(),
+                })
+                .collect(),
+        )
+    }
+
+    fn new(invalid_params: Vec<InvalidParam>) -> Problem {
+        Problem {
+            type_uri: "about:blank".to_owned(),
+            title: "Your request parameters didn't validate.".to_owned(),
+            status: 400,
+            detail: None,
+            instance: None,
+            invalid_params,
+        }
+    }
+
+    /// Sets the URI identifying the problem type
+    pub fn with_type<S: Into<String>>(mut self, type_uri: S) -> Self {
+        self.type_uri = type_uri.into();
+        self
+    }
+
+    /// Sets the problem's title
+    pub fn with_title<S: Into<String>>(mut self, title: S) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Sets the HTTP status code (e.g., 422)
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Sets the occurrence-specific explanation
+    pub fn with_detail<S: Into<String>>(mut self, detail: S) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Sets the URI identifying this occurrence of the problem
+    pub fn with_instance<S: Into<String>>(mut self, instance: S) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::locale::FluentCatalog;
+    use crate::problem::Problem;
+    use crate::validate::ValidateError;
+    use serde_json::json;
+
+    fn errors() -> Vec<ValidateError> {
+        vec![
+            ValidateError::InputTooShort {
+                field: "password",
+                min: 8,
+            }
+            .with_message(Some("pw_short"), Some("At least {min} characters"), None),
+            ValidateError::InvalidEmail { field: "email" },
+        ]
+    }
+
+    #[test]
+    fn test_serialize_error() {
+        let value = serde_json::to_value(&errors()[0]).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "field": "password",
+                "code": "pw_short",
+                "message": "At least 8 characters",
+                "params": { "min": "8" },
+            })
+        );
+    }
+
+    #[test]
+    fn test_problem_document() {
+        let problem = Problem::validation(&errors())
+            .with_status(422)
+            .with_instance("/signup");
+
+        assert_eq!(
+            serde_json::to_value(&problem).unwrap(),
+            json!({
+                "type": "about:blank",
+                "title": "Your request parameters didn't validate.",
+                "status": 422,
+                "instance": "/signup",
+                "invalid-params": [
+                    { "name": "password", "reason": "At least 8 characters", "code": "pw_short" },
+                    { "name": "email", "reason": "email: not a valid email address", "code": "invalid_email" },
+                ],
+            })
+        );
+    }
+
+    #[test]
+    fn test_localized_problem() {
+        let mut catalog = FluentCatalog::new();
+        catalog
+            .add_resource("de", "invalid_email = Keine gültige E-Mail-Adresse")
+            .unwrap();
+
+        let problem = Problem::localized(&errors(), &catalog, "de");
+        assert_eq!(
+            problem.invalid_params[1].reason,
+            "Keine gültige E-Mail-Adresse"
+        );
+        assert_eq!(problem.invalid_params[0].reason, "At least 8 characters");
+    }
+}
@@ -0,0 +1,692 @@
+//! Forms defined at runtime rather than with the derive macros
+//!
+//! A `FormSchema` is a list of `FieldSchema`s, built in code or loaded from
+//! TOML or JSON.  Each field accepts the same rules as `#[validate(...)]`.  A
+//! schema validates a submission (a map of field names to submitted values),
+//! returning the same `ValidateError`s as ValidateForm, and renders through
+//! `HtmlFormBuilder` like HtmlForm.
+//!
+//! Requires the `schema` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use webforms::schema::{FieldSchema, FormSchema};
+//!
+//! let schema = FormSchema::new()
+//!     .field(FieldSchema::new("email").input_type("email").required().email())
+//!     .field(FieldSchema::new("age").input_type("number").min_value(18));
+//!
+//! let mut submission = HashMap::new();
+//! submission.insert("email".to_owned(), "mike@test.com".to_owned());
+//! submission.insert("age".to_owned(), "12".to_owned());
+//!
+//! let errors = schema.validate(&submission).unwrap_err();
+//! assert_eq!(errors[0].code(), "too_small");
+//! ```
+//!
+//! The same schema in TOML:
+//!
+//! ```toml
+//! [[fields]]
+//! name = "email"
+//! type = "email"
+//! required = true
+//! email = true
+//!
+//! [[fields]]
+//! name = "age"
+//! type = "number"
+//! min_value = 18
+//! ```
+
+use crate::html::{HtmlAttribute, HtmlFieldBuilder, HtmlFormBuilder};
+use crate::types::{EMAIL, PHONE, URL};
+use crate::validate::{ValidateError, ValidationMode};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Errors that can occur while loading a schema
+#[derive(Debug)]
+pub enum SchemaError {
+    /// The schema was not valid TOML
+    Toml(toml::de::Error),
+
+    /// The schema was not valid JSON
+    Json(serde_json::Error),
+
+    /// A field's `regex` failed to compile
+    Regex { field: String, error: regex::Error },
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchemaError::Toml(e) => write!(f, "invalid schema: {}", e),
+            SchemaError::Json(e) => write!(f, "invalid schema: {}", e),
+            SchemaError::Regex { field, error } => {
+                write!(f, "invalid regex for field `{}`: {}", field, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// Describes a single field of a runtime form along with its rules.  Rules
+/// are named after their `#[validate(...)]` counterparts
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldSchema {
+    /// Name the field is submitted, rendered and reported under
+    pub name: String,
+
+    /// Html input type (e.g., `text`, `number`, `email`).  Defaults to `text`
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub input_type: Option<String>,
+
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub required: bool,
+
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub not_blank: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<i64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<i64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_value: Option<i64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_value: Option<i64>,
+
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub email: bool,
+
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub phone: bool,
+
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub url: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+
+    /// Name of another field this field must equal (like `#[validate_match]`)
+    #[serde(rename = "match", default, skip_serializing_if = "Option::is_none")]
+    pub must_match: Option<String>,
+
+    /// Custom message applied to every rule on this field.  May reference
+    /// `{field}`, `{min}`, `{max}` and `{value}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// Custom code applied to every rule on this field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+impl FieldSchema {
+    /// Creates a new text field without any rules
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the field
+    pub fn new<S: Into<String>>(name: S) -> FieldSchema {
+        FieldSchema {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the html input type (e.g., `number`)
+    pub fn input_type<S: Into<String>>(mut self, ty: S) -> Self {
+        self.input_type = Some(ty.into());
+        self
+    }
+
+    /// Adds the `required` rule
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Adds the `not_blank` rule
+    pub fn not_blank(mut self) -> Self {
+        self.not_blank = true;
+        self
+    }
+
+    /// Adds the `min_length` rule
+    pub fn min_length(mut self, min: i64) -> Self {
+        self.min_length = Some(min);
+        self
+    }
+
+    /// Adds the `max_length` rule
+    pub fn max_length(mut self, max: i64) -> Self {
+        self.max_length = Some(max);
+        self
+    }
+
+    /// Adds the `min_value` rule
+    pub fn min_value(mut self, min: i64) -> Self {
+        self.min_value = Some(min);
+        self
+    }
+
+    /// Adds the `max_value` rule
+    pub fn max_value(mut self, max: i64) -> Self {
+        self.max_value = Some(max);
+        self
+    }
+
+    /// Adds the `email` rule
+    pub fn email(mut self) -> Self {
+        self.email = true;
+        self
+    }
+
+    /// Adds the `phone` rule
+    pub fn phone(mut self) -> Self {
+        self.phone = true;
+        self
+    }
+
+    /// Adds the `url` rule
+    pub fn url(mut self) -> Self {
+        self.url = true;
+        self
+    }
+
+    /// Adds the `regex` rule
+    pub fn regex<S: Into<String>>(mut self, regex: S) -> Self {
+        self.regex = Some(regex.into());
+        self
+    }
+
+    /// Requires this field to equal the field named `other`
+    pub fn must_match<S: Into<String>>(mut self, other: S) -> Self {
+        self.must_match = Some(other.into());
+        self
+    }
+
+    /// Sets a custom message for every rule on this field
+    pub fn message<S: Into<String>>(mut self, message: S) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Sets a custom code for every rule on this field
+    pub fn code<S: Into<String>>(mut self, code: S) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// Returns the html input type of this field
+    pub fn html_input_type(&self) -> &str {
+        match self.input_type {
+            Some(ref ty) => ty,
+            None if self.email => "email",
+            None if self.phone => "tel",
+            None if self.url => "url",
+            None => "text",
+        }
+    }
+}
+
+/// A form defined at runtime
+#[derive(Debug, Default)]
+pub struct FormSchema {
+    fields: Vec<FieldSchema>,
+    regexes: HashMap<String, Regex>,
+}
+
+/// Shape of a schema file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SchemaFile {
+    fields: Vec<FieldSchema>,
+}
+
+impl FormSchema {
+    /// Creates an empty schema
+    pub fn new() -> FormSchema {
+        FormSchema::default()
+    }
+
+    /// Adds a field to this schema
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - Field to add
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field's `regex` fails to compile.  Use `try_field` for
+    /// fields that are not known to be valid
+    pub fn field(self, field: FieldSchema) -> Self {
+        match self.try_field(field) {
+            Ok(schema) => schema,
+            Err(e) => panic!("WebForms: {}", e),
+        }
+    }
+
+    /// Adds a field to this schema, failing if its `regex` does not compile
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - Field to add
+    pub fn try_field(mut self, field: FieldSchema) -> Result<Self, SchemaError> {
+        if let Some(ref regex) = field.regex {
+            let compiled = Regex::new(regex).map_err(|error| SchemaError::Regex {
+                field: field.name.clone(),
+                error,
+            })?;
+            self.regexes.insert(field.name.clone(), compiled);
+        }
+
+        self.fields.push(field);
+        Ok(self)
+    }
+
+    /// Loads a schema from TOML, where each field is a `[[fields]]` table
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - TOML document
+    pub fn from_toml(s: &str) -> Result<FormSchema, SchemaError> {
+        let file: SchemaFile = toml::from_str(s).map_err(SchemaError::Toml)?;
+        FormSchema::from_fields(file.fields)
+    }
+
+    /// Loads a schema from JSON of the form `{"fields": [...]}`
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - JSON document
+    pub fn from_json(s: &str) -> Result<FormSchema, SchemaError> {
+        let file: SchemaFile = serde_json::from_str(s).map_err(SchemaError::Json)?;
+        FormSchema::from_fields(file.fields)
+    }
+
+    fn from_fields(fields: Vec<FieldSchema>) -> Result<FormSchema, SchemaError> {
+        fields
+            .into_iter()
+            .try_fold(FormSchema::new(), |schema, field| schema.try_field(field))
+    }
+
+    /// Returns the fields of this schema, in the order they were added
+    pub fn fields(&self) -> &[FieldSchema] {
+        &self.fields
+    }
+
+    /// Validates a submission, checking every rule on every field
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - Submitted values, keyed by field name
+    pub fn validate(&self, values: &HashMap<String, String>) -> Result<(), Vec<ValidateError>> {
+        self.validate_with(values, ValidationMode::All)
+    }
+
+    /// Validates a submission.  Missing fields are treated as empty, and
+    /// fields that are empty (but not `required`) skip their other rules
+    ///
+    /// # Arguments
+    ///
+    /// * `values` - Submitted values, keyed by field name
+    /// * `mode` - How many rules to check once validation starts failing
+    pub fn validate_with(
+        &self,
+        values: &HashMap<String, String>,
+        mode: ValidationMode,
+    ) -> Result<(), Vec<ValidateError>> {
+        let mut v = Vec::new();
+
+        for field in &self.fields {
+            let value = values.get(&field.name).map(|s| s.as_str()).unwrap_or("");
+            v.extend(self.check_field(field, value, values, mode));
+            if let ValidationMode::FailFast = mode {
+                if !v.is_empty() {
+                    return Err(v);
+                }
+            }
+        }
+
+        match v.len() {
+            0 => Ok(()),
+            _ => Err(v),
+        }
+    }
+
+    /// Returns every rule on `field` that `value` fails, or only the first
+    /// one unless `mode` checks all of them
+    fn check_field(
+        &self,
+        field: &FieldSchema,
+        value: &str,
+        values: &HashMap<String, String>,
+        mode: ValidationMode,
+    ) -> Vec<ValidateError> {
+        let present = !value.is_empty();
+        let length = value.len() as i64;
+        let number = || value.trim().parse::<i64>();
+        let numeric = present && (field.min_value.is_some() || field.max_value.is_some());
+
+        // Rules are checked lazily, so stopping at the first failure skips
+        // the rest.  Each reports under an empty name until `report` attaches
+        // the field's
+        let rules: [&dyn Fn() -> Option<ValidateError>; 12] = [
+            &|| {
+                let missing = field.required && !present;
+                failure(missing, ValidateError::Required { field: "" })
+            },
+            &|| {
+                let blank = present && field.not_blank && value.trim().is_empty();
+                failure(blank, ValidateError::Blank { field: "" })
+            },
+            &|| {
+                let min = field.min_length.filter(|min| present && length < *min)?;
+                Some(ValidateError::InputTooShort { field: "", min })
+            },
+            &|| {
+                let max = field.max_length.filter(|max| present && length > *max)?;
+                Some(ValidateError::InputTooLong { field: "", max })
+            },
+            &|| {
+                let invalid = numeric && number().is_err();
+                failure(invalid, ValidateError::InvalidCharacters { field: "" })
+            },
+            &|| {
+                let n = number().ok().filter(|_| numeric)?;
+                let min = field.min_value.filter(|min| n < *min)?;
+                Some(ValidateError::TooSmall { field: "", min })
+            },
+            &|| {
+                let n = number().ok().filter(|_| numeric)?;
+                let max = field.max_value.filter(|max| n > *max)?;
+                Some(ValidateError::TooLarge { field: "", max })
+            },
+            &|| {
+                let invalid = present && field.email && !EMAIL.is_match(value);
+                failure(invalid, ValidateError::InvalidEmail { field: "" })
+            },
+            &|| {
+                let invalid = present && field.phone && !PHONE.is_match(value);
+                failure(invalid, ValidateError::InvalidPhoneNumber { field: "" })
+            },
+            &|| {
+                let invalid = present && field.url && !URL.is_match(value);
+                failure(invalid, ValidateError::InvalidUrl { field: "" })
+            },
+            &|| {
+                let regex = self.regexes.get(&field.name).filter(|_| present)?;
+                let invalid = !regex.is_match(value);
+                failure(invalid, ValidateError::InvalidRegex { field: "" })
+            },
+            &|| {
+                let other = field.must_match.as_ref()?;
+                let other = values.get(other).map(|s| s.as_str()).unwrap_or("");
+                failure(value != other, ValidateError::FieldMismatch { field: "" })
+            },
+        ];
+
+        let errors = rules
+            .iter()
+            .filter_map(|rule| rule())
+            .map(|error| report(field, value, error));
+
+        match mode {
+            ValidationMode::All => errors.collect(),
+            _ => errors.take(1).collect(),
+        }
+    }
+
+    /// Builds a blank html form containing every field in this schema, with
+    /// html attributes matching the field's rules
+    pub fn form(&self) -> HtmlFormBuilder<'_> {
+        let mut form = HtmlFormBuilder::new();
+        for field in &self.fields {
+            form.add_field(field.name.as_str(), field_builder(field));
+        }
+        form
+    }
+}
+
+/// Builds the html input for a single field
+fn field_builder(field: &FieldSchema) -> HtmlFieldBuilder {
+    let mut attrs = HashSet::new();
+    attrs.insert(HtmlAttribute::new_pair("type", field.html_input_type()));
+
+    let pairs = [
+        ("minlength", field.min_length),
+        ("maxlength", field.max_length),
+        ("min", field.min_value),
+        ("max", field.max_value),
+    ];
+    for (attr, value) in pairs.iter() {
+        if let Some(value) = value {
+            attrs.insert(HtmlAttribute::new_pair(*attr, value.to_string()));
+        }
+    }
+
+    if let Some(ref regex) = field.regex {
+        attrs.insert(HtmlAttribute::new_pair("pattern", regex.as_str()));
+    }
+
+    if field.required {
+        attrs.insert(HtmlAttribute::new_single("required"));
+    }
+
+    HtmlFieldBuilder::with_attrs("input", Some(field.name.as_str()), attrs)
+}
+
+/// Returns `error` if `failed`
+fn failure(failed: bool, error: ValidateError) -> Option<ValidateError> {
+    match failed {
+        true => Some(error),
+        false => None,
+    }
+}
+
+/// Reports an error under `field`'s name, attaching the field's custom code
+/// and message if it has any
+fn report(field: &FieldSchema, value: &str, error: ValidateError) -> ValidateError {
+    let error = ValidateError::Renamed {
+        field: field.name.clone(),
+        error: Box::new(error),
+    };
+
+    if field.message.is_none() && field.code.is_none() {
+        return error;
+    }
+
+    error.with_message(
+        field.code.clone(),
+        field.message.as_deref(),
+        Some(value.to_owned()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn submission(values: &[(&str, &str)]) -> HashMap<String, String> {
+        values
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn fields(errs: &[ValidateError]) -> Vec<(&str, &str)> {
+        errs.iter().map(|e| (e.field(), e.code())).collect()
+    }
+
+    fn survey() -> FormSchema {
+        FormSchema::new()
+            .field(
+                FieldSchema::new("name")
+                    .required()
+                    .not_blank()
+                    .max_length(8),
+            )
+            .field(FieldSchema::new("email").email())
+            .field(
+                FieldSchema::new("age")
+                    .input_type("number")
+                    .min_value(18)
+                    .max_value(65),
+            )
+            .field(
+                FieldSchema::new("zip")
+                    .regex(r"^\d{5}$")
+                    .message("{value} is not a zip code"),
+            )
+            .field(FieldSchema::new("confirm").must_match("email"))
+    }
+
+    #[test]
+    fn test_builder_valid() {
+        let values = submission(&[
+            ("name", "Mike"),
+            ("email", "mike@test.com"),
+            ("age", "30"),
+            ("zip", "12345"),
+            ("confirm", "mike@test.com"),
+        ]);
+        assert!(survey().validate(&values).is_ok());
+
+        // Optional fields may be left out entirely
+        let values = submission(&[("name", "Mike")]);
+        assert!(survey().validate(&values).is_ok());
+    }
+
+    #[test]
+    fn test_builder_invalid() {
+        let values = submission(&[
+            ("name", "   "),
+            ("email", "mike"),
+            ("age", "old"),
+            ("zip", "1234"),
+        ]);
+        let errs = survey().validate(&values).unwrap_err();
+
+        assert_eq!(errs[3].to_string(), "1234 is not a zip code");
+        assert_eq!(
+            fields(&errs),
+            vec![
+                ("name", "blank"),
+                ("email", "invalid_email"),
+                ("age", "invalid_characters"),
+                ("zip", "invalid_regex"),
+                ("confirm", "field_mismatch"),
+            ]
+        );
+
+        let errs = survey()
+            .validate_with(&submission(&[]), ValidationMode::FailFast)
+            .unwrap_err();
+        assert_eq!(fields(&errs), vec![("name", "required")]);
+    }
+
+    #[test]
+    fn test_validation_modes() {
+        let schema = FormSchema::new()
+            .field(
+                FieldSchema::new("name")
+                    .not_blank()
+                    .max_length(2)
+                    .regex("^[a-z]+$"),
+            )
+            .field(
+                FieldSchema::new("nick")
+                    .min_length(3)
+                    .message("{field} is too short"),
+            );
+        let values = submission(&[("name", "    "), ("nick", "mo")]);
+
+        let errs = schema.validate(&values).unwrap_err();
+        assert_eq!(
+            fields(&errs),
+            vec![
+                ("name", "blank"),
+                ("name", "input_too_long"),
+                ("name", "invalid_regex"),
+                ("nick", "input_too_short"),
+            ]
+        );
+        assert_eq!(errs[3].to_string(), "nick is too short");
+
+        // Only the first failing rule of each field is checked
+        let errs = schema
+            .validate_with(&values, ValidationMode::FirstPerField)
+            .unwrap_err();
+        assert_eq!(
+            fields(&errs),
+            vec![("name", "blank"), ("nick", "input_too_short")]
+        );
+
+        let errs = schema
+            .validate_with(&values, ValidationMode::FailFast)
+            .unwrap_err();
+        assert_eq!(fields(&errs), vec![("name", "blank")]);
+        assert_eq!(errs[0].to_string(), "name: must not be blank");
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let schema = FormSchema::from_toml(
+            r#"
+            [[fields]]
+            name = "age"
+            type = "number"
+            required = true
+            min_value = 18
+            code = "too_young"
+            "#,
+        )
+        .unwrap();
+
+        let errs = schema.validate(&submission(&[("age", "12")])).unwrap_err();
+        assert_eq!(fields(&errs), vec![("age", "too_young")]);
+
+        let input = schema.form().builder("age").finish().to_string();
+        assert!(input.contains("type='number'"));
+        assert!(input.contains("min='18'"));
+        assert!(input.contains("required"));
+    }
+
+    #[test]
+    fn test_from_json() {
+        let schema =
+            FormSchema::from_json(r#"{"fields": [{"name": "site", "url": true}]}"#).unwrap();
+        assert_eq!(schema.fields()[0].html_input_type(), "url");
+
+        let errs = schema
+            .validate(&submission(&[("site", "site")]))
+            .unwrap_err();
+        assert_eq!(fields(&errs), vec![("site", "invalid_url")]);
+    }
+
+    #[test]
+    fn test_load_errors() {
+        match FormSchema::from_json(r#"{"fields": [{"name": "a", "regex": "("}]}"#) {
+            Err(SchemaError::Regex { field, .. }) => assert_eq!(field, "a"),
+            r => panic!("unexpected result: {:?}", r),
+        }
+
+        assert!(FormSchema::from_toml("[[fields]]\nname = \"a\"\nmin_lenght = 3").is_err());
+    }
+}
@@ -0,0 +1,944 @@
+//! Helpers for testing code that handles forms
+//!
+//! * [`assert_valid!`](crate::assert_valid) and
+//!   [`assert_invalid!`](crate::assert_invalid) check the outcome of validating
+//!   a form
+//! * [`Submission`] builds request bodies to send to form handlers
+//! * [`RenderedForm`] parses the HTML rendered for a form (e.g., by
+//!   `HtmlFormBuilder`) and looks up its fields by name
+//! * Forms deriving ValidateForm with `#[validate_config(arbitrary)]` implement
+//!   proptest's `Arbitrary`, generating forms that pass every rule described by
+//!   [`FormMeta`](crate::meta::FormMeta) (including regular expressions), and
+//!   [`ArbitraryForm`], generating forms with a single rule broken
+//!
+//! Requires the `testing` feature.
+//!
+//! # Assertions
+//!
+//! `assert_invalid!` lists the errors validation must report, in order, as
+//! field name and either a [`Code`] or a custom code.  A `Code` matches errors
+//! of that kind even when the rule set a custom code
+//!
+//! ```
+//! use lazy_static::lazy_static;
+//! use regex::Regex;
+//! use webforms::validate::{Code, ValidateError, ValidateForm};
+//! use webforms::{assert_invalid, assert_valid};
+//!
+//! #[derive(ValidateForm)]
+//! struct LoginForm<'a> {
+//!     #[validate(email)]
+//!     pub email: &'a str,
+//!
+//!     #[validate(min_length = 8, code = "pw_short")]
+//!     pub password: &'a str,
+//! }
+//!
+//! assert_valid!(LoginForm { email: "mike@test.com", password: "itsasecret" });
+//! assert_invalid!(
+//!     LoginForm { email: "mike", password: "secret" },
+//!     "email" => Code::InvalidEmail,
+//!     "password" => "pw_short",
+//! );
+//! ```
+//!
+//! # Property Testing
+//!
+//! Each [`Invalid`] case generated by [`ArbitraryForm::invalid`] records the
+//! field and error code validation must report, which [`Invalid::check`]
+//! verifies.  Both shrink like any other proptest strategy.
+//!
+//! Every field's type must implement [`FieldValue`] (strings, numbers, bools,
+//! `Vec<T>` and `Option<T>` of those) or `Arbitrary`, the form must implement
+//! `Debug`, and `validate_match` fields are generated as a copy of the field
+//! they match, so both must have the same type.  Patterns are generated with
+//! `proptest::string::string_regex` after removing a leading `^` and trailing
+//! `$`, so they can't contain other anchors.
+//!
+//! ## Example
+//!
+//! ```
+//! use lazy_static::lazy_static;
+//! use proptest::prelude::*;
+//! use regex::Regex;
+//! use webforms::testing::ArbitraryForm;
+//! use webforms::validate::{ValidateError, ValidateForm};
+//!
+//! #[derive(Debug, ValidateForm)]
+//! #[validate_config(arbitrary)]
+//! struct SignupForm {
+//!     #[validate(min_length = 3, max_length = 16, regex = r"^[a-z]+$")]
+//!     pub username: String,
+//!
+//!     #[validate(email)]
+//!     pub email: Option<String>,
+//!
+//!     #[validate(min_value = 18)]
+//!     pub age: u8,
+//! }
+//!
+//! // Add #[test] to each function in a test module
+//! proptest! {
+//!     fn valid_forms_pass(form in any::<SignupForm>()) {
+//!         prop_assert!(form.validate().is_ok());
+//!     }
+//!
+//!     fn invalid_forms_fail(case in SignupForm::invalid()) {
+//!         case.check()?;
+//!     }
+//! }
+//! # valid_forms_pass();
+//! # invalid_forms_fail();
+//! ```
+
+use crate::meta::{FieldMeta, Rule};
+use crate::validate::{
+    Code, ValidateError, ValidateForm, ValidationMode, EMAIL_PATTERN, PHONE_PATTERN, URL_PATTERN,
+};
+use proptest::prelude::*;
+use proptest::strategy::{BoxedStrategy, LazyJust, Union};
+use proptest::test_runner::TestCaseError;
+use regex::Regex;
+use std::fmt::{Debug, Display};
+
+#[cfg(feature = "html")]
+mod html;
+mod submission;
+
+#[cfg(feature = "html")]
+pub use self::html::{RenderedField, RenderedForm};
+pub use self::submission::Submission;
+
+// Generated code refers to proptest through this crate
+#[doc(hidden)]
+pub use proptest;
+
+/// Characters used for generated strings without a pattern, so their length
+/// in bytes and in characters match
+const ALPHANUMERIC: &str = "[a-zA-Z0-9]";
+
+/// Asserts that a form passes validation, listing every error otherwise
+///
+/// ```ignore
+/// assert_valid!(form);
+/// ```
+#[macro_export]
+macro_rules! assert_valid {
+    ($form:expr $(,)?) => {
+        $crate::testing::assert_valid(&$form)
+    };
+}
+
+/// Asserts that a form fails validation.  When errors are listed (as
+/// `field => code`), validation must report exactly those errors, in order.
+/// Codes are either a `webforms::validate::Code` or a custom code
+///
+/// ```ignore
+/// assert_invalid!(form);
+/// assert_invalid!(form, "email" => Code::InvalidEmail, "password" => "pw_short");
+/// ```
+#[macro_export]
+macro_rules! assert_invalid {
+    ($form:expr $(,)?) => {
+        $crate::testing::assert_invalid(&$form, &[])
+    };
+    ($form:expr, $($field:expr => $code:expr),+ $(,)?) => {
+        $crate::testing::assert_invalid(
+            &$form,
+            &[$(($field, &$code as &dyn $crate::testing::ExpectedCode)),+],
+        )
+    };
+}
+
+/// An error code expected by [`assert_invalid!`](crate::assert_invalid)
+pub trait ExpectedCode: Display {
+    /// Returns true if `error` has this code
+    fn matches(&self, error: &ValidateError) -> bool;
+}
+
+/// Matches errors of this kind, even if the rule set a custom code
+impl ExpectedCode for Code {
+    fn matches(&self, error: &ValidateError) -> bool {
+        error.kind() == *self
+    }
+}
+
+/// Matches errors reporting this code (custom or not)
+impl ExpectedCode for &str {
+    fn matches(&self, error: &ValidateError) -> bool {
+        error.code() == *self
+    }
+}
+
+/// Panics if a form fails validation.  Called by
+/// [`assert_valid!`](crate::assert_valid)
+///
+/// # Arguments
+/// * `form` - Form to validate
+#[track_caller]
+pub fn assert_valid<F: ValidateForm>(form: &F) {
+    if let Err(errors) = form.validate() {
+        panic!(
+            "expected the form to be valid, got:\n{}",
+            describe_errors(&errors)
+        );
+    }
+}
+
+/// Panics if a form passes validation, or if `expected` is not empty and
+/// doesn't match the errors reported.  Called by
+/// [`assert_invalid!`](crate::assert_invalid)
+///
+/// # Arguments
+/// * `form` - Form to validate
+/// * `expected` - Field name and code of each error expected, in order
+#[track_caller]
+pub fn assert_invalid<F: ValidateForm>(form: &F, expected: &[(&str, &dyn ExpectedCode)]) {
+    let errors = match form.validate() {
+        Ok(()) => panic!("expected the form to be invalid, but it passed validation"),
+        Err(errors) => errors,
+    };
+
+    if expected.is_empty() {
+        return;
+    }
+
+    let matches = errors.len() == expected.len()
+        && errors
+            .iter()
+            .zip(expected)
+            .all(|(error, (field, code))| error.field() == *field && code.matches(error));
+
+    if !matches {
+        let expected: Vec<String> = expected
+            .iter()
+            .map(|(field, code)| format!("  {}: {}", field, code))
+            .collect();
+
+        panic!(
+            "form reported the wrong errors\nexpected:\n{}\ngot:\n{}",
+            expected.join("\n"),
+            describe_errors(&errors)
+        );
+    }
+}
+
+/// Lists errors one per line as `field: code (message)`
+fn describe_errors(errors: &[ValidateError]) -> String {
+    let lines: Vec<String> = errors
+        .iter()
+        .map(|e| format!("  {}: {} ({})", e.field(), e.code(), e))
+        .collect();
+    lines.join("\n")
+}
+
+/// A form with a single rule broken, along with the error validating it must
+/// report
+#[derive(Clone, Debug)]
+pub struct Invalid<F> {
+    /// The invalid form
+    pub form: F,
+
+    /// Name of the field holding the invalid value
+    pub field: &'static str,
+
+    /// Code of the error expected for `field`
+    pub code: &'static str,
+}
+
+impl<F: ValidateForm> Invalid<F> {
+    /// Checks that validating the form (in `ValidationMode::All`) reports the
+    /// expected error.  Other errors may be reported as well, e.g., a value
+    /// that is too short may also fail a regex
+    pub fn check(&self) -> Result<(), TestCaseError> {
+        let errors = match self.form.validate_with(ValidationMode::All) {
+            Ok(()) => {
+                return Err(TestCaseError::fail(format!(
+                    "expected `{}` to fail with `{}`, but the form is valid",
+                    self.field, self.code
+                )))
+            }
+            Err(errors) => errors,
+        };
+
+        match errors
+            .iter()
+            .any(|e| e.field() == self.field && e.code() == self.code)
+        {
+            true => Ok(()),
+            false => Err(TestCaseError::fail(format!(
+                "expected `{}` to fail with `{}`, got {:?}",
+                self.field, self.code, errors
+            ))),
+        }
+    }
+}
+
+/// Generates forms with one rule broken.  Implemented by ValidateForm with
+/// `#[validate_config(arbitrary)]`
+pub trait ArbitraryForm: Arbitrary + ValidateForm + 'static {
+    /// Returns a strategy for each rule that can be broken, generating an
+    /// otherwise valid form with that rule broken
+    fn invalid_cases() -> Vec<BoxedStrategy<Invalid<Self>>>;
+
+    /// Returns a strategy generating forms with a single rule broken, chosen
+    /// from every rule on the form
+    ///
+    /// # Panics
+    ///
+    /// Panics if the form has no rules that can be broken
+    fn invalid() -> BoxedStrategy<Invalid<Self>> {
+        let cases = Self::invalid_cases();
+        if cases.is_empty() {
+            panic!("WebForms: form has no rules that can be broken");
+        }
+
+        Union::new(cases).boxed()
+    }
+}
+
+/// A type the fields of an arbitrary form can hold, generated according to
+/// the rules applied to the field
+pub trait FieldValue: Sized + Debug {
+    /// Returns a strategy generating values that satisfy every rule on a field
+    ///
+    /// # Arguments
+    /// * `field` - Field the value is generated for
+    fn valid(field: &'static FieldMeta) -> BoxedStrategy<Self>;
+
+    /// Returns a strategy generating values that break a rule, or None if
+    /// the rule can't be broken with this type
+    ///
+    /// # Arguments
+    /// * `field` - Field the value is generated for
+    /// * `rule` - Rule to break
+    fn invalid(field: &'static FieldMeta, rule: &Rule) -> Option<BoxedStrategy<Self>>;
+}
+
+impl FieldValue for String {
+    fn valid(field: &'static FieldMeta) -> BoxedStrategy<Self> {
+        let (min, max) = length_bounds(field);
+        let required = field
+            .rules()
+            .any(|r| *r == Rule::Required || *r == Rule::NotBlank);
+
+        let values = field.rules().find_map(|r| match *r {
+            Rule::OneOf(values) => Some(values),
+            _ => None,
+        });
+        let patterns: Vec<&str> = field.rules().filter_map(pattern).collect();
+
+        let strategy = match (values, patterns.first()) {
+            (Some(values), _) => proptest::sample::select(values)
+                .prop_map(str::to_owned)
+                .boxed(),
+            (None, Some(pattern)) => regex_strategy(pattern),
+            (None, None) => {
+                let min = min.max(required as usize);
+                let max = max.unwrap_or(min + 32);
+                regex_strategy(&format!("{}{{{},{}}}", ALPHANUMERIC, min, max))
+            }
+        };
+
+        let patterns: Vec<Regex> = patterns.iter().map(|p| compile(p)).collect();
+        strategy
+            .prop_filter("value must satisfy the field's rules", move |value| {
+                field.rules().all(|rule| satisfies(rule, value))
+                    && patterns.iter().all(|p| p.is_match(value))
+            })
+            .boxed()
+    }
+
+    fn invalid(field: &'static FieldMeta, rule: &Rule) -> Option<BoxedStrategy<Self>> {
+        // An empty value would fail `required` instead of the rule being broken
+        let min = field.rules().any(|r| *r == Rule::Required) as usize;
+
+        match *rule {
+            Rule::MinLength(len) if len <= min => None,
+            Rule::MinLength(len) => Some(regex_strategy(&format!(
+                "{}{{{},{}}}",
+                ALPHANUMERIC,
+                min,
+                len - 1
+            ))),
+            Rule::MaxLength(max) => Some(regex_strategy(&format!(
+                "{}{{{},{}}}",
+                ALPHANUMERIC,
+                max + 1,
+                max + 16
+            ))),
+            Rule::Required => Some(Just(String::new()).boxed()),
+            Rule::NotBlank => Some(regex_strategy(&format!("[ \t]{{{},4}}", min))),
+            Rule::OneOf(values) => Some(
+                regex_strategy(&format!("{}{{{},16}}", ALPHANUMERIC, min))
+                    .prop_filter("value must not be allowed", move |v| {
+                        !values.contains(&v.as_str())
+                    })
+                    .boxed(),
+            ),
+            ref rule => {
+                let pattern = compile(pattern(rule)?);
+                Some(
+                    regex_strategy(&format!("[a-zA-Z0-9 @.:/_+-]{{{},24}}", min))
+                        .prop_filter("value must not match the pattern", move |v| {
+                            !pattern.is_match(v)
+                        })
+                        .boxed(),
+                )
+            }
+        }
+    }
+}
+
+impl<T: FieldValue + 'static> FieldValue for Option<T> {
+    fn valid(field: &'static FieldMeta) -> BoxedStrategy<Self> {
+        match field.rules().any(|r| *r == Rule::Required) {
+            true => T::valid(field).prop_map(Some).boxed(),
+            false => proptest::option::weighted(0.75, T::valid(field)).boxed(),
+        }
+    }
+
+    fn invalid(field: &'static FieldMeta, rule: &Rule) -> Option<BoxedStrategy<Self>> {
+        match *rule {
+            Rule::Required => Some(LazyJust::new(|| None).boxed()),
+            _ => Some(T::invalid(field, rule)?.prop_map(Some).boxed()),
+        }
+    }
+}
+
+impl<T: Arbitrary + 'static> FieldValue for Vec<T> {
+    fn valid(field: &'static FieldMeta) -> BoxedStrategy<Self> {
+        let (min, max) = length_bounds(field);
+        let max = max.unwrap_or(min + 4);
+        proptest::collection::vec(any::<T>(), min..=max).boxed()
+    }
+
+    fn invalid(_: &'static FieldMeta, rule: &Rule) -> Option<BoxedStrategy<Self>> {
+        match *rule {
+            Rule::MinLength(min) if min > 0 => {
+                Some(proptest::collection::vec(any::<T>(), 0..min).boxed())
+            }
+            Rule::MaxLength(max) => {
+                Some(proptest::collection::vec(any::<T>(), max + 1..=max + 4).boxed())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl FieldValue for bool {
+    fn valid(_: &'static FieldMeta) -> BoxedStrategy<Self> {
+        any::<bool>().boxed()
+    }
+
+    fn invalid(_: &'static FieldMeta, _: &Rule) -> Option<BoxedStrategy<Self>> {
+        None
+    }
+}
+
+macro_rules! int_field_value {
+    ($($ty:ty),*) => {
+        $(
+            impl FieldValue for $ty {
+                fn valid(field: &'static FieldMeta) -> BoxedStrategy<Self> {
+                    let (min, max) = value_bounds(field);
+                    let min = min.map_or(<$ty>::MIN, |m| {
+                        m.ceil().max(<$ty>::MIN as f64).min(<$ty>::MAX as f64) as $ty
+                    });
+                    let max = max.map_or(<$ty>::MAX, |m| {
+                        m.floor().max(<$ty>::MIN as f64).min(<$ty>::MAX as f64) as $ty
+                    });
+
+                    match min <= max {
+                        true => (min..=max).boxed(),
+                        false => nothing(min),
+                    }
+                }
+
+                fn invalid(_: &'static FieldMeta, rule: &Rule) -> Option<BoxedStrategy<Self>> {
+                    match *rule {
+                        // Every value below the minimum
+                        Rule::MinValue(min) if min > <$ty>::MIN as f64 => {
+                            let end = min.ceil().min(<$ty>::MAX as f64) as $ty;
+                            Some((<$ty>::MIN..=end)
+                                .prop_filter("value must be below the minimum", move |v| (*v as f64) < min)
+                                .boxed())
+                        }
+                        // Every value above the maximum
+                        Rule::MaxValue(max) if max < <$ty>::MAX as f64 => {
+                            let start = max.floor().max(<$ty>::MIN as f64) as $ty;
+                            Some((start..=<$ty>::MAX)
+                                .prop_filter("value must be above the maximum", move |v| (*v as f64) > max)
+                                .boxed())
+                        }
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+int_field_value!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+/// Converts a bound to a float type, clamped to half of its range so the
+/// width of a range between two bounds stays finite
+macro_rules! float_bound {
+    ($ty:ty, $value:expr) => {
+        ($value)
+            .max(<$ty>::MIN as f64 / 2.0)
+            .min(<$ty>::MAX as f64 / 2.0) as $ty
+    };
+}
+
+macro_rules! float_field_value {
+    ($($ty:ty),*) => {
+        $(
+            impl FieldValue for $ty {
+                fn valid(field: &'static FieldMeta) -> BoxedStrategy<Self> {
+                    let (min, max) = match value_bounds(field) {
+                        (Some(min), Some(max)) => (min, max),
+                        (Some(min), None) => (min, min + 2e6),
+                        (None, Some(max)) => (max - 2e6, max),
+                        (None, None) => (-1e6, 1e6),
+                    };
+                    let (min, max) = (float_bound!($ty, min), float_bound!($ty, max));
+
+                    match min <= max {
+                        true => (min..=max).boxed(),
+                        false => nothing(min),
+                    }
+                }
+
+                fn invalid(_: &'static FieldMeta, rule: &Rule) -> Option<BoxedStrategy<Self>> {
+                    // Values up to 1e6 (or the bound's magnitude, if larger)
+                    // past the bound
+                    let (start, end, filter) = match *rule {
+                        Rule::MinValue(min) => (min - min.abs().max(1e6), min, min),
+                        Rule::MaxValue(max) => (max, max + max.abs().max(1e6), max),
+                        _ => return None,
+                    };
+                    let (start, end) = (float_bound!($ty, start), float_bound!($ty, end));
+                    if start >= end {
+                        return None;
+                    }
+
+                    let strategy = start..=end;
+                    Some(match *rule {
+                        Rule::MinValue(_) => strategy
+                            .prop_filter("value must be below the minimum", move |v| (*v as f64) < filter)
+                            .boxed(),
+                        _ => strategy
+                            .prop_filter("value must be above the maximum", move |v| (*v as f64) > filter)
+                            .boxed(),
+                    })
+                }
+            }
+        )*
+    };
+}
+
+float_field_value!(f32, f64);
+
+/// Returns the minimum and maximum length allowed on a field
+fn length_bounds(field: &FieldMeta) -> (usize, Option<usize>) {
+    let mut bounds = (0, None);
+    for rule in field.rules() {
+        match *rule {
+            Rule::MinLength(min) => bounds.0 = bounds.0.max(min),
+            Rule::MaxLength(max) => bounds.1 = Some(bounds.1.map_or(max, |m: usize| m.min(max))),
+            _ => {}
+        }
+    }
+    bounds
+}
+
+/// Returns the minimum and maximum value allowed on a field
+fn value_bounds(field: &FieldMeta) -> (Option<f64>, Option<f64>) {
+    let mut bounds = (None, None);
+    for rule in field.rules() {
+        match *rule {
+            Rule::MinValue(min) => bounds.0 = Some(bounds.0.map_or(min, |m: f64| m.max(min))),
+            Rule::MaxValue(max) => bounds.1 = Some(bounds.1.map_or(max, |m: f64| m.min(max))),
+            _ => {}
+        }
+    }
+    bounds
+}
+
+/// Returns a strategy rejecting every value, for fields whose rules leave
+/// no value valid (e.g., a minimum above the maximum)
+fn nothing<T: Clone + Debug + 'static>(value: T) -> BoxedStrategy<T> {
+    Just(value)
+        .prop_filter("no value satisfies the field's rules", |_| false)
+        .boxed()
+}
+
+/// Returns the pattern a rule requires values to match, if any
+fn pattern(rule: &Rule) -> Option<&'static str> {
+    match *rule {
+        Rule::Pattern(pattern) => Some(pattern),
+        Rule::Email => Some(EMAIL_PATTERN),
+        Rule::Phone => Some(PHONE_PATTERN),
+        Rule::Url => Some(URL_PATTERN),
+        _ => None,
+    }
+}
+
+/// Returns true if a string satisfies a rule.  Patterns are checked
+/// separately, and rules that don't apply to strings always pass
+fn satisfies(rule: &Rule, value: &str) -> bool {
+    match *rule {
+        Rule::MinLength(min) => value.len() >= min,
+        Rule::MaxLength(max) => value.len() <= max,
+        Rule::Required => !value.is_empty(),
+        Rule::NotBlank => !value.trim().is_empty(),
+        Rule::OneOf(values) => values.contains(&value),
+        _ => true,
+    }
+}
+
+/// Compiles a pattern from a field's rules
+fn compile(pattern: &str) -> Regex {
+    Regex::new(pattern).unwrap_or_else(|e| panic!("WebForms: invalid pattern `{}`: {}", pattern, e))
+}
+
+/// Returns a strategy generating strings matching a pattern.  A leading `^`
+/// and trailing `$` are removed, since proptest can't generate anchors
+fn regex_strategy(pattern: &str) -> BoxedStrategy<String> {
+    let unanchored = pattern.strip_prefix('^').unwrap_or(pattern);
+    let unanchored = match unanchored.ends_with('$') && !unanchored.ends_with("\\$") {
+        true => &unanchored[..unanchored.len() - 1],
+        false => unanchored,
+    };
+
+    proptest::string::string_regex(unanchored)
+        .unwrap_or_else(|e| {
+            panic!(
+                "WebForms: can't generate values for pattern `{}`: {}",
+                pattern, e
+            )
+        })
+        .boxed()
+}
+
+/// Support code for the derive macros, not public API.  Picks the strategy
+/// for a field: `FieldValue` if its type implements it (so the field's rules
+/// apply), otherwise `Arbitrary`
+#[doc(hidden)]
+pub mod __private {
+    use super::FieldValue;
+    use crate::meta::{FieldMeta, Rule};
+    use proptest::arbitrary::{any, Arbitrary};
+    use proptest::strategy::{BoxedStrategy, Strategy};
+    use std::marker::PhantomData;
+
+    pub struct Probe<T>(pub PhantomData<T>);
+
+    pub trait ViaFieldValue<T> {
+        fn webforms_valid(&self, field: &'static FieldMeta) -> BoxedStrategy<T>;
+        fn webforms_invalid(
+            &self,
+            field: &'static FieldMeta,
+            rule: &Rule,
+        ) -> Option<BoxedStrategy<T>>;
+    }
+
+    impl<T: FieldValue> ViaFieldValue<T> for &Probe<T> {
+        fn webforms_valid(&self, field: &'static FieldMeta) -> BoxedStrategy<T> {
+            T::valid(field)
+        }
+
+        fn webforms_invalid(
+            &self,
+            field: &'static FieldMeta,
+            rule: &Rule,
+        ) -> Option<BoxedStrategy<T>> {
+            T::invalid(field, rule)
+        }
+    }
+
+    pub trait ViaArbitrary<T> {
+        fn webforms_valid(&self, field: &'static FieldMeta) -> BoxedStrategy<T>;
+        fn webforms_invalid(
+            &self,
+            field: &'static FieldMeta,
+            rule: &Rule,
+        ) -> Option<BoxedStrategy<T>>;
+    }
+
+    impl<T: Arbitrary + 'static> ViaArbitrary<T> for Probe<T> {
+        fn webforms_valid(&self, _: &'static FieldMeta) -> BoxedStrategy<T> {
+            any::<T>().boxed()
+        }
+
+        fn webforms_invalid(&self, _: &'static FieldMeta, _: &Rule) -> Option<BoxedStrategy<T>> {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::meta::{Constraint, FieldMeta, Rule};
+    use crate::testing::{ArbitraryForm, FieldValue, Invalid};
+    use crate::validate::{Code, ValidateError, ValidateForm};
+    use lazy_static::lazy_static;
+    use proptest::prelude::*;
+    use proptest::test_runner::TestRunner;
+    use regex::Regex;
+
+    #[derive(Clone, Debug, ValidateForm)]
+    #[validate_config(arbitrary, mode = "fail_fast")]
+    struct SignupForm {
+        #[validate(min_length = 3, max_length = 12, regex = r"^[a-z][a-z0-9_]*$")]
+        pub username: String,
+
+        #[validate(required, email, code = "bad_email")]
+        pub email: Option<String>,
+
+        #[validate(min_value = 18, max_value = 120)]
+        pub age: u8,
+
+        #[validate(one_of("S", "M", "L"))]
+        pub size: String,
+
+        #[validate(not_blank)]
+        pub bio: Option<String>,
+
+        #[validate(min_length = 1)]
+        pub tags: Vec<u32>,
+
+        pub password: String,
+
+        #[validate_match(password)]
+        pub confirm: String,
+
+        pub newsletter: bool,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug, ValidateForm)]
+    #[validate_config(arbitrary)]
+    enum Contact {
+        Phone(#[validate(phone)] String),
+        Site {
+            #[validate(url)]
+            url: String,
+
+            #[validate(max_value = 10)]
+            rank: Option<i32>,
+        },
+    }
+
+    #[derive(Clone, Debug, ValidateForm)]
+    #[validate_config(arbitrary)]
+    struct ProfileForm {
+        #[validate(required, not_blank)]
+        pub bio: String,
+
+        #[validate(required, min_length = 4, regex = r"^[a-z]*$")]
+        pub nick: String,
+
+        #[validate(required, one_of("en", "de"))]
+        pub language: String,
+    }
+
+    proptest! {
+        #[test]
+        fn test_valid_forms(form in any::<SignupForm>()) {
+            prop_assert!(form.validate().is_ok(), "{:?}", form.validate());
+        }
+
+        #[test]
+        fn test_invalid_forms(case in SignupForm::invalid()) {
+            case.check()?;
+        }
+
+        #[test]
+        fn test_invalid_required_forms(case in ProfileForm::invalid()) {
+            // Broken rules other than `required` mustn't leave the field empty
+            case.check()?;
+        }
+
+        #[test]
+        fn test_valid_enum(contact in any::<Contact>()) {
+            prop_assert!(contact.validate().is_ok(), "{:?}", contact.validate());
+        }
+
+        #[test]
+        fn test_invalid_enum(case in Contact::invalid()) {
+            case.check()?;
+        }
+    }
+
+    #[test]
+    fn test_invalid_cases() {
+        // One case per rule
+        assert_eq!(SignupForm::invalid_cases().len(), 11);
+    }
+
+    #[test]
+    fn test_check_reports_missing_error() {
+        let form = SignupForm {
+            username: "mike".to_owned(),
+            email: Some("mike@test.com".to_owned()),
+            age: 30,
+            size: "M".to_owned(),
+            bio: None,
+            tags: vec![1],
+            password: "secret".to_owned(),
+            confirm: "secret".to_owned(),
+            newsletter: false,
+        };
+
+        let valid = Invalid {
+            form: form.clone(),
+            field: "age",
+            code: "too_small",
+        };
+        assert!(valid.check().is_err());
+
+        let wrong_code = Invalid {
+            form: SignupForm { age: 10, ..form },
+            field: "age",
+            code: "too_large",
+        };
+        assert!(wrong_code.check().is_err());
+    }
+
+    #[derive(ValidateForm)]
+    struct LoginForm<'a> {
+        #[validate(email, code = "bad_email")]
+        pub email: &'a str,
+
+        #[validate(min_length = 8)]
+        pub password: &'a str,
+    }
+
+    #[test]
+    fn test_assert_valid() {
+        assert_valid!(LoginForm {
+            email: "mike@test.com",
+            password: "itsasecret",
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "expected the form to be valid, got:\n  password: input_too_short")]
+    fn test_assert_valid_fails() {
+        assert_valid!(LoginForm {
+            email: "mike@test.com",
+            password: "secret",
+        });
+    }
+
+    #[test]
+    fn test_assert_invalid() {
+        let form = LoginForm {
+            email: "mike",
+            password: "secret",
+        };
+
+        assert_invalid!(form);
+        assert_invalid!(
+            form,
+            "email" => Code::InvalidEmail,
+            "password" => Code::InputTooShort,
+        );
+        assert_invalid!(form, "email" => "bad_email", "password" => "input_too_short");
+    }
+
+    #[test]
+    #[should_panic(expected = "expected the form to be invalid")]
+    fn test_assert_invalid_valid_form() {
+        assert_invalid!(LoginForm {
+            email: "mike@test.com",
+            password: "itsasecret",
+        });
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected:\n  email: invalid_email\n  password: input_too_short\ngot:\n  email: bad_email"
+    )]
+    fn test_assert_invalid_wrong_errors() {
+        let form = LoginForm {
+            email: "mike",
+            password: "itsasecret",
+        };
+
+        // The custom code doesn't match, and an error is missing
+        assert_invalid!(form, "email" => "invalid_email", "password" => Code::InputTooShort);
+    }
+
+    /// Returns a number field with the given rules, including ones the
+    /// attributes can't express (e.g., negative bounds)
+    fn number(rules: &[Rule]) -> &'static FieldMeta {
+        let constraints: Vec<_> = rules
+            .iter()
+            .map(|&rule| Constraint {
+                rule,
+                message: None,
+                code: None,
+            })
+            .collect();
+
+        Box::leak(Box::new(FieldMeta {
+            name: "value",
+            variant: None,
+            rust_type: "f64",
+            input_type: "number",
+            optional: false,
+            constraints: Box::leak(constraints.into_boxed_slice()),
+            nested: || None,
+        }))
+    }
+
+    #[test]
+    fn test_value_bounds() {
+        let mut runner = TestRunner::default();
+
+        // A maximum below the default minimum
+        let field = number(&[Rule::MaxValue(-5e6)]);
+        runner
+            .run(&f64::valid(field), |v| {
+                prop_assert!(v <= -5e6);
+                Ok(())
+            })
+            .unwrap();
+
+        // Bounds too close for f32 to tell apart from 1e6 below them
+        let rule = Rule::MinValue(1e20);
+        runner
+            .run(&f32::invalid(number(&[rule]), &rule).unwrap(), |v| {
+                prop_assert!((v as f64) < 1e20);
+                Ok(())
+            })
+            .unwrap();
+
+        // Bounds beyond the range of the type
+        let rule = Rule::MinValue(i64::MIN as f64 + 4096.0);
+        runner
+            .run(&i64::invalid(number(&[rule]), &rule).unwrap(), |v| {
+                prop_assert!((v as f64) < i64::MIN as f64 + 4096.0);
+                Ok(())
+            })
+            .unwrap();
+        let rule = Rule::MinValue(1e30);
+        assert!(u8::invalid(number(&[rule]), &rule).is_some());
+        let rule = Rule::MaxValue(-1.0);
+        assert!(u8::invalid(number(&[rule]), &rule).is_some());
+        let rule = Rule::MinValue(f64::MIN);
+        assert!(f64::invalid(number(&[rule]), &rule).is_none());
+
+        // No value is valid, so none is generated
+        let field = number(&[Rule::MinValue(10.2), Rule::MaxValue(10.8)]);
+        runner
+            .run(&u8::valid(field), |v| {
+                prop_assert!(false, "generated {}", v);
+                Ok(())
+            })
+            .unwrap();
+    }
+}
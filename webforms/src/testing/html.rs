@@ -0,0 +1,477 @@
+//! Parses rendered forms so tests can query their fields by name
+
+use crate::html::HtmlFormBuilder;
+use crate::testing::Submission;
+
+/// Fields of a rendered form: every `<input>`, `<select>`, `<textarea>` and
+/// `<button>` found in the HTML, in document order.  Attribute values may be
+/// quoted with `'` or `"` (or unquoted) and character references are decoded,
+/// so assertions don't depend on how the HTML was written
+///
+/// ```
+/// use webforms::testing::RenderedForm;
+///
+/// let form = RenderedForm::parse(
+///     "<input name='email' type='email' required>\
+///      <select name='size'><option value='S'>Small</option><option value='M' selected>Medium</option></select>",
+/// );
+///
+/// assert_eq!(form.field("email").input_type(), "email");
+/// assert!(form.field("email").has_attr("required"));
+/// assert_eq!(form.field("size").value(), Some("M"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct RenderedForm {
+    fields: Vec<RenderedField>,
+}
+
+/// A single field of a [`RenderedForm`]
+#[derive(Clone, Debug, Default)]
+pub struct RenderedField {
+    tag: String,
+    attrs: Vec<(String, Option<String>)>,
+    text: String,
+    options: Vec<RenderedField>,
+}
+
+impl RenderedForm {
+    /// Parses the fields out of rendered HTML.  Markup other than fields is
+    /// skipped
+    ///
+    /// # Arguments
+    ///
+    /// * `html` - Rendered form
+    pub fn parse<S: AsRef<str>>(html: S) -> RenderedForm {
+        let mut parser = Parser {
+            html: html.as_ref(),
+            pos: 0,
+        };
+        let mut fields: Vec<RenderedField> = vec![];
+        let mut select: Option<RenderedField> = None;
+
+        while let Some(tag) = parser.next_tag() {
+            match tag {
+                Tag::Start(field) => match field.tag.as_str() {
+                    "input" | "button" => fields.push(field),
+                    "textarea" => {
+                        let text = parser.text_until("textarea");
+                        fields.push(RenderedField { text, ..field });
+                    }
+                    "select" => select = Some(field),
+                    "option" => {
+                        let text = parser.text_until("option");
+                        if let Some(ref mut select) = select {
+                            select.options.push(RenderedField {
+                                text: text.trim().to_owned(),
+                                ..field
+                            });
+                        }
+                    }
+                    _ => {}
+                },
+                Tag::End(ref name) if name == "select" => fields.extend(select.take()),
+                Tag::End(_) => {}
+            }
+        }
+        fields.extend(select);
+
+        RenderedForm { fields }
+    }
+
+    /// Returns every field, in document order
+    pub fn fields(&self) -> &[RenderedField] {
+        &self.fields
+    }
+
+    /// Returns the first field with a name, or None if there isn't one
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Value of the field's name attribute
+    pub fn get<S: AsRef<str>>(&self, name: S) -> Option<&RenderedField> {
+        self.fields.iter().find(|f| f.name() == Some(name.as_ref()))
+    }
+
+    /// Returns the first field with a name
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Value of the field's name attribute
+    ///
+    /// # Panics
+    ///
+    /// Panics (listing the fields present) if there's no field with the name
+    #[track_caller]
+    pub fn field<S: AsRef<str>>(&self, name: S) -> &RenderedField {
+        match self.get(name.as_ref()) {
+            Some(field) => field,
+            None => {
+                let names: Vec<&str> = self.fields.iter().filter_map(|f| f.name()).collect();
+                panic!(
+                    "WebForms: no field named `{}` in the rendered form (found: {})",
+                    name.as_ref(),
+                    names.join(", ")
+                )
+            }
+        }
+    }
+
+    /// Returns the submission a browser would send for this form as
+    /// rendered: every named field's value, skipping disabled fields,
+    /// unchecked checkboxes and radio buttons, and buttons
+    pub fn submission(&self) -> Submission {
+        let mut submission = Submission::new();
+        for field in &self.fields {
+            let name = match field.name() {
+                Some(name) => name,
+                None => continue,
+            };
+
+            let skip = field.has_attr("disabled")
+                || field.tag == "button"
+                || ["submit", "reset", "button", "image", "file"].contains(&field.input_type())
+                || (["checkbox", "radio"].contains(&field.input_type()) && !field.is_checked());
+            if skip {
+                continue;
+            }
+
+            let value = match field.input_type() {
+                "checkbox" | "radio" => field.value().unwrap_or("on"),
+                _ => field.value().unwrap_or(""),
+            };
+            submission = submission.field(name, value);
+        }
+        submission
+    }
+}
+
+impl<'a, 'b> From<&'b HtmlFormBuilder<'a>> for RenderedForm {
+    /// Renders and parses a form builder
+    fn from(form: &'b HtmlFormBuilder<'a>) -> RenderedForm {
+        RenderedForm::parse(form.to_string())
+    }
+}
+
+impl RenderedField {
+    /// Returns the field's tag (e.g., `input` or `select`)
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Returns the field's name attribute
+    pub fn name(&self) -> Option<&str> {
+        self.attr("name")
+    }
+
+    /// Returns the value of an attribute, or None if the attribute is missing.
+    /// Attributes without a value (e.g., `required`) return an empty string
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the attribute (case-insensitive)
+    pub fn attr<S: AsRef<str>>(&self, name: S) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name.as_ref()))
+            .map(|(_, v)| v.as_ref().map_or("", |v| v.as_str()))
+    }
+
+    /// Returns true if the field has an attribute, with or without a value
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the attribute (case-insensitive)
+    pub fn has_attr<S: AsRef<str>>(&self, name: S) -> bool {
+        self.attr(name).is_some()
+    }
+
+    /// Returns the type of an `<input>` (`text` if not set), or the tag of
+    /// any other field
+    pub fn input_type(&self) -> &str {
+        match self.tag.as_str() {
+            "input" => self.attr("type").unwrap_or("text"),
+            tag => tag,
+        }
+    }
+
+    /// Returns the field's current value: the text of a `<textarea>`, the
+    /// selected option of a `<select>` (or its first option), or the value
+    /// attribute of any other field
+    pub fn value(&self) -> Option<&str> {
+        match self.tag.as_str() {
+            "textarea" => Some(&self.text),
+            "select" => self
+                .options
+                .iter()
+                .find(|o| o.has_attr("selected"))
+                .or_else(|| self.options.first())
+                .map(|o| o.attr("value").unwrap_or(&o.text)),
+            _ => self.attr("value"),
+        }
+    }
+
+    /// Returns true if a checkbox or radio button is checked
+    pub fn is_checked(&self) -> bool {
+        self.has_attr("checked")
+    }
+
+    /// Returns true if the field has the `required` attribute
+    pub fn is_required(&self) -> bool {
+        self.has_attr("required")
+    }
+
+    /// Returns the options of a `<select>`.  Use `attr`, `value` and
+    /// `has_attr("selected")` to inspect each one
+    pub fn options(&self) -> &[RenderedField] {
+        &self.options
+    }
+}
+
+/// A start or end tag
+enum Tag {
+    Start(RenderedField),
+    End(String),
+}
+
+/// Minimal HTML tokenizer, reading tags and the text of `<textarea>` and
+/// `<option>` elements
+struct Parser<'a> {
+    html: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Returns the next start or end tag, skipping text, comments and
+    /// doctypes
+    fn next_tag(&mut self) -> Option<Tag> {
+        loop {
+            let start = self.pos + self.html[self.pos..].find('<')?;
+            self.pos = start + 1;
+            let rest = &self.html[self.pos..];
+
+            if rest.starts_with("!--") {
+                self.pos = match rest.find("-->") {
+                    Some(end) => self.pos + end + 3,
+                    None => self.html.len(),
+                };
+            } else if rest.starts_with('/') {
+                self.pos += 1;
+                let name = self.name();
+                self.skip_past('>');
+                return Some(Tag::End(name));
+            } else if rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                let tag = self.name();
+                let attrs = self.attrs();
+                return Some(Tag::Start(RenderedField {
+                    tag,
+                    attrs,
+                    ..RenderedField::default()
+                }));
+            } else {
+                self.skip_past('>');
+            }
+        }
+    }
+
+    /// Reads a tag or attribute name, lowercased
+    fn name(&mut self) -> String {
+        let rest = &self.html[self.pos..];
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/' || c == '=')
+            .unwrap_or(rest.len());
+        self.pos += end;
+        rest[..end].to_ascii_lowercase()
+    }
+
+    /// Reads attributes up to and including the end of the start tag
+    fn attrs(&mut self) -> Vec<(String, Option<String>)> {
+        let mut attrs = vec![];
+        loop {
+            self.skip_while(|c| c.is_whitespace() || c == '/');
+            match self.html[self.pos..].chars().next() {
+                None => return attrs,
+                Some('>') => {
+                    self.pos += 1;
+                    return attrs;
+                }
+                Some(_) => {}
+            }
+
+            let name = self.name();
+            self.skip_while(char::is_whitespace);
+            if !self.html[self.pos..].starts_with('=') {
+                attrs.push((name, None));
+                continue;
+            }
+
+            self.pos += 1;
+            self.skip_while(char::is_whitespace);
+            let rest = &self.html[self.pos..];
+            let value = match rest.chars().next() {
+                Some(quote @ '\'') | Some(quote @ '"') => {
+                    let end = rest[1..].find(quote).map_or(rest.len(), |e| e + 1);
+                    self.pos += (end + 1).min(rest.len());
+                    &rest[1..end]
+                }
+                _ => {
+                    let end = rest
+                        .find(|c: char| c.is_whitespace() || c == '>')
+                        .unwrap_or(rest.len());
+                    self.pos += end;
+                    &rest[..end]
+                }
+            };
+            attrs.push((name, Some(decode(value))));
+        }
+    }
+
+    /// Reads the text up to the end tag of `tag` (or the next tag, for
+    /// elements like `<option>` whose end tag is optional)
+    fn text_until(&mut self, tag: &str) -> String {
+        let rest = &self.html[self.pos..];
+        let end = match tag {
+            "textarea" => rest
+                .to_ascii_lowercase()
+                .find("</textarea")
+                .unwrap_or(rest.len()),
+            _ => rest.find('<').unwrap_or(rest.len()),
+        };
+        self.pos += end;
+        decode(&rest[..end])
+    }
+
+    fn skip_while<F: Fn(char) -> bool>(&mut self, f: F) {
+        let rest = &self.html[self.pos..];
+        self.pos += rest.find(|c: char| !f(c)).unwrap_or(rest.len());
+    }
+
+    fn skip_past(&mut self, c: char) {
+        self.pos = match self.html[self.pos..].find(c) {
+            Some(end) => self.pos + end + c.len_utf8(),
+            None => self.html.len(),
+        };
+    }
+}
+
+/// Decodes the character references escaping sets in HTML, named (`&amp;`,
+/// `&lt;`, `&gt;`, `&quot;`, `&apos;`) and numeric (`&#39;`, `&#x27;`).
+/// Anything else is left as is
+fn decode(s: &str) -> String {
+    let mut decoded = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let reference = rest.find(';').map(|end| (&rest[1..end], end));
+        let c = reference.and_then(|(name, _)| match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ if name.starts_with("#x") || name.starts_with("#X") => {
+                u32::from_str_radix(&name[2..], 16)
+                    .ok()
+                    .and_then(std::char::from_u32)
+            }
+            _ if name.starts_with('#') => name[1..].parse().ok().and_then(std::char::from_u32),
+            _ => None,
+        });
+
+        match (c, reference) {
+            (Some(c), Some((_, end))) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html::HtmlForm;
+    use crate::testing::RenderedForm;
+
+    #[derive(HtmlForm)]
+    struct SignupForm {
+        #[form(min_length = 3)]
+        pub username: String,
+
+        #[form(email)]
+        pub email: String,
+    }
+
+    #[test]
+    fn test_parse() {
+        let form = RenderedForm::parse(
+            r#"<form method="post">
+                <!-- <input name="commented"> -->
+                <input name=username type="text" value='Mike &amp; &#39;Co&#x27;' required>
+                <input type="checkbox" name="terms" checked />
+                <input type="checkbox" name="news">
+                <input type="submit" value="Go">
+                <TEXTAREA name="bio">Hi <b>there</b> &lt;3</textarea>
+                <select name="size">
+                    <option value="S">Small
+                    <option selected>Medium</option>
+                </select>
+                <input name="nickname" disabled value="mo">
+            </form>"#,
+        );
+
+        let username = form.field("username");
+        assert_eq!(username.input_type(), "text");
+        assert_eq!(username.value(), Some("Mike & 'Co'"));
+        assert!(username.is_required());
+        assert_eq!(username.attr("REQUIRED"), Some(""));
+
+        assert!(form.get("commented").is_none());
+        assert!(form.field("terms").is_checked());
+        assert!(!form.field("news").is_checked());
+        assert_eq!(form.field("bio").tag(), "textarea");
+        assert_eq!(form.field("bio").value(), Some("Hi <b>there</b> <3"));
+        assert_eq!(form.field("size").options().len(), 2);
+        assert_eq!(form.field("size").value(), Some("Medium"));
+        assert_eq!(form.fields().len(), 7);
+
+        assert_eq!(
+            form.submission().to_urlencoded(),
+            "username=Mike+%26+%27Co%27&terms=on&bio=Hi+%3Cb%3Ethere%3C%2Fb%3E+%3C3&size=Medium"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "no field named `phone` in the rendered form (found: email)")]
+    fn test_missing_field() {
+        RenderedForm::parse("<input name='email'>").field("phone");
+    }
+
+    #[test]
+    fn test_from_builder() {
+        let form = SignupForm {
+            username: "mike".to_owned(),
+            email: "mike@test.com".to_owned(),
+        };
+        let form = form.form();
+        let rendered = RenderedForm::from(&form);
+
+        assert_eq!(rendered.field("username").attr("minlength"), Some("3"));
+        assert_eq!(rendered.field("email").input_type(), "email");
+        assert!(rendered.field("email").is_required());
+        assert_eq!(rendered.fields().len(), 2);
+
+        // Re-rendering keeps what was submitted
+        let submission = rendered.submission();
+        assert_eq!(submission.get("username"), Some("mike"));
+        assert_eq!(submission.get("email"), Some("mike@test.com"));
+    }
+}
@@ -0,0 +1,148 @@
+//! Builds form submissions to send to handlers under test
+
+use std::fmt::Display;
+
+/// A form submission, built one field at a time.  Fields are kept in the
+/// order they are added and a name may appear more than once (e.g., a group
+/// of checkboxes)
+///
+/// ```
+/// use webforms::testing::Submission;
+///
+/// let body = Submission::new()
+///     .field("email", "mike@test.com")
+///     .field("age", 30)
+///     .field("tags", "a&b")
+///     .to_urlencoded();
+///
+/// assert_eq!(body, "email=mike%40test.com&age=30&tags=a%26b");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Submission {
+    fields: Vec<(String, String)>,
+}
+
+impl Submission {
+    /// Creates an empty submission
+    pub fn new() -> Submission {
+        Submission { fields: vec![] }
+    }
+
+    /// Adds a value for a field, keeping any values already added for it
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the field
+    /// * `value` - Value submitted for the field
+    pub fn field<S: Into<String>, V: Display>(mut self, name: S, value: V) -> Self {
+        self.fields.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Replaces every value of a field with `value`, adding the field if it
+    /// isn't present.  Useful to break one field of an otherwise valid
+    /// submission
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the field
+    /// * `value` - Value submitted for the field
+    pub fn set<S: Into<String>, V: Display>(self, name: S, value: V) -> Self {
+        let name = name.into();
+        self.remove(&name).field(name, value)
+    }
+
+    /// Removes every value of a field, e.g., to test a missing field
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the field
+    pub fn remove<S: AsRef<str>>(mut self, name: S) -> Self {
+        self.fields.retain(|(n, _)| n != name.as_ref());
+        self
+    }
+
+    /// Returns the first value submitted for a field
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the field
+    pub fn get<S: AsRef<str>>(&self, name: S) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(n, _)| n == name.as_ref())
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Returns every field and value, in the order they were added
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+
+    /// Encodes the submission as an `application/x-www-form-urlencoded` body
+    pub fn to_urlencoded(&self) -> String {
+        let pairs: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(name, value)| format!("{}={}", urlencode(name), urlencode(value)))
+            .collect();
+        pairs.join("&")
+    }
+}
+
+/// Percent-encodes a name or value for a urlencoded body: spaces become `+`
+/// and every byte other than `A-Z a-z 0-9 * - . _` is escaped
+fn urlencode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'*' | b'-' | b'.' | b'_' => {
+                encoded.push(b as char)
+            }
+            b' ' => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::Submission;
+
+    #[test]
+    fn test_to_urlencoded() {
+        let submission = Submission::new()
+            .field("name", "Mike Smith")
+            .field("tags", "a")
+            .field("tags", "b")
+            .field("bio", "100% café\n");
+
+        assert_eq!(
+            submission.to_urlencoded(),
+            "name=Mike+Smith&tags=a&tags=b&bio=100%25+caf%C3%A9%0A"
+        );
+        assert_eq!(Submission::new().to_urlencoded(), "");
+    }
+
+    #[test]
+    fn test_set_and_remove() {
+        let submission = Submission::new()
+            .field("tags", "a")
+            .field("email", "mike@test.com")
+            .field("tags", "b")
+            .set("tags", "c")
+            .set("age", 30);
+
+        assert_eq!(submission.get("tags"), Some("c"));
+        assert_eq!(submission.get("age"), Some("30"));
+        assert_eq!(
+            submission.to_urlencoded(),
+            "email=mike%40test.com&tags=c&age=30"
+        );
+
+        let submission = submission.remove("email");
+        assert_eq!(submission.get("email"), None);
+        assert_eq!(submission.fields().len(), 2);
+    }
+}
@@ -0,0 +1,448 @@
+//! Types that can only hold valid input.  Each is built with `FromStr` or
+//! `TryFrom`, using the same checks as the matching ValidateForm rule, so a
+//! value that made it into one of these types never needs validating again.
+//!
+//! `BoundedString` and `Ranged` carry their limits in the type, e.g.
+//! `BoundedString<3, 32>` or `Ranged<u8, 1, 100>`.
+//!
+//! Parsing failures are reported as a `ValidateError` for the field `value`;
+//! use `ValidateError::with_field` to report it under a form field instead.
+//!
+//! # Example
+//!
+//! ```
+//! use std::convert::TryFrom;
+//! use webforms::types::Email;
+//!
+//! let email = Email::try_from("mike@test.com").unwrap();
+//! assert_eq!(email.as_str(), "mike@test.com");
+//!
+//! assert!("mike".parse::<Email>().is_err());
+//! ```
+
+use crate::validate::{ValidateError, EMAIL_PATTERN, PHONE_PATTERN, URL_PATTERN};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+lazy_static! {
+    pub(crate) static ref EMAIL: Regex =
+        Regex::new(EMAIL_PATTERN).expect("failed to compile regex");
+    pub(crate) static ref PHONE: Regex =
+        Regex::new(PHONE_PATTERN).expect("failed to compile regex");
+    pub(crate) static ref URL: Regex = Regex::new(URL_PATTERN).expect("failed to compile regex");
+}
+
+/// Implements the constructors and conversions shared by every string type
+/// in this module.  `$check` returns the error for invalid input
+macro_rules! string_type {
+    ($(#[$meta:meta])* $name:ident, $check:expr) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+        pub struct $name(String);
+
+        impl $name {
+            /// Returns the value as a string slice
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            /// Consumes the value, returning the underlying string
+            pub fn into_inner(self) -> String {
+                self.0
+            }
+
+            fn check(s: &str) -> Result<(), ValidateError> {
+                let check: fn(&str) -> Result<(), ValidateError> = $check;
+                check(s)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ValidateError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $name::check(s).map(|_| $name(s.to_owned()))
+            }
+        }
+
+        impl<'a> TryFrom<&'a str> for $name {
+            type Error = ValidateError;
+
+            fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+                s.parse()
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = ValidateError;
+
+            fn try_from(s: String) -> Result<Self, Self::Error> {
+                $name::check(&s).map(|_| $name(s))
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+
+        #[cfg(feature = "parse")]
+        impl crate::parse::FromFormField for $name {
+            fn from_field(field: &crate::parse::Field) -> Result<Self, ValidateError> {
+                field.parse()
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.0)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+                $name::try_from(s).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+string_type!(
+    /// An email address, checked like the `email` rule
+    Email,
+    |s| match EMAIL.is_match(s) {
+        true => Ok(()),
+        false => Err(ValidateError::InvalidEmail { field: "value" }),
+    }
+);
+
+string_type!(
+    /// A U.S. phone number, checked like the `phone` rule
+    PhoneNumber,
+    |s| match PHONE.is_match(s) {
+        true => Ok(()),
+        false => Err(ValidateError::InvalidPhoneNumber { field: "value" }),
+    }
+);
+
+string_type!(
+    /// An absolute http or https url, checked like the `url` rule
+    Url,
+    |s| match URL.is_match(s) {
+        true => Ok(()),
+        false => Err(ValidateError::InvalidUrl { field: "value" }),
+    }
+);
+
+string_type!(
+    /// A string containing at least one character
+    NonEmptyString,
+    |s| match s.is_empty() {
+        true => Err(ValidateError::Required { field: "value" }),
+        false => Ok(()),
+    }
+);
+
+/// A string whose length (in bytes, like the `min_length` and `max_length`
+/// rules) is between `MIN` and `MAX` inclusive
+///
+/// ```
+/// use webforms::types::BoundedString;
+///
+/// let name: BoundedString<3, 16> = "mike".parse().unwrap();
+/// assert!("mo".parse::<BoundedString<3, 16>>().is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BoundedString<const MIN: usize, const MAX: usize>(String);
+
+impl<const MIN: usize, const MAX: usize> BoundedString<MIN, MAX> {
+    /// Creates a new BoundedString, failing if the string is too short or too long
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - String to wrap
+    pub fn new<S: Into<String>>(s: S) -> Result<Self, ValidateError> {
+        let s = s.into();
+        if s.len() < MIN {
+            Err(ValidateError::InputTooShort {
+                field: "value",
+                min: MIN as i64,
+            })
+        } else if s.len() > MAX {
+            Err(ValidateError::InputTooLong {
+                field: "value",
+                max: MAX as i64,
+            })
+        } else {
+            Ok(BoundedString(s))
+        }
+    }
+
+    /// Returns the value as a string slice
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Consumes the value, returning the underlying string
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl<const MIN: usize, const MAX: usize> FromStr for BoundedString<MIN, MAX> {
+    type Err = ValidateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BoundedString::new(s)
+    }
+}
+
+impl<'a, const MIN: usize, const MAX: usize> TryFrom<&'a str> for BoundedString<MIN, MAX> {
+    type Error = ValidateError;
+
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        BoundedString::new(s)
+    }
+}
+
+impl<const MIN: usize, const MAX: usize> TryFrom<String> for BoundedString<MIN, MAX> {
+    type Error = ValidateError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        BoundedString::new(s)
+    }
+}
+
+impl<const MIN: usize, const MAX: usize> AsRef<str> for BoundedString<MIN, MAX> {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<const MIN: usize, const MAX: usize> fmt::Display for BoundedString<MIN, MAX> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(feature = "parse")]
+impl<const MIN: usize, const MAX: usize> crate::parse::FromFormField for BoundedString<MIN, MAX> {
+    fn from_field(field: &crate::parse::Field) -> Result<Self, ValidateError> {
+        field.parse()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<const MIN: usize, const MAX: usize> serde::Serialize for BoundedString<MIN, MAX> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const MIN: usize, const MAX: usize> serde::Deserialize<'de> for BoundedString<MIN, MAX> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+        BoundedString::new(s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// An integer of type `T` between `MIN` and `MAX` inclusive, like a field
+/// with both the `min_value` and `max_value` rules
+///
+/// ```
+/// use webforms::types::Ranged;
+///
+/// let qty: Ranged<u8, 1, 100> = Ranged::new(5).unwrap();
+/// assert_eq!(qty.get(), 5);
+/// assert!(Ranged::<i64, 1, 100>::new(0).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ranged<T, const MIN: i64, const MAX: i64>(T);
+
+impl<T: Copy + Into<i128>, const MIN: i64, const MAX: i64> Ranged<T, MIN, MAX> {
+    /// Creates a new Ranged value, failing if the value is out of range
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Value to wrap
+    pub fn new(value: T) -> Result<Self, ValidateError> {
+        let v: i128 = value.into();
+        if v < i128::from(MIN) {
+            Err(ValidateError::TooSmall {
+                field: "value",
+                min: MIN,
+            })
+        } else if v > i128::from(MAX) {
+            Err(ValidateError::TooLarge {
+                field: "value",
+                max: MAX,
+            })
+        } else {
+            Ok(Ranged(value))
+        }
+    }
+
+    /// Returns the wrapped value
+    pub fn get(self) -> T {
+        self.0
+    }
+}
+
+impl<T, const MIN: i64, const MAX: i64> FromStr for Ranged<T, MIN, MAX>
+where
+    T: Copy + Into<i128> + FromStr,
+{
+    type Err = ValidateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().parse() {
+            Ok(value) => Ranged::new(value),
+            Err(_) => Err(ValidateError::InvalidCharacters { field: "value" }),
+        }
+    }
+}
+
+#[cfg(feature = "parse")]
+impl<T, const MIN: i64, const MAX: i64> crate::parse::FromFormField for Ranged<T, MIN, MAX>
+where
+    T: Copy + Into<i128> + FromStr,
+{
+    fn from_field(field: &crate::parse::Field) -> Result<Self, ValidateError> {
+        field.parse()
+    }
+}
+
+impl<T: fmt::Display, const MIN: i64, const MAX: i64> fmt::Display for Ranged<T, MIN, MAX> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize, const MIN: i64, const MAX: i64> serde::Serialize for Ranged<T, MIN, MAX> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T, const MIN: i64, const MAX: i64> serde::Deserialize<'de> for Ranged<T, MIN, MAX>
+where
+    T: Copy + Into<i128> + serde::Deserialize<'de>,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = T::deserialize(deserializer)?;
+        Ranged::new(value).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_email() {
+        let email = Email::try_from("mike@test.com").unwrap();
+        assert_eq!(email.to_string(), "mike@test.com");
+        assert_eq!(
+            "mike@".parse::<Email>().unwrap_err().code(),
+            "invalid_email"
+        );
+    }
+
+    #[test]
+    fn test_phone() {
+        assert!(PhoneNumber::try_from("(555) 555-1234".to_owned()).is_ok());
+        assert_eq!(
+            "555".parse::<PhoneNumber>().unwrap_err().code(),
+            "invalid_phone_number"
+        );
+    }
+
+    #[test]
+    fn test_url() {
+        assert!("https://example.com/path?q=1".parse::<Url>().is_ok());
+        assert!("http://localhost:8080".parse::<Url>().is_ok());
+        assert_eq!(
+            "ftp://example.com".parse::<Url>().unwrap_err().code(),
+            "invalid_url"
+        );
+        assert!("https://".parse::<Url>().is_err());
+    }
+
+    #[test]
+    fn test_non_empty() {
+        assert_eq!(NonEmptyString::try_from("a").unwrap().into_inner(), "a");
+        assert_eq!(
+            NonEmptyString::try_from("")
+                .unwrap_err()
+                .with_field("name")
+                .field(),
+            "name"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let email: Email = serde_json::from_str("\"mike@test.com\"").unwrap();
+        assert_eq!(serde_json::to_string(&email).unwrap(), "\"mike@test.com\"");
+        assert!(serde_json::from_str::<Email>("\"mike\"").is_err());
+    }
+
+    #[test]
+    fn test_bounded_string() {
+        let name = BoundedString::<3, 5>::try_from("mike").unwrap();
+        assert_eq!(name.as_str(), "mike");
+        assert_eq!(
+            BoundedString::<3, 5>::new("mo").unwrap_err().code(),
+            "input_too_short"
+        );
+        assert_eq!(
+            "mikey!".parse::<BoundedString<3, 5>>().unwrap_err().code(),
+            "input_too_long"
+        );
+    }
+
+    #[test]
+    fn test_ranged() {
+        assert_eq!(Ranged::<u64, 1, 10>::new(10).unwrap().get(), 10);
+        assert_eq!(
+            Ranged::<i8, -5, 5>::new(-6).unwrap_err().code(),
+            "too_small"
+        );
+        assert_eq!(
+            Ranged::<u64, 1, 10>::new(u64::MAX).unwrap_err().code(),
+            "too_large"
+        );
+        assert_eq!(
+            "x".parse::<Ranged<u8, 1, 10>>().unwrap_err().code(),
+            "invalid_characters"
+        );
+        assert_eq!("7".parse::<Ranged<u8, 1, 10>>().unwrap().to_string(), "7");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_bounded_serde() {
+        assert!(serde_json::from_str::<BoundedString<1, 3>>("\"abcd\"").is_err());
+        assert!(serde_json::from_str::<Ranged<u8, 1, 3>>("4").is_err());
+
+        let qty: Ranged<u8, 1, 3> = serde_json::from_str("2").unwrap();
+        assert_eq!(serde_json::to_string(&qty).unwrap(), "2");
+    }
+}
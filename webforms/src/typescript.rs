@@ -0,0 +1,672 @@
+//! Generates TypeScript definitions and validators for forms
+//!
+//! `Form::typescript()` returns the source of a `.ts` module declaring an
+//! interface for the form (and every form nested in it) along with a
+//! `validate<Form>` function checking the same rules as the server, so
+//! single-page apps need not re-declare form payloads by hand:
+//!
+//! * `Option<T>` fields become optional properties
+//! * numbers become `number`, `bool` becomes `boolean`, `Vec<T>` becomes
+//!   `T[]` and other types become `string`
+//! * fields whose type is itself a form refer to that form's interface
+//! * enums follow serde's default (externally tagged) representation: a
+//!   union with one member per variant, an object whose only property is
+//!   named after the variant (e.g., `{ Card: PaymentCard } | { Invoice:
+//!   string }`).  Struct variants get an interface, a variant with a single
+//!   unnamed field holds that field's value and other tuple variants hold a
+//!   tuple
+//!
+//! The validators return an array of `{ field, code, message }`, using the
+//! same codes and messages (including custom ones) as `ValidateError`.  As on
+//! the server, the rules of nested forms are not checked by the form holding
+//! them.  String lengths are counted in UTF-8 bytes, like `str::len`.
+//!
+//! Requires the `typescript` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use webforms::typescript::TypeScript;
+//! use webforms::validate::{ValidateError, ValidateForm};
+//!
+//! #[derive(ValidateForm)]
+//! struct LoginForm {
+//!     #[validate(min_length = 4)]
+//!     pub username: String,
+//!
+//!     pub remember: Option<bool>,
+//! }
+//!
+//! let ts = LoginForm::typescript();
+//! assert!(ts.contains("export interface LoginForm {\n  username: string;\n  remember?: boolean;\n}"));
+//! assert!(ts.contains("export function validateLoginForm(form: LoginForm): ValidationError[]"));
+//! ```
+//!
+//! Several forms can be written to the same module with [`typescript`]:
+//!
+//! ```ignore
+//! let ts = webforms::typescript::typescript(&[LoginForm::form_info(), SignupForm::form_info()]);
+//! std::fs::write("frontend/src/forms.ts", ts)?;
+//! ```
+
+use crate::client::{default_message, js_string, regex_js};
+use crate::meta::{FieldMeta, FormInfo, FormMeta, Rule};
+use crate::validate::{interpolate, EMAIL_PATTERN, PHONE_PATTERN, URL_PATTERN};
+
+/// Generates TypeScript definitions for a form.  Implemented for every type
+/// implementing `FormMeta`
+pub trait TypeScript: FormMeta {
+    /// Returns a TypeScript module describing and validating this form, see
+    /// the module documentation
+    fn typescript() -> String {
+        typescript(&[Self::form_info()])
+    }
+}
+
+impl<T: FormMeta> TypeScript for T {}
+
+/// Declarations shared by every form in a module
+const PRELUDE: &str = r#"export interface ValidationError {
+  field: string;
+  code: string;
+  message: string;
+}
+
+function byteLength(value: string): number {
+  return new TextEncoder().encode(value).length;
+}
+
+function fail(errors: ValidationError[], field: string, code: string, message: string, value: unknown): void {
+  errors.push({ field, code, message: message.split("{value}").join(String(value)) });
+}
+"#;
+
+/// Returns a TypeScript module describing and validating each of the given
+/// forms, along with every form nested in them
+///
+/// # Arguments
+/// * `forms` - Forms to describe
+pub fn typescript(forms: &[FormInfo]) -> String {
+    let mut all = vec![];
+    for form in forms {
+        collect(*form, &mut all);
+    }
+
+    let names: Vec<_> = forms.iter().map(|f| f.name).collect();
+    let mut ts = format!(
+        "// TypeScript definitions for {}, generated by webforms\n\n{}",
+        names.join(", "),
+        PRELUDE
+    );
+
+    for form in all {
+        ts.push('\n');
+        ts.push_str(&declarations(form));
+        ts.push('\n');
+        ts.push_str(&validator(form));
+    }
+
+    ts
+}
+
+/// Adds a form to `all` after the forms nested in it, unless a form with the
+/// same name was already added
+///
+/// # Arguments
+/// * `form` - Form to add
+/// * `all` - Forms found so far
+fn collect(form: FormInfo, all: &mut Vec<FormInfo>) {
+    if all.iter().any(|f| f.name == form.name) {
+        return;
+    }
+
+    // Added before its fields so recursive forms terminate
+    all.push(form);
+    let index = all.len() - 1;
+
+    for field in form.fields {
+        if let Some(nested) = (field.nested)() {
+            collect(nested, all);
+        }
+    }
+
+    // Nested forms are declared first
+    let form = all.remove(index);
+    all.push(form);
+}
+
+/// Returns the variants of a form, in declaration order.  Structs have a
+/// single variant, `None`
+///
+/// # Arguments
+/// * `form` - Form to inspect
+fn variants(form: FormInfo) -> Vec<Option<&'static str>> {
+    let mut variants = vec![];
+    for field in form.fields {
+        if !variants.contains(&field.variant) {
+            variants.push(field.variant);
+        }
+    }
+    variants
+}
+
+/// Returns the fields of a variant (or struct), in declaration order
+///
+/// # Arguments
+/// * `form` - Form to inspect
+/// * `variant` - Variant whose fields are returned
+fn variant_fields(form: FormInfo, variant: Option<&str>) -> Vec<&'static FieldMeta> {
+    form.fields
+        .iter()
+        .filter(|f| f.variant == variant)
+        .collect()
+}
+
+/// Returns true if the fields belong to a tuple variant (or struct), i.e.
+/// they are named after their position
+///
+/// # Arguments
+/// * `fields` - Fields of the variant
+fn is_tuple(fields: &[&FieldMeta]) -> bool {
+    !fields.is_empty() && fields.iter().all(|f| f.name.parse::<usize>().is_ok())
+}
+
+/// Returns the interface (or, for enums, the interfaces and union type)
+/// describing a form.  Enums are externally tagged, as serde represents them
+/// by default
+///
+/// # Arguments
+/// * `form` - Form to describe
+fn declarations(form: FormInfo) -> String {
+    let variants = variants(form);
+    if let [] | [None] = variants.as_slice() {
+        return interface(form.name, form.fields.iter());
+    }
+
+    let mut ts = String::new();
+    let mut members = vec![];
+    for variant in variants {
+        let variant = variant.unwrap_or_default();
+        let fields = variant_fields(form, Some(variant));
+        let ty = match fields.as_slice() {
+            [field] if is_tuple(&fields) => value_type(field),
+            _ if is_tuple(&fields) => {
+                let types: Vec<_> = fields.iter().map(|f| value_type(f)).collect();
+                format!("[{}]", types.join(", "))
+            }
+            _ => {
+                let name = format!("{}{}", form.name, variant);
+                ts.push_str(&interface(&name, fields.into_iter()));
+                ts.push('\n');
+                name
+            }
+        };
+        members.push(format!("{{ {}: {} }}", property(variant), ty));
+    }
+
+    ts.push_str(&format!(
+        "export type {} = {};\n",
+        form.name,
+        members.join(" | ")
+    ));
+    ts
+}
+
+/// Returns an interface declaring the given fields
+///
+/// # Arguments
+/// * `name` - Name of the interface
+/// * `fields` - Fields to declare
+fn interface<'a, I: Iterator<Item = &'a FieldMeta>>(name: &str, fields: I) -> String {
+    let mut ts = format!("export interface {} {{\n", name);
+    for field in fields {
+        ts.push_str(&format!(
+            "  {}{}: {};\n",
+            property(field.name),
+            if field.optional { "?" } else { "" },
+            ts_type(field)
+        ));
+    }
+    ts.push_str("}\n");
+    ts
+}
+
+/// Returns the `validate<Form>` function checking the rules of a form.  The
+/// rules of an enum are chosen by the variant the value is tagged with
+///
+/// # Arguments
+/// * `form` - Form to validate
+fn validator(form: FormInfo) -> String {
+    let mut ts = format!(
+        "export function validate{0}(form: {0}): ValidationError[] {{\n  const errors: ValidationError[] = [];\n",
+        form.name
+    );
+
+    let variants = variants(form);
+    match variants.as_slice() {
+        [] | [None] => ts.push_str(&indent(&variant_checks(form, None), 1)),
+        _ => {
+            let mut branches = vec![];
+            for variant in variants {
+                let checks = variant_checks(form, variant);
+                if checks.is_empty() {
+                    continue;
+                }
+                branches.push(format!(
+                    "if ({} in form) {{\n{}}}",
+                    js_string(variant.unwrap_or_default()),
+                    indent(checks.trim_start(), 1)
+                ));
+            }
+            if !branches.is_empty() {
+                ts.push('\n');
+                ts.push_str(&indent(&branches.join(" else "), 1));
+            }
+        }
+    }
+
+    ts.push_str("\n  return errors;\n}\n");
+    ts
+}
+
+/// Returns the checks of every field of a variant (or struct) with rules,
+/// each preceded by a blank line
+///
+/// # Arguments
+/// * `form` - Form to validate
+/// * `variant` - Variant whose fields are checked
+fn variant_checks(form: FormInfo, variant: Option<&str>) -> String {
+    let fields = variant_fields(form, variant);
+    let target = match variant {
+        Some(variant) => access("form", variant),
+        None => "form".to_owned(),
+    };
+
+    fields
+        .iter()
+        .filter(|f| !f.constraints.is_empty())
+        .map(|field| {
+            // A variant with a single unnamed field holds the value itself
+            let value = match fields.len() == 1 && is_tuple(&fields) && variant.is_some() {
+                true => target.clone(),
+                false => access(&target, field.name),
+            };
+            format!("\n{}", field_checks(field, &value, &target))
+        })
+        .collect()
+}
+
+/// Returns the statements checking each rule on a field, appending any
+/// failures to `errors`.  As on the server, only `required` and
+/// `validate_match` apply to a missing optional value, and a failed
+/// `required` skips the other rules
+///
+/// # Arguments
+/// * `field` - Field to check
+/// * `value` - Expression reading the field
+/// * `target` - Expression holding the fields of the form or variant
+fn field_checks(field: &FieldMeta, value: &str, target: &str) -> String {
+    let mut ts = format!("const value = {};\n", value);
+
+    let mut rules = vec![];
+    let mut required = None;
+    for constraint in field.constraints {
+        let statement = format!(
+            "if ({}) {{\n  fail(errors, {}, {}, {}, value);\n}}\n",
+            condition(field, &constraint.rule, target),
+            js_string(field.name),
+            js_string(constraint.code()),
+            js_string(&message(field, constraint.message, &constraint.rule))
+        );
+
+        match constraint.rule {
+            Rule::Matches(_) => ts.push_str(&statement),
+            Rule::Required => required = Some(statement),
+            _ => rules.push(statement),
+        }
+    }
+
+    let rules = rules.concat();
+    match required {
+        Some(required) if rules.is_empty() => ts.push_str(&required),
+        Some(required) => {
+            ts.push_str(required.trim_end());
+            ts.push_str(" else {\n");
+            ts.push_str(&indent(&rules, 1));
+            ts.push_str("}\n");
+        }
+        None if rules.is_empty() => {}
+        None if field.optional => {
+            ts.push_str("if (value !== undefined && value !== null) {\n");
+            ts.push_str(&indent(&rules, 1));
+            ts.push_str("}\n");
+        }
+        None => ts.push_str(&rules),
+    }
+
+    format!("{{\n{}}}\n", indent(&ts, 1))
+}
+
+/// Returns the expression that is true when `value` fails a rule
+///
+/// # Arguments
+/// * `field` - Field being checked
+/// * `rule` - Rule to check
+/// * `target` - Expression holding the fields of the form or variant
+fn condition(field: &FieldMeta, rule: &Rule, target: &str) -> String {
+    match *rule {
+        Rule::MinLength(len) => format!("{} < {}", length(field), len),
+        Rule::MaxLength(len) => format!("{} > {}", length(field), len),
+        Rule::MinValue(min) => format!("value < {}", min),
+        Rule::MaxValue(max) => format!("value > {}", max),
+        Rule::Pattern(pattern) => format!("!{}.test(value)", regex_js(pattern)),
+        Rule::Email => format!("!{}.test(value)", regex_js(EMAIL_PATTERN)),
+        Rule::Phone => format!("!{}.test(value)", regex_js(PHONE_PATTERN)),
+        Rule::Url => format!("!{}.test(value)", regex_js(URL_PATTERN)),
+        Rule::Matches(other) => format!("value !== {}", access(target, other)),
+        Rule::Required => match (field.optional, ts_type(field) == "string") {
+            (true, true) => r#"value === undefined || value === null || value === """#.to_owned(),
+            (true, false) => "value === undefined || value === null".to_owned(),
+            (false, _) => r#"value === """#.to_owned(),
+        },
+        Rule::NotBlank => r#"value.trim() === """#.to_owned(),
+        Rule::OneOf(values) => {
+            let values: Vec<_> = values.iter().map(|v| js_string(v)).collect();
+            format!("![{}].includes(value)", values.join(", "))
+        }
+    }
+}
+
+/// Returns the expression for the length of `value`: its UTF-8 byte length
+/// for strings, as on the server, and its number of elements otherwise
+///
+/// # Arguments
+/// * `field` - Field being checked
+fn length(field: &FieldMeta) -> &'static str {
+    match ts_type(field).as_str() {
+        "string" => "byteLength(value)",
+        _ => "value.length",
+    }
+}
+
+/// Returns the message reported when a rule fails, with `{field}`, `{min}`
+/// and `{max}` filled in.  `{value}` is filled in when the check fails
+///
+/// # Arguments
+/// * `field` - Field being checked
+/// * `message` - Custom message, if any
+/// * `rule` - Rule that failed
+fn message(field: &FieldMeta, message: Option<&str>, rule: &Rule) -> String {
+    let mut params = vec![("field", field.name.to_owned())];
+    match *rule {
+        Rule::MinLength(len) => params.push(("min", len.to_string())),
+        Rule::MaxLength(len) => params.push(("max", len.to_string())),
+        Rule::MinValue(min) => params.push(("min", min.to_string())),
+        Rule::MaxValue(max) => params.push(("max", max.to_string())),
+        _ => {}
+    }
+
+    interpolate(message.unwrap_or_else(|| default_message(rule)), &params)
+}
+
+/// Returns the TypeScript type of a field's value
+///
+/// # Arguments
+/// * `field` - Field to inspect
+fn ts_type(field: &FieldMeta) -> String {
+    if let Some(form) = (field.nested)() {
+        return form.name.to_owned();
+    }
+
+    let ty = field.rust_type.trim();
+    let ty = match ty.starts_with("Option<") && ty.ends_with('>') {
+        true => &ty["Option<".len()..ty.len() - 1],
+        false => ty,
+    };
+
+    match rust_type(ty) {
+        "string" if field.input_type == "number" => "number".to_owned(),
+        _ => type_name(ty),
+    }
+}
+
+/// Returns the TypeScript type of a field held directly by a variant, i.e.
+/// not as a property, where a missing optional value is `null`
+///
+/// # Arguments
+/// * `field` - Field to inspect
+fn value_type(field: &FieldMeta) -> String {
+    match field.optional {
+        true => format!("{} | null", ts_type(field)),
+        false => ts_type(field),
+    }
+}
+
+/// Converts a Rust type to its TypeScript equivalent
+///
+/// # Arguments
+/// * `ty` - Rust type, as written in the struct
+fn type_name(ty: &str) -> String {
+    let ty = ty.trim().trim_start_matches('&');
+    if ty.starts_with("Vec<") && ty.ends_with('>') {
+        return format!("{}[]", type_name(&ty["Vec<".len()..ty.len() - 1]));
+    }
+    if ty.starts_with('[') && ty.ends_with(']') {
+        let inner = &ty[1..ty.len() - 1];
+        return format!("{}[]", type_name(inner.split(';').next().unwrap_or(inner)));
+    }
+
+    rust_type(ty).to_owned()
+}
+
+/// Returns the TypeScript type of a scalar Rust type
+///
+/// # Arguments
+/// * `ty` - Rust type, without any reference
+fn rust_type(ty: &str) -> &'static str {
+    match ty {
+        "bool" => "boolean",
+        "f32" | "f64" | "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32"
+        | "u64" | "u128" | "usize" => "number",
+        _ => "string",
+    }
+}
+
+/// Returns a field's name as an interface property, quoted unless it is a
+/// valid identifier
+///
+/// # Arguments
+/// * `name` - Name of the field
+fn property(name: &str) -> String {
+    match is_identifier(name) {
+        true => name.to_owned(),
+        false => js_string(name),
+    }
+}
+
+/// Returns the expression reading a field from `target`
+///
+/// # Arguments
+/// * `target` - Expression holding the field (e.g., `form`)
+/// * `name` - Name of the field
+fn access(target: &str, name: &str) -> String {
+    match is_identifier(name) {
+        true => format!("{}.{}", target, name),
+        false if name.parse::<usize>().is_ok() => format!("{}[{}]", target, name),
+        false => format!("{}[{}]", target, js_string(name)),
+    }
+}
+
+/// Returns true if `name` can be used as a property name without quotes
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' || c == '$' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        }
+        _ => false,
+    }
+}
+
+/// Indents every line of `code` by two spaces per level
+fn indent(code: &str, level: usize) -> String {
+    let prefix = "  ".repeat(level);
+    code.lines()
+        .map(|line| match line.is_empty() {
+            true => "\n".to_owned(),
+            false => format!("{}{}\n", prefix, line),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::meta::FormMeta;
+    use crate::typescript::{typescript, TypeScript};
+    use crate::validate::{ValidateError, ValidateForm};
+    use lazy_static::lazy_static;
+    use regex::Regex;
+
+    #[derive(ValidateForm)]
+    struct Address {
+        #[validate(max_length = 64)]
+        pub street: String,
+    }
+
+    #[derive(ValidateForm)]
+    #[form(rename_all = "camelCase")]
+    struct SignupForm {
+        #[validate(
+            min_length = 3,
+            message = "{field} needs {min} characters, not '{value}'"
+        )]
+        pub user_name: String,
+
+        #[validate(required, email)]
+        pub email: Option<String>,
+
+        #[validate(min_value = 18)]
+        pub age: Option<u8>,
+
+        pub password: String,
+
+        #[validate_match(password)]
+        pub confirm: String,
+
+        #[validate(one_of("S", "M", "L"))]
+        pub size: String,
+
+        pub tags: Vec<String>,
+
+        pub address: Address,
+    }
+
+    #[test]
+    fn test_interfaces() {
+        let ts = SignupForm::typescript();
+
+        assert!(ts.starts_with("// TypeScript definitions for SignupForm"));
+        assert!(ts.contains("export interface Address {\n  street: string;\n}\n"));
+        assert!(ts.contains(
+            "export interface SignupForm {
+  userName: string;
+  email?: string;
+  age?: number;
+  password: string;
+  confirm: string;
+  size: string;
+  tags: string[];
+  address: Address;
+}
+"
+        ));
+
+        // Nested forms are declared first
+        assert!(ts.find("interface Address").unwrap() < ts.find("interface SignupForm").unwrap());
+    }
+
+    #[test]
+    fn test_validator() {
+        let ts = SignupForm::typescript();
+
+        assert!(ts.contains(
+            r#"  {
+    const value = form.userName;
+    if (byteLength(value) < 3) {
+      fail(errors, "userName", "input_too_short", "userName needs 3 characters, not '{value}'", value);
+    }
+  }
+"#
+        ));
+        assert!(ts.contains(
+            r#"  {
+    const value = form.email;
+    if (value === undefined || value === null || value === "") {
+      fail(errors, "email", "required", "email: is required", value);
+    } else {
+      if (!new RegExp("#
+        ));
+        assert!(ts.contains(
+            r#"    if (value !== undefined && value !== null) {
+      if (value < 18) {
+        fail(errors, "age", "too_small", "age: input below required minimum. (18 minimum)", value);
+      }
+    }
+"#
+        ));
+        assert!(ts.contains(r#"    if (value !== form.password) {"#));
+        assert!(ts.contains(r#"    if (!["S", "M", "L"].includes(value)) {"#));
+        assert!(ts.contains("export function validateAddress(form: Address): ValidationError[]"));
+    }
+
+    #[allow(dead_code)]
+    #[derive(ValidateForm)]
+    enum Payment {
+        Card {
+            #[validate(min_length = 16)]
+            number: String,
+        },
+        Invoice(#[validate(email)] String),
+        Transfer(#[validate(min_length = 8)] String),
+        Cheque(String, #[validate(min_value = 1)] u32),
+    }
+
+    #[test]
+    fn test_enum() {
+        let ts = typescript(&[Payment::form_info()]);
+
+        // Enums are externally tagged, as serde serializes them
+        assert!(ts.contains("export interface PaymentCard {\n  number: string;\n}\n"));
+        assert!(!ts.contains("interface PaymentInvoice"));
+        assert!(ts.contains(
+            "export type Payment = { Card: PaymentCard } | { Invoice: string } | { Transfer: string } | { Cheque: [string, number] };\n"
+        ));
+
+        // Variants sharing a field name only check their own rules
+        assert!(ts.contains(
+            r#"  if ("Card" in form) {
+    {
+      const value = form.Card.number;
+      if (byteLength(value) < 16) {
+        fail(errors, "number", "input_too_short", "number: input too short. (16 min length)", value);
+      }
+    }
+  } else if ("Invoice" in form) {
+    {
+      const value = form.Invoice;
+      if (!new RegExp("#
+        ));
+        assert!(ts.contains(
+            r#"  } else if ("Transfer" in form) {
+    {
+      const value = form.Transfer;
+      if (byteLength(value) < 8) {"#
+        ));
+        assert!(ts.contains(
+            r#"  } else if ("Cheque" in form) {
+    {
+      const value = form.Cheque[1];
+      if (value < 1) {"#
+        ));
+    }
+}
@@ -10,13 +10,48 @@
 //! | regex | String | String |  Checks if input is a match against the supplied regex | 1 |
 //! | email | String | None | Checks if input matches an email address (via regex) | 1 |
 //! | phone | String | None | Checks if input matches a phone number (via regex) | 2 |
+//! | url | String | None | Checks if input is an http(s) url (via regex) | 1 |
 //! | min_value | Integer/Float | Integer/Float | Checks if input is greater than or equal to specified value | |
 //! | max_value | Integer/Float | Integer/Float | Checks if input is less than or euqal to specified value | |
+//! | required | Option/String | None | Checks if input is present (not `None`) and not empty | |
+//! | not_blank | String | None | Checks if input contains something other than whitespace | |
+//! | one_of | String | List of strings | Checks if input is one of the listed values, e.g. `one_of("S", "M", "L")` | |
+//!
+//! By default every rule is checked.  Use `#[validate_config(mode = "first_per_field")]`
+//! or `#[validate_config(mode = "fail_fast")]` on the struct, or call
+//! `validate_with(ValidationMode::...)`, to stop checking once a rule fails.
 //!
 //! Notes:
 //! * 1 - Requires crate to depend on `regex` and `lazy_static` crates and import them.  See below for example.
 //! * 2 - Currently only matches on US phone numbers
 //!
+//! Types that are validated the same way wherever they appear (e.g., a
+//! `Username` newtype) can implement [`Validate`], usually via
+//! `#[derive(Validate)]` using the same attributes.  ValidateForm checks every
+//! field whose (concrete) type implements it and reports its errors under the
+//! field's name, or their path from it (e.g., `address.city`) for a struct
+//! with several fields:
+//!
+//! ```ignore
+//! #[derive(Validate)]
+//! struct Username(#[validate(min_length = 3, max_length = 16)] String);
+//!
+//! #[derive(ValidateForm)]
+//! struct SignupForm {
+//!     pub username: Username,
+//! }
+//! ```
+//!
+//! Any `#[validate]`, `#[validate_match]` or `#[validate_regex]` attribute may also
+//! supply a `message` and/or `code` that are stored on the resulting error.  The
+//! message may reference `{field}`, `{min}`, `{max}` and `{value}`, which are
+//! filled in when validation fails:
+//!
+//! ```ignore
+//! #[validate(min_length = 8, message = "Password needs at least {min} characters", code = "pw_short")]
+//! pub password: String,
+//! ```
+//!
 //! # Example
 //!
 //! ```
@@ -47,12 +82,22 @@
 //! }
 //! ```
 
+use std::borrow::Cow;
 use std::fmt::{self, Display};
-// Import and re-export the macro
-pub use webforms_derive::ValidateForm;
+// Import and re-export the macros
+pub use webforms_derive::{Validate, ValidateForm};
+
+/// Pattern used by the `email` rule and `types::Email`
+pub const EMAIL_PATTERN: &str = r"^[a-zA-Z0-9_.+-]+@[a-zA-Z0-9-]+\.[a-zA-Z0-9-.]+$";
+
+/// Pattern used by the `phone` rule and `types::PhoneNumber` (U.S. numbers only)
+pub const PHONE_PATTERN: &str = r"^(\+\d{1,2}\s)?\(?\d{3}\)?[\s.-]?\d{3}[\s.-]?\d{4}$";
+
+/// Pattern used by the `url` rule and `types::Url` (absolute http and https urls)
+pub const URL_PATTERN: &str = r"^https?://[^\s/?#:@]+(:\d{1,5})?([/?#]\S*)?$";
 
 // Errors that can appear if validation fails
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ValidateError {
     /// Input was too short (< min_length)
     InputTooShort { field: &'static str, min: i64 },
@@ -75,64 +120,571 @@ pub enum ValidateError {
     /// The phone number entered does not match our regex
     InvalidPhoneNumber { field: &'static str },
 
+    /// The url entered does not match our url regex
+    InvalidUrl { field: &'static str },
+
     /// The field failed the user-passed regex
     InvalidRegex { field: &'static str },
 
     /// Two fields do not match
     FieldMismatch { field: &'static str },
+
+    /// A required field was missing (`None`) or empty
+    Required { field: &'static str },
+
+    /// Input contained only whitespace
+    Blank { field: &'static str },
+
+    /// Input was not one of the allowed values (one_of)
+    NotOneOf { field: &'static str },
+
+    /// A field was not submitted (see `parse::FromForm`)
+    Missing { field: &'static str },
+
+    /// A submitted value could not be parsed into the field's type, e.g.
+    /// `abc` for an integer.  `expected` describes the type (e.g., "an integer")
+    InvalidValue {
+        field: &'static str,
+        expected: &'static str,
+    },
+
+    /// An uploaded file was larger than the `file` rule's `max_size`, in bytes
+    FileTooLarge { field: &'static str, max: u64 },
+
+    /// An uploaded file's extension or detected type isn't allowed by the
+    /// `file` rule, or its type couldn't be detected
+    InvalidFileType { field: &'static str },
+
+    /// An uploaded image's dimensions are outside the `file` rule's limits
+    InvalidImageDimensions {
+        field: &'static str,
+        width: u32,
+        height: u32,
+    },
+
+    /// A rule failed and its attribute supplied a custom message and/or code
+    Custom {
+        error: Box<ValidateError>,
+        code: Option<Cow<'static, str>>,
+        message: Option<String>,
+    },
+
+    /// An error reported under a name built at runtime, e.g. the path of a
+    /// value nested in the field holding it (`address.city`)
+    Renamed {
+        field: String,
+        error: Box<ValidateError>,
+    },
+}
+
+impl ValidateError {
+    /// Returns the name of the field that failed validation, or its path
+    /// (e.g., `address.city`) if it is nested in another field
+    pub fn field(&self) -> &str {
+        match self {
+            ValidateError::InputTooShort { field, .. } => field,
+            ValidateError::InputTooLong { field, .. } => field,
+            ValidateError::TooSmall { field, .. } => field,
+            ValidateError::TooLarge { field, .. } => field,
+            ValidateError::InvalidCharacters { field } => field,
+            ValidateError::InvalidEmail { field } => field,
+            ValidateError::InvalidPhoneNumber { field } => field,
+            ValidateError::InvalidUrl { field } => field,
+            ValidateError::InvalidRegex { field } => field,
+            ValidateError::FieldMismatch { field } => field,
+            ValidateError::Required { field } => field,
+            ValidateError::Blank { field } => field,
+            ValidateError::NotOneOf { field } => field,
+            ValidateError::Missing { field } => field,
+            ValidateError::InvalidValue { field, .. } => field,
+            ValidateError::FileTooLarge { field, .. } => field,
+            ValidateError::InvalidFileType { field } => field,
+            ValidateError::InvalidImageDimensions { field, .. } => field,
+            ValidateError::Custom { error, .. } => error.field(),
+            ValidateError::Renamed { field, .. } => field,
+        }
+    }
+
+    /// Returns the kind of rule that failed, ignoring any custom code
+    pub fn kind(&self) -> Code {
+        match self {
+            ValidateError::InputTooShort { .. } => Code::InputTooShort,
+            ValidateError::InputTooLong { .. } => Code::InputTooLong,
+            ValidateError::TooSmall { .. } => Code::TooSmall,
+            ValidateError::TooLarge { .. } => Code::TooLarge,
+            ValidateError::InvalidCharacters { .. } => Code::InvalidCharacters,
+            ValidateError::InvalidEmail { .. } => Code::InvalidEmail,
+            ValidateError::InvalidPhoneNumber { .. } => Code::InvalidPhoneNumber,
+            ValidateError::InvalidUrl { .. } => Code::InvalidUrl,
+            ValidateError::InvalidRegex { .. } => Code::InvalidRegex,
+            ValidateError::FieldMismatch { .. } => Code::FieldMismatch,
+            ValidateError::Required { .. } => Code::Required,
+            ValidateError::Blank { .. } => Code::Blank,
+            ValidateError::NotOneOf { .. } => Code::NotOneOf,
+            ValidateError::Missing { .. } => Code::Missing,
+            ValidateError::InvalidValue { .. } => Code::InvalidValue,
+            ValidateError::FileTooLarge { .. } => Code::FileTooLarge,
+            ValidateError::InvalidFileType { .. } => Code::InvalidFileType,
+            ValidateError::InvalidImageDimensions { .. } => Code::InvalidImageDimensions,
+            ValidateError::Custom { error, .. } => error.kind(),
+            ValidateError::Renamed { error, .. } => error.kind(),
+        }
+    }
+
+    /// Returns a short, machine-readable code describing this error.  Custom
+    /// codes set via `#[validate(..., code = "...")]` take precedence
+    pub fn code(&self) -> &str {
+        match self {
+            ValidateError::Custom { error, code, .. } => match code {
+                Some(code) => code,
+                None => error.code(),
+            },
+            ValidateError::Renamed { error, .. } => error.code(),
+            _ => self.kind().as_str(),
+        }
+    }
+
+    /// Returns the custom message attached to this error, if one was set
+    /// via `#[validate(..., message = "...")]`
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            ValidateError::Custom { message, .. } => message.as_ref().map(|m| m.as_str()),
+            ValidateError::Renamed { error, .. } => error.message(),
+            _ => None,
+        }
+    }
+
+    /// Returns the parameters available for message interpolation
+    /// (e.g., `field`, `min`, `max`)
+    pub fn params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![("field", self.field().to_owned())];
+        match self {
+            ValidateError::InputTooShort { min, .. } | ValidateError::TooSmall { min, .. } => {
+                params.push(("min", min.to_string()))
+            }
+            ValidateError::InputTooLong { max, .. } | ValidateError::TooLarge { max, .. } => {
+                params.push(("max", max.to_string()))
+            }
+            ValidateError::InvalidValue { expected, .. } => {
+                params.push(("expected", (*expected).to_owned()))
+            }
+            ValidateError::FileTooLarge { max, .. } => params.push(("max", max.to_string())),
+            ValidateError::InvalidImageDimensions { width, height, .. } => {
+                params.push(("width", width.to_string()));
+                params.push(("height", height.to_string()));
+            }
+            ValidateError::Custom { error, .. } | ValidateError::Renamed { error, .. } => {
+                params.extend(error.params().into_iter().skip(1));
+            }
+            _ => {}
+        }
+        params
+    }
+
+    /// Attaches a custom code and/or message to this error.  Any `{field}`,
+    /// `{min}`, `{max}` or `{value}` placeholders in the message are replaced
+    /// with the values describing this failure
+    ///
+    /// # Arguments
+    ///
+    /// * `code` - Custom error code
+    /// * `message` - Message template to interpolate
+    /// * `value` - Value of the field that failed validation
+    pub fn with_message<C: Into<Cow<'static, str>>>(
+        self,
+        code: Option<C>,
+        message: Option<&str>,
+        value: Option<String>,
+    ) -> ValidateError {
+        let mut params = self.params();
+        if let Some(value) = value {
+            params.push(("value", value));
+        }
+
+        ValidateError::Custom {
+            message: message.map(|m| interpolate(m, &params)),
+            error: Box::new(self),
+            code: code.map(Into::into),
+        }
+    }
+
+    /// Reports this error under a different field.  Used to re-root the error
+    /// of a single value (e.g., a newtype's `0`) at the field holding it.  The
+    /// path of a nested error keeps everything after its first segment
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name of the field
+    pub fn with_field(mut self, name: &'static str) -> ValidateError {
+        self.set_field(name);
+        self
+    }
+
+    /// Reports this error as one of a value nested in the field `parent`,
+    /// prefixing its field with the parent's name (e.g., `city` becomes
+    /// `address.city`)
+    ///
+    /// # Arguments
+    ///
+    /// * `parent` - Name (or path) of the field holding the value
+    pub fn with_parent(self, parent: &str) -> ValidateError {
+        let field = format!("{}.{}", parent, self.field());
+        match self {
+            ValidateError::Renamed { error, .. } => ValidateError::Renamed { field, error },
+            error => ValidateError::Renamed {
+                field,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    fn set_field(&mut self, name: &'static str) {
+        match self {
+            ValidateError::InputTooShort { field, .. }
+            | ValidateError::InputTooLong { field, .. }
+            | ValidateError::TooSmall { field, .. }
+            | ValidateError::TooLarge { field, .. }
+            | ValidateError::InvalidCharacters { field }
+            | ValidateError::InvalidEmail { field }
+            | ValidateError::InvalidPhoneNumber { field }
+            | ValidateError::InvalidUrl { field }
+            | ValidateError::InvalidRegex { field }
+            | ValidateError::FieldMismatch { field }
+            | ValidateError::Required { field }
+            | ValidateError::Blank { field }
+            | ValidateError::NotOneOf { field }
+            | ValidateError::Missing { field }
+            | ValidateError::InvalidValue { field, .. }
+            | ValidateError::FileTooLarge { field, .. }
+            | ValidateError::InvalidFileType { field }
+            | ValidateError::InvalidImageDimensions { field, .. } => *field = name,
+            ValidateError::Custom { error, .. } => error.set_field(name),
+            ValidateError::Renamed { field, .. } => {
+                *field = match field.find('.') {
+                    Some(idx) => format!("{}{}", name, &field[idx..]),
+                    None => name.to_owned(),
+                }
+            }
+        }
+    }
+}
+
+/// Kinds of rules that can fail, i.e., a [`ValidateError`] without the field
+/// it was reported for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Code {
+    InputTooShort,
+    InputTooLong,
+    TooSmall,
+    TooLarge,
+    InvalidCharacters,
+    InvalidEmail,
+    InvalidPhoneNumber,
+    InvalidUrl,
+    InvalidRegex,
+    FieldMismatch,
+    Required,
+    Blank,
+    NotOneOf,
+    Missing,
+    InvalidValue,
+    FileTooLarge,
+    InvalidFileType,
+    InvalidImageDimensions,
+}
+
+impl Code {
+    /// Returns the code reported by errors of this kind without a custom code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Code::InputTooShort => "input_too_short",
+            Code::InputTooLong => "input_too_long",
+            Code::TooSmall => "too_small",
+            Code::TooLarge => "too_large",
+            Code::InvalidCharacters => "invalid_characters",
+            Code::InvalidEmail => "invalid_email",
+            Code::InvalidPhoneNumber => "invalid_phone_number",
+            Code::InvalidUrl => "invalid_url",
+            Code::InvalidRegex => "invalid_regex",
+            Code::FieldMismatch => "field_mismatch",
+            Code::Required => "required",
+            Code::Blank => "blank",
+            Code::NotOneOf => "not_one_of",
+            Code::Missing => "missing",
+            Code::InvalidValue => "invalid_value",
+            Code::FileTooLarge => "file_too_large",
+            Code::InvalidFileType => "invalid_file_type",
+            Code::InvalidImageDimensions => "invalid_image_dimensions",
+        }
+    }
+}
+
+impl Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::error::Error for ValidateError {}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ValidateError {
+    /// Serializes as `{field, code, message, params}`
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        use std::collections::BTreeMap;
+
+        let params: BTreeMap<_, _> = self
+            .params()
+            .into_iter()
+            .filter(|(k, _)| *k != "field")
+            .collect();
+
+        let mut s = serializer.serialize_struct("ValidateError", 4)?;
+        s.serialize_field("field", self.field())?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", &self.to_string())?;
+        s.serialize_field("params", &params)?;
+        s.end()
+    }
+}
+
+/// Replaces each `{name}` placeholder in `template` with its matching
+/// parameter value.  Unknown placeholders are left untouched
+///
+/// # Arguments
+///
+/// * `template` - Message containing placeholders
+/// * `params` - Name/value pairs to substitute
+pub fn interpolate<S: AsRef<str>>(template: &str, params: &[(S, String)]) -> String {
+    let mut message = template.to_owned();
+    for (name, value) in params {
+        message = message.replace(&format!("{{{}}}", name.as_ref()), value);
+    }
+    message
 }
 
 impl Display for ValidateError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.describe(f, self.field())
+    }
+}
+
+impl ValidateError {
+    /// Writes the default description of this error for `field`, which is
+    /// the error's own field unless it was renamed
+    fn describe(&self, f: &mut fmt::Formatter, field: &str) -> fmt::Result {
         match self {
-            ValidateError::InputTooShort { field, min } => {
+            ValidateError::InputTooShort { min, .. } => {
                 write!(f, "{}: input too short. ({} min length)", field, min)
             }
 
-            ValidateError::InputTooLong { field, max } => {
+            ValidateError::InputTooLong { max, .. } => {
                 write!(f, "{}: input too long. ({} max length)", field, max)
             }
-            ValidateError::TooSmall { field, min } => write!(
+            ValidateError::TooSmall { min, .. } => write!(
                 f,
                 "{}: input below required minimum. ({} minimum)",
                 field, min
             ),
-            ValidateError::TooLarge { field, max } => write!(
+            ValidateError::TooLarge { max, .. } => write!(
                 f,
                 "{}: input above maximum allowed. ({} maximum)",
                 field, max
             ),
-            ValidateError::InvalidCharacters { field } => {
+            ValidateError::InvalidCharacters { .. } => {
                 write!(f, "{}: contains invalid characters", field)
             }
-            ValidateError::InvalidEmail { field } => {
+            ValidateError::InvalidEmail { .. } => {
                 write!(f, "{}: not a valid email address", field)
             }
-            ValidateError::InvalidPhoneNumber { field } => {
+            ValidateError::InvalidPhoneNumber { .. } => {
                 write!(f, "{}: not a valid U.S. phone number", field)
             }
-            ValidateError::InvalidRegex { field } => {
+            ValidateError::InvalidUrl { .. } => write!(f, "{}: not a valid url", field),
+            ValidateError::InvalidRegex { .. } => {
                 write!(f, "{}: does not match required input", field)
             }
-            ValidateError::FieldMismatch { field } => {
+            ValidateError::FieldMismatch { .. } => {
                 write!(f, "{}: does not match other field", field)
             }
+            ValidateError::Required { .. } => write!(f, "{}: is required", field),
+            ValidateError::Blank { .. } => write!(f, "{}: must not be blank", field),
+            ValidateError::NotOneOf { .. } => {
+                write!(f, "{}: not one of the allowed values", field)
+            }
+            ValidateError::Missing { .. } => write!(f, "{}: is missing", field),
+            ValidateError::InvalidValue { expected, .. } => {
+                write!(f, "{}: expected {}", field, expected)
+            }
+            ValidateError::FileTooLarge { max, .. } => {
+                write!(f, "{}: file too large. ({} bytes max)", field, max)
+            }
+            ValidateError::InvalidFileType { .. } => {
+                write!(f, "{}: file type not allowed", field)
+            }
+            ValidateError::InvalidImageDimensions { width, height, .. } => write!(
+                f,
+                "{}: image dimensions not allowed. ({}x{})",
+                field, width, height
+            ),
+            ValidateError::Custom { error, message, .. } => match message {
+                Some(message) => write!(f, "{}", message),
+                None => error.describe(f, field),
+            },
+            ValidateError::Renamed { error, .. } => error.describe(f, field),
         }
     }
 }
 
+/// Controls how many rules are checked once validation starts failing.  Rules
+/// are checked in the order their attributes appear, so cheap checks (e.g.,
+/// `min_length`) placed before costly ones (e.g., `regex`) let the latter be
+/// skipped
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// Check every rule on every field and report all errors
+    #[default]
+    All,
+
+    /// Stop checking a field after its first failed rule
+    FirstPerField,
+
+    /// Stop validating the form after the first failed rule
+    FailFast,
+}
+
 /// Validates a form according to attributes set via #[validate] attribute
 /// on a given struct.  The attributes are set on the individual fields in
 /// a struct.
 pub trait ValidateForm {
     /// Performs form validation, retuns Ok if validation passed, or a vector
-    /// of errors if validation failed
+    /// of errors if validation failed.  Uses the mode set with
+    /// `#[validate_config(mode = "...")]`, or `ValidationMode::All`
     fn validate(&self) -> Result<(), Vec<ValidateError>>;
+
+    /// Same as `validate`, but overrides the struct's validation mode
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - How many rules to check once validation starts failing
+    fn validate_with(&self, _mode: ValidationMode) -> Result<(), Vec<ValidateError>> {
+        self.validate()
+    }
+}
+
+/// Validates a single value, independent of the form it appears in.  Usually
+/// derived with `#[derive(Validate)]` on a newtype, using the same attributes
+/// as ValidateForm.  Forms deriving ValidateForm check any field whose type
+/// implements this trait
+pub trait Validate {
+    /// Returns Ok if the value is valid, or a vector of errors otherwise
+    fn validate(&self) -> Result<(), Vec<ValidateError>>;
+
+    /// Same as `validate`, but overrides the value's validation mode
+    ///
+    /// # Arguments
+    ///
+    /// * `mode` - How many rules to check once validation starts failing
+    fn validate_with(&self, _mode: ValidationMode) -> Result<(), Vec<ValidateError>> {
+        self.validate()
+    }
+
+    /// Validates this value as the value of the field `field`, reporting
+    /// errors under their path in the form (e.g., `address.city`).  The
+    /// derive reports the errors of a newtype under `field` itself
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - Name of the field holding this value
+    /// * `mode` - How many rules to check once validation starts failing
+    fn validate_field(
+        &self,
+        field: &'static str,
+        mode: ValidationMode,
+    ) -> Result<(), Vec<ValidateError>> {
+        self.validate_with(mode)
+            .map_err(|errs| errs.into_iter().map(|e| e.with_parent(field)).collect())
+    }
+}
+
+impl<T: Validate> Validate for Option<T> {
+    fn validate(&self) -> Result<(), Vec<ValidateError>> {
+        match self {
+            Some(value) => value.validate(),
+            None => Ok(()),
+        }
+    }
+
+    fn validate_with(&self, mode: ValidationMode) -> Result<(), Vec<ValidateError>> {
+        match self {
+            Some(value) => value.validate_with(mode),
+            None => Ok(()),
+        }
+    }
+
+    fn validate_field(
+        &self,
+        field: &'static str,
+        mode: ValidationMode,
+    ) -> Result<(), Vec<ValidateError>> {
+        match self {
+            Some(value) => value.validate_field(field, mode),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Support code for the derive macros, not public API.  Lets ValidateForm
+/// call `Validate` on fields whose type implements it, and do nothing for
+/// other fields, without knowing the field types up front
+#[doc(hidden)]
+pub mod __private {
+    use super::{Validate, ValidateError, ValidationMode};
+
+    pub struct Nested<'a, T: ?Sized>(pub &'a T);
+
+    pub trait ViaValidate {
+        fn webforms_validate(
+            &self,
+            field: &'static str,
+            mode: ValidationMode,
+        ) -> Result<(), Vec<ValidateError>>;
+    }
+
+    impl<'a, T: Validate + ?Sized> ViaValidate for &Nested<'a, T> {
+        fn webforms_validate(
+            &self,
+            field: &'static str,
+            mode: ValidationMode,
+        ) -> Result<(), Vec<ValidateError>> {
+            self.0.validate_field(field, mode)
+        }
+    }
+
+    pub trait ViaNothing {
+        fn webforms_validate(
+            &self,
+            field: &'static str,
+            mode: ValidationMode,
+        ) -> Result<(), Vec<ValidateError>>;
+    }
+
+    impl<'a, T: ?Sized> ViaNothing for Nested<'a, T> {
+        fn webforms_validate(
+            &self,
+            _: &'static str,
+            _: ValidationMode,
+        ) -> Result<(), Vec<ValidateError>> {
+            Ok(())
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::validate::{ValidateError, ValidateForm};
+    use crate::validate::{Code, Validate, ValidateError, ValidateForm, ValidationMode};
+    use crate::{assert_invalid, assert_valid};
     use lazy_static::lazy_static;
     use regex::Regex;
 
@@ -198,8 +750,7 @@ mod tests {
             ..Default::default()
         };
 
-        let res = form.validate();
-        assert!(res.is_ok());
+        assert_valid!(form);
     }
 
     #[test]
@@ -209,15 +760,7 @@ mod tests {
             ..Default::default()
         };
 
-        let res = form.validate();
-        assert!(res.is_err());
-        let errs = res.unwrap_err();
-        assert_eq!(errs.len(), 1);
-
-        match errs[0] {
-            ValidateError::InputTooShort { field: _, min: _ } => {}
-            _ => panic!("Wrong Error for Too Short"),
-        }
+        assert_invalid!(form, "username" => Code::InputTooShort);
     }
 
     #[test]
@@ -227,15 +770,7 @@ mod tests {
             ..Default::default()
         };
 
-        let res = form.validate();
-        assert!(res.is_err());
-        let errs = res.unwrap_err();
-        assert_eq!(errs.len(), 1);
-
-        match errs[0] {
-            ValidateError::InputTooLong { field: _, max: _ } => {}
-            _ => panic!("Wrong Error for Too Long"),
-        }
+        assert_invalid!(form, "username" => Code::InputTooLong);
     }
 
     #[test]
@@ -245,15 +780,7 @@ mod tests {
             ..Default::default()
         };
 
-        let res = form.validate();
-        assert!(res.is_err());
-        let errs = res.unwrap_err();
-        assert_eq!(errs.len(), 1);
-
-        match errs[0] {
-            ValidateError::InvalidEmail { field: _ } => {}
-            _ => panic!("Wrong Error for Invalid Email"),
-        }
+        assert_invalid!(form, "email" => Code::InvalidEmail);
     }
 
     #[test]
@@ -264,15 +791,7 @@ mod tests {
             ..Default::default()
         };
 
-        let res = form.validate();
-        assert!(res.is_err());
-        let errs = res.unwrap_err();
-        assert_eq!(errs.len(), 1);
-
-        match errs[0] {
-            ValidateError::InvalidRegex { field: _ } => {}
-            _ => panic!("Wrong Error for Invalid Regex"),
-        }
+        assert_invalid!(form, "some_string" => Code::InvalidRegex);
     }
 
     #[test]
@@ -282,15 +801,7 @@ mod tests {
             ..Default::default()
         };
 
-        let res = form.validate();
-        assert!(res.is_err());
-        let errs = res.unwrap_err();
-        assert_eq!(errs.len(), 1);
-
-        match errs[0] {
-            ValidateError::FieldMismatch { field: _ } => {}
-            _ => panic!("Wrong Error for Field Mismatch"),
-        }
+        assert_invalid!(form, "some_string_2" => Code::FieldMismatch);
     }
 
     #[test]
@@ -300,15 +811,7 @@ mod tests {
             ..Default::default()
         };
 
-        let res = form.validate();
-        assert!(res.is_err());
-        let errs = res.unwrap_err();
-        assert_eq!(errs.len(), 1);
-
-        match errs[0] {
-            ValidateError::InvalidPhoneNumber { field: _ } => {}
-            _ => panic!("Wrong Error for Invalid Phone Number"),
-        }
+        assert_invalid!(form, "phone" => Code::InvalidPhoneNumber);
     }
 
     #[test]
@@ -318,15 +821,7 @@ mod tests {
             ..Default::default()
         };
 
-        let res = form.validate();
-        assert!(res.is_err());
-        let errs = res.unwrap_err();
-        assert_eq!(errs.len(), 1);
-
-        match errs[0] {
-            ValidateError::TooSmall { field: _, min: _ } => {}
-            _ => panic!("Wrong Error for Too Small"),
-        }
+        assert_invalid!(form, "age" => Code::TooSmall);
     }
 
     #[test]
@@ -336,15 +831,7 @@ mod tests {
             ..Default::default()
         };
 
-        let res = form.validate();
-        assert!(res.is_err());
-        let errs = res.unwrap_err();
-        assert_eq!(errs.len(), 1);
-
-        match errs[0] {
-            ValidateError::TooLarge { field: _, max: _ } => {}
-            _ => panic!("Wrong Error for Too Large"),
-        }
+        assert_invalid!(form, "age" => Code::TooLarge);
     }
 
     #[test]
@@ -354,8 +841,7 @@ mod tests {
             ..Default::default()
         };
 
-        let res = form.validate();
-        assert!(res.is_ok());
+        assert_valid!(form);
     }
 
     #[test]
@@ -365,14 +851,527 @@ mod tests {
             ..Default::default()
         };
 
-        let res = form.validate();
-        assert!(res.is_err());
-        let errs = res.unwrap_err();
+        assert_invalid!(form, "opt_number" => Code::TooSmall);
+    }
+
+    #[derive(ValidateForm)]
+    struct MessageForm<'a> {
+        #[validate(min_length = 8, message = "{field} needs at least {min} characters", code = "pw_short")]
+        pub password: &'a str,
+
+        #[validate(min_value = 18, message = "{value} is under {min}")]
+        pub age: i16,
+
+        #[validate_match(password, code = "pw_mismatch")]
+        pub password2: &'a str,
+    }
+
+    #[test]
+    fn test_custom_message_and_code() {
+        let form = MessageForm {
+            password: "short",
+            age: 21,
+            password2: "short",
+        };
+
+        let errs = form.validate().unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert_eq!(errs[0].field(), "password");
+        assert_eq!(errs[0].code(), "pw_short");
+        assert_eq!(
+            errs[0].message(),
+            Some("password needs at least 8 characters")
+        );
+        assert_eq!(errs[0].to_string(), "password needs at least 8 characters");
+    }
+
+    #[test]
+    fn test_custom_message_value() {
+        let form = MessageForm {
+            password: "password",
+            age: 12,
+            password2: "password",
+        };
+
+        let errs = form.validate().unwrap_err();
         assert_eq!(errs.len(), 1);
+        assert_eq!(errs[0].code(), "too_small");
+        assert_eq!(errs[0].message(), Some("12 is under 18"));
+    }
+
+    #[test]
+    fn test_custom_code_without_message() {
+        let form = MessageForm {
+            password: "password",
+            age: 21,
+            password2: "drowssap",
+        };
+
+        let errs = form.validate().unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert_eq!(errs[0].code(), "pw_mismatch");
+        assert_eq!(errs[0].kind(), Code::FieldMismatch);
+        assert_eq!(errs[0].message(), None);
+        assert_eq!(errs[0].to_string(), "password2: does not match other field");
+    }
+
+    #[derive(ValidateForm)]
+    #[validate_config(mode = "first_per_field")]
+    struct ModeForm<'a> {
+        #[validate(min_length = 8, regex = r"^[a-z]+\d+$")]
+        pub password: &'a str,
+
+        #[validate(email, max_length = 8)]
+        pub email: &'a str,
+    }
+
+    #[test]
+    fn test_mode_first_per_field() {
+        let form = ModeForm {
+            password: "!!",
+            email: "not-an-email",
+        };
+
+        let errs = form.validate().unwrap_err();
+        assert_eq!(errs.len(), 2);
+        assert_eq!(errs[0].code(), "input_too_short");
+        assert_eq!(errs[1].code(), "invalid_email");
+    }
+
+    #[test]
+    fn test_mode_override() {
+        let form = ModeForm {
+            password: "!!",
+            email: "not-an-email",
+        };
+
+        let errs = form.validate_with(ValidationMode::All).unwrap_err();
+        assert_eq!(errs.len(), 4);
+
+        let errs = form.validate_with(ValidationMode::FailFast).unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert_eq!(errs[0].field(), "password");
+
+        let form = ModeForm {
+            password: "abcdefgh1",
+            email: "a@b.com",
+        };
+        assert!(form.validate_with(ValidationMode::FailFast).is_ok());
+    }
+
+    #[derive(ValidateForm)]
+    struct GenericForm<'a, T, N: Copy, const LEN: usize>
+    where
+        T: std::fmt::Display,
+    {
+        #[validate(min_length = 3, max_length = 8)]
+        pub name: T,
+
+        #[validate(min_value = 1, max_value = 300)]
+        pub count: N,
+
+        #[validate(optional)]
+        #[validate(min_value = 10)]
+        pub limit: Option<N>,
+
+        #[validate_match(name)]
+        pub confirm: &'a str,
+
+        #[validate(email)]
+        pub email: T,
+
+        pub codes: [u8; LEN],
+    }
+
+    #[test]
+    fn test_generic_form() {
+        let form: GenericForm<String, u16, 2> = GenericForm {
+            name: "mike".to_owned(),
+            count: 5,
+            limit: Some(20),
+            confirm: "mike",
+            email: "mike@test.com".to_owned(),
+            codes: [1, 2],
+        };
+        assert!(form.validate().is_ok());
+
+        let form: GenericForm<String, i32, 0> = GenericForm {
+            name: "mo".to_owned(),
+            count: 400,
+            limit: Some(2),
+            confirm: "mike",
+            email: "mike".to_owned(),
+            codes: [],
+        };
+
+        let errs = form.validate().unwrap_err();
+        let codes: Vec<_> = errs.iter().map(|e| e.code()).collect();
+        assert_eq!(
+            codes,
+            vec!["input_too_short", "too_large", "too_small", "field_mismatch", "invalid_email"]
+        );
+
+        // Signed types work too, and can't exceed a limit they can't hold
+        let form: GenericForm<&str, i8, 0> = GenericForm {
+            name: "mike",
+            count: -5,
+            limit: Some(i8::MAX),
+            confirm: "mike",
+            email: "mike@test.com",
+            codes: [],
+        };
+        let errs = form.validate().unwrap_err();
+        let codes: Vec<_> = errs.iter().map(|e| e.code()).collect();
+        assert_eq!(codes, vec!["too_small"]);
+    }
+
+    fn fields(errs: &[ValidateError]) -> Vec<(&str, &str)> {
+        errs.iter().map(|e| (e.field(), e.code())).collect()
+    }
+
+    #[derive(ValidateForm)]
+    struct Email(#[validate(email)] String);
+
+    #[derive(ValidateForm)]
+    struct Range(#[validate(min_value = 1)] u32, #[validate(max_value = 10)] u32);
+
+    #[derive(ValidateForm)]
+    #[allow(dead_code)]
+    enum Payment<'a> {
+        Card {
+            #[validate(min_length = 12, max_length = 19)]
+            number: &'a str,
+            #[validate(min_length = 3)]
+            cvc: &'a str,
+            #[validate_match(cvc)]
+            cvc_confirm: &'a str,
+        },
+        Paypal(#[validate(email)] &'a str),
+        Cash,
+    }
+
+    #[test]
+    fn test_newtype() {
+        assert!(Email("mike@test.com".to_owned()).validate().is_ok());
+
+        let errs = Email("mike".to_owned()).validate().unwrap_err();
+        assert_eq!(fields(&errs), vec![("0", "invalid_email")]);
+    }
+
+    #[test]
+    fn test_tuple_struct() {
+        assert!(Range(1, 10).validate().is_ok());
+
+        let errs = Range(0, 11).validate().unwrap_err();
+        assert_eq!(fields(&errs), vec![("0", "too_small"), ("1", "too_large")]);
+    }
+
+    #[test]
+    fn test_enum_variants() {
+        assert!(Payment::Cash.validate().is_ok());
+        assert!(Payment::Paypal("mike@test.com").validate().is_ok());
+
+        let card = Payment::Card {
+            number: "4111111111111111",
+            cvc: "123",
+            cvc_confirm: "123",
+        };
+        assert!(card.validate().is_ok());
+
+        let card = Payment::Card {
+            number: "4111",
+            cvc: "12",
+            cvc_confirm: "123",
+        };
+        assert_eq!(
+            fields(&card.validate().unwrap_err()),
+            vec![
+                ("number", "input_too_short"),
+                ("cvc", "input_too_short"),
+                ("cvc_confirm", "field_mismatch"),
+            ]
+        );
+
+        assert_eq!(
+            fields(&Payment::Paypal("mike").validate().unwrap_err()),
+            vec![("0", "invalid_email")]
+        );
+    }
+
+    #[derive(ValidateForm)]
+    #[form(rename_all = "camelCase")]
+    struct RenameForm {
+        #[form(min_length = 2)]
+        pub first_name: String,
+
+        #[form(rename = "user-name")]
+        #[validate(max_length = 4)]
+        pub user_name: String,
+
+        #[validate_match(user_name)]
+        pub confirm_user_name: String,
+    }
+
+    #[test]
+    fn test_rename() {
+        let form = RenameForm {
+            first_name: "m".to_owned(),
+            user_name: "mikey".to_owned(),
+            confirm_user_name: "mike".to_owned(),
+        };
+
+        assert_eq!(
+            fields(&form.validate().unwrap_err()),
+            vec![
+                ("firstName", "input_too_short"),
+                ("user-name", "input_too_long"),
+                ("confirmUserName", "field_mismatch"),
+            ]
+        );
+    }
+
+    #[derive(ValidateForm)]
+    struct UrlForm<'a> {
+        #[validate(url)]
+        pub homepage: &'a str,
+    }
 
-        match errs[0] {
-            ValidateError::TooSmall { field: _, min: _ } => {}
-            _ => panic!("Wrong Error for Too Small"),
+    #[test]
+    fn test_url() {
+        assert!(UrlForm { homepage: "https://test.com/about" }.validate().is_ok());
+        assert_eq!(
+            fields(&UrlForm { homepage: "test.com" }.validate().unwrap_err()),
+            vec![("homepage", "invalid_url")]
+        );
+    }
+
+    #[derive(ValidateForm)]
+    struct SizeForm<'a> {
+        #[validate(one_of("S", "M", "L"))]
+        pub size: &'a str,
+
+        #[validate(one_of("red", "blue"), message = "{value} is not available")]
+        pub color: Option<String>,
+    }
+
+    #[test]
+    fn test_one_of() {
+        assert!(SizeForm { size: "M", color: None }.validate().is_ok());
+
+        let errors = SizeForm {
+            size: "XL",
+            color: Some("green".to_owned()),
         }
+        .validate()
+        .unwrap_err();
+
+        assert_eq!(errors[1].message(), Some("green is not available"));
+        assert_eq!(
+            fields(&errors),
+            vec![("size", "not_one_of"), ("color", "not_one_of")]
+        );
+    }
+
+    #[derive(ValidateForm)]
+    struct RequiredForm<'a> {
+        #[validate(required, not_blank)]
+        pub name: String,
+
+        #[validate(required)]
+        #[validate(min_length = 3)]
+        pub nickname: Option<&'a str>,
+
+        #[validate(min_value = 18)]
+        pub age: Option<u8>,
+
+        #[validate(required)]
+        pub count: Option<u32>,
+
+        pub password: Option<String>,
+
+        #[validate_match(password)]
+        pub confirm: Option<String>,
+    }
+
+    impl<'a> Default for RequiredForm<'a> {
+        fn default() -> Self {
+            RequiredForm {
+                name: "Mike".to_owned(),
+                nickname: Some("mikey"),
+                age: None,
+                count: Some(1),
+                password: None,
+                confirm: None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_required_present() {
+        assert!(RequiredForm::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_required_missing() {
+        let form = RequiredForm {
+            name: "".to_owned(),
+            nickname: None,
+            count: None,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            fields(&form.validate().unwrap_err()),
+            vec![("name", "required"), ("nickname", "required"), ("count", "required")]
+        );
+    }
+
+    #[test]
+    fn test_required_empty_skips_rules() {
+        let form = RequiredForm {
+            nickname: Some(""),
+            ..Default::default()
+        };
+
+        assert_eq!(fields(&form.validate().unwrap_err()), vec![("nickname", "required")]);
+    }
+
+    #[test]
+    fn test_not_blank() {
+        let form = RequiredForm {
+            name: " \t ".to_owned(),
+            ..Default::default()
+        };
+
+        assert_eq!(fields(&form.validate().unwrap_err()), vec![("name", "blank")]);
+    }
+
+    #[test]
+    fn test_option_detected() {
+        let form = RequiredForm {
+            nickname: Some("mo"),
+            age: Some(10),
+            confirm: Some("secret".to_owned()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            fields(&form.validate().unwrap_err()),
+            vec![
+                ("nickname", "input_too_short"),
+                ("age", "too_small"),
+                ("confirm", "field_mismatch"),
+            ]
+        );
+    }
+
+    #[derive(Validate)]
+    struct Username(#[validate(min_length = 3, max_length = 8)] String);
+
+    #[derive(Validate)]
+    struct Sku(#[validate(regex = r"^[A-Z]{3}-\d{4}$", code = "bad_sku")] &'static str);
+
+    #[derive(ValidateForm)]
+    struct OrderForm {
+        pub username: Username,
+
+        pub sku: Option<Sku>,
+
+        #[validate(min_value = 1)]
+        pub quantity: u32,
+    }
+
+    #[test]
+    fn test_validate_newtype() {
+        assert!(Username("mike".to_owned()).validate().is_ok());
+        assert_eq!(
+            fields(&Username("mo".to_owned()).validate().unwrap_err()),
+            vec![("0", "input_too_short")]
+        );
+    }
+
+    #[test]
+    fn test_validate_fields() {
+        let form = OrderForm {
+            username: Username("mike".to_owned()),
+            sku: Some(Sku("ABC-1234")),
+            quantity: 1,
+        };
+        assert!(form.validate().is_ok());
+
+        let form = OrderForm {
+            username: Username("mo".to_owned()),
+            sku: Some(Sku("abc")),
+            quantity: 0,
+        };
+        assert_eq!(
+            fields(&form.validate().unwrap_err()),
+            vec![("username", "input_too_short"), ("sku", "bad_sku"), ("quantity", "too_small")]
+        );
+
+        let form = OrderForm {
+            username: Username("mikey".to_owned()),
+            sku: None,
+            quantity: 2,
+        };
+        assert!(form.validate().is_ok());
+    }
+
+    #[derive(Validate)]
+    struct Address {
+        #[validate(min_length = 3)]
+        #[validate(regex = r"^[A-Z]")]
+        pub street: String,
+
+        #[validate(min_length = 2)]
+        pub city: String,
+
+        pub zip: Option<Username>,
+    }
+
+    #[derive(ValidateForm)]
+    struct ShippingForm {
+        pub address: Address,
+    }
+
+    #[test]
+    fn test_validate_nested_paths() {
+        let address = |street: &str, zip| Address {
+            street: street.to_owned(),
+            city: "X".to_owned(),
+            zip: Some(Username(zip)),
+        };
+
+        let form = ShippingForm {
+            address: address("st", "mo".to_owned()),
+        };
+        let errs = form.validate().unwrap_err();
+        assert_eq!(
+            fields(&errs),
+            vec![
+                ("address.street", "input_too_short"),
+                ("address.street", "invalid_regex"),
+                ("address.city", "input_too_short"),
+                ("address.zip", "input_too_short"),
+            ]
+        );
+        assert_eq!(
+            errs[2].to_string(),
+            "address.city: input too short. (2 min length)"
+        );
+        assert_eq!(errs[2].params()[0], ("field", "address.city".to_owned()));
+
+        // The form's mode applies to nested values too
+        let errs = form
+            .validate_with(ValidationMode::FirstPerField)
+            .unwrap_err();
+        assert_eq!(errs.len(), 3);
+        let errs = form.validate_with(ValidationMode::FailFast).unwrap_err();
+        assert_eq!(fields(&errs), vec![("address.street", "input_too_short")]);
+
+        // Renaming keeps the rest of the path
+        let err = errs.into_iter().next().unwrap().with_field("shipping");
+        assert_eq!(err.field(), "shipping.street");
+        assert_eq!(err.with_parent("order").field(), "order.shipping.street");
     }
 }
@@ -0,0 +1,159 @@
+//! Parses the `file(...)` rule, understood by ValidateForm and HtmlForm
+
+use quote::{quote, ToTokens};
+
+/// Limits set by `#[validate(file(...))]`, converted to a
+/// `webforms::parse::FileRule`
+pub(crate) struct FileRule {
+    max_size: Option<u64>,
+    extensions: Vec<String>,
+    mime: Vec<String>,
+    min_width: Option<u32>,
+    max_width: Option<u32>,
+    min_height: Option<u32>,
+    max_height: Option<u32>,
+}
+
+impl FileRule {
+    /// Parses the arguments of a `file(...)` rule, e.g.,
+    /// `file(max_size = "5MB", extensions("png", "jpg"), mime("image/*"))`
+    ///
+    /// # Arguments
+    /// * `list` - The `file(...)` list
+    pub fn parse(list: &syn::MetaList) -> FileRule {
+        let mut rule = FileRule {
+            max_size: None,
+            extensions: vec![],
+            mime: vec![],
+            min_width: None,
+            max_width: None,
+            min_height: None,
+            max_height: None,
+        };
+
+        for nested in list.nested.iter() {
+            match nested {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(ref nv)) if nv.ident == "max_size" => {
+                    rule.max_size = Some(match nv.lit {
+                        syn::Lit::Str(ref s) => parse_size(&s.value()),
+                        syn::Lit::Int(ref i) => i.value(),
+                        _ => panic!("ValidateForm: `max_size` requires a size (e.g., \"5MB\")"),
+                    })
+                }
+                syn::NestedMeta::Meta(syn::Meta::NameValue(ref nv)) => {
+                    let value = match nv.lit {
+                        syn::Lit::Int(ref i) if i.value() <= u64::from(u32::MAX) => {
+                            Some(i.value() as u32)
+                        }
+                        _ => panic!("ValidateForm: `{}` requires an integer argument", nv.ident),
+                    };
+
+                    if nv.ident == "min_width" {
+                        rule.min_width = value;
+                    } else if nv.ident == "max_width" {
+                        rule.max_width = value;
+                    } else if nv.ident == "min_height" {
+                        rule.min_height = value;
+                    } else if nv.ident == "max_height" {
+                        rule.max_height = value;
+                    } else {
+                        panic!("ValidateForm: unknown `file` argument `{}`", nv.ident);
+                    }
+                }
+                syn::NestedMeta::Meta(syn::Meta::List(ref list)) => {
+                    let values = list.nested.iter().map(|nested| match nested {
+                        syn::NestedMeta::Literal(syn::Lit::Str(ref s)) => s.value(),
+                        _ => panic!("ValidateForm: `{}` requires a list of strings", list.ident),
+                    });
+
+                    if list.ident == "extensions" {
+                        rule.extensions
+                            .extend(values.map(|e| e.trim_start_matches('.').to_owned()));
+                    } else if list.ident == "mime" {
+                        rule.mime.extend(values);
+                    } else {
+                        panic!("ValidateForm: unknown `file` argument `{}`", list.ident);
+                    }
+                }
+                _ => panic!("ValidateForm: unsupported `file` argument"),
+            }
+        }
+
+        rule
+    }
+
+    /// Returns the value of the html `accept` attribute matching the allowed
+    /// extensions and types, or None if any file is allowed
+    pub fn accept(&self) -> Option<String> {
+        let accept: Vec<String> = self
+            .extensions
+            .iter()
+            .map(|e| format!(".{}", e))
+            .chain(self.mime.iter().cloned())
+            .collect();
+
+        match accept.is_empty() {
+            true => None,
+            false => Some(accept.join(",")),
+        }
+    }
+}
+
+impl ToTokens for FileRule {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let max_size = optional(self.max_size);
+        let min_width = optional(self.min_width);
+        let max_width = optional(self.max_width);
+        let min_height = optional(self.min_height);
+        let max_height = optional(self.max_height);
+        let extensions = &self.extensions;
+        let mime = &self.mime;
+
+        tokens.extend(quote! {
+            ::webforms::parse::FileRule {
+                max_size: #max_size,
+                extensions: &[#(#extensions),*],
+                mime: &[#(#mime),*],
+                min_width: #min_width,
+                max_width: #max_width,
+                min_height: #min_height,
+                max_height: #max_height,
+            }
+        });
+    }
+}
+
+/// Writes an optional limit as `Some(limit)` or `None`
+fn optional<T: ToTokens>(value: Option<T>) -> proc_macro2::TokenStream {
+    match value {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None },
+    }
+}
+
+/// Parses a size such as `512`, `100KB` or `5MB` into bytes.  Units are
+/// powers of 1024 and not case sensitive (`KB` and `KiB` are the same)
+///
+/// # Arguments
+/// * `size` - Size to parse
+fn parse_size(size: &str) -> u64 {
+    let size = size.trim();
+    let split = size
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(size.len());
+    let (number, unit) = (&size[..split], size[split..].trim());
+
+    let multiplier: u64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1024,
+        "m" | "mb" | "mib" => 1024 * 1024,
+        "g" | "gb" | "gib" => 1024 * 1024 * 1024,
+        _ => panic!("ValidateForm: unknown size unit `{}` in `{}`", unit, size),
+    };
+
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .unwrap_or_else(|| panic!("ValidateForm: invalid file size `{}`", size))
+}
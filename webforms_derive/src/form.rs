@@ -0,0 +1,136 @@
+//! Handles the shared #[form] attribute, understood by every derive in this crate
+
+use crate::parse_attribute_list;
+
+/// Naming convention applied to every field by `#[form(rename_all = "...")]`.
+/// Field names are assumed to be written in snake_case, as Rust expects
+#[derive(Clone, Copy)]
+pub(crate) enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    /// Parses a naming convention using the same names as serde
+    ///
+    /// # Arguments
+    /// * `rule` - Name of the convention (e.g., `camelCase`)
+    fn parse(rule: &str) -> RenameRule {
+        match rule {
+            "lowercase" => RenameRule::Lower,
+            "UPPERCASE" => RenameRule::Upper,
+            "PascalCase" => RenameRule::Pascal,
+            "camelCase" => RenameRule::Camel,
+            "snake_case" => RenameRule::Snake,
+            "SCREAMING_SNAKE_CASE" => RenameRule::ScreamingSnake,
+            "kebab-case" => RenameRule::Kebab,
+            "SCREAMING-KEBAB-CASE" => RenameRule::ScreamingKebab,
+            r => panic!("WebForms: unknown rename_all rule `{}`, expected one of `lowercase`, `UPPERCASE`, `PascalCase`, `camelCase`, `snake_case`, `SCREAMING_SNAKE_CASE`, `kebab-case` or `SCREAMING-KEBAB-CASE`", r),
+        }
+    }
+
+    /// Applies this convention to a snake_case field name
+    ///
+    /// # Arguments
+    /// * `name` - Field name to convert
+    pub fn apply(self, name: &str) -> String {
+        match self {
+            RenameRule::Lower | RenameRule::Snake => name.to_owned(),
+            RenameRule::Upper | RenameRule::ScreamingSnake => name.to_ascii_uppercase(),
+            RenameRule::Kebab => name.replace('_', "-"),
+            RenameRule::ScreamingKebab => name.replace('_', "-").to_ascii_uppercase(),
+            RenameRule::Pascal | RenameRule::Camel => {
+                let mut out = String::with_capacity(name.len());
+                let mut upper = matches!(self, RenameRule::Pascal);
+
+                for c in name.chars() {
+                    if c == '_' {
+                        upper = !out.is_empty();
+                    } else if upper {
+                        out.push(c.to_ascii_uppercase());
+                        upper = false;
+                    } else {
+                        out.push(c);
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+/// Returns true if the name/value pair is handled here rather than by the
+/// derive reading the rest of the #[form] attribute
+///
+/// # Arguments
+/// * `nv` - Name/value pair found in a #[form] attribute
+pub(crate) fn is_form_attribute(nv: &syn::MetaNameValue) -> bool {
+    nv.ident == "rename" || nv.ident == "rename_all"
+}
+
+/// Parses `#[form(rename_all = "...")]` from the attributes of a struct or enum
+///
+/// # Arguments
+/// * `attrs` - Attributes attached to the struct or enum
+pub(crate) fn rename_rule(attrs: &[syn::Attribute]) -> Option<RenameRule> {
+    let mut rule = None;
+    for attr in attrs.iter().filter(|a| a.path.is_ident("form")) {
+        parse_attribute_list(attr, |meta| {
+            if let syn::Meta::NameValue(ref nv) = meta {
+                if nv.ident == "rename_all" {
+                    rule = match nv.lit {
+                        syn::Lit::Str(ref s) => Some(RenameRule::parse(&s.value())),
+                        _ => panic!("WebForms: rename_all requires a string argument"),
+                    };
+                }
+            }
+        });
+    }
+    rule
+}
+
+/// Returns the name a field is submitted, rendered and reported under: the
+/// value of `#[form(rename = "...")]`, otherwise the field's ident with the
+/// struct's `rename_all` rule applied.  Tuple fields default to their index
+///
+/// # Arguments
+/// * `field` - Field to name
+/// * `index` - Position of the field in its struct or variant
+/// * `rule` - Rule from the struct's `#[form(rename_all = "...")]`, if any
+pub(crate) fn field_name(field: &syn::Field, index: usize, rule: Option<RenameRule>) -> String {
+    for attr in field.attrs.iter().filter(|a| a.path.is_ident("form")) {
+        let mut rename = None;
+        parse_attribute_list(attr, |meta| {
+            if let syn::Meta::NameValue(ref nv) = meta {
+                if nv.ident == "rename" {
+                    rename = match nv.lit {
+                        syn::Lit::Str(ref s) => Some(s.value()),
+                        _ => panic!("WebForms: rename requires a string argument"),
+                    };
+                }
+            }
+        });
+
+        if let Some(name) = rename {
+            return name;
+        }
+    }
+
+    match field.ident {
+        Some(ref ident) => {
+            let name = ident.to_string();
+            let name = name.trim_start_matches("r#");
+            match rule {
+                Some(rule) => rule.apply(name),
+                None => name.to_owned(),
+            }
+        }
+        None => index.to_string(),
+    }
+}
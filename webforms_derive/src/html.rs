@@ -1,6 +1,6 @@
 //! #[derive(HtmlForm) macro implementation
 
-use crate::is_option;
+use crate::{is_option, option_inner, with_bounds};
 use crate::proc_macro::TokenStream;
 use lazy_static::lazy_static;
 use quote::quote;
@@ -24,10 +24,12 @@ lazy_static! {
 /// Implementation for the HtmlForm macro
 pub(crate) fn impl_html_macro(ast: syn::DeriveInput) -> TokenStream {
     let name = &ast.ident;
-    let generics = &ast.generics;
 
     let st = HtmlStruct::parse(&ast);
 
+    let generics = with_bounds(&ast.generics, &st.bounds());
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
     let fields = &st.fields;
     let validators = &st.validators;
     let field_names: Vec<&str> = st
@@ -40,22 +42,53 @@ pub(crate) fn impl_html_macro(ast: syn::DeriveInput) -> TokenStream {
         .collect();
 
     let field_idents: Vec<_> = st.fields.iter().map(|f| &f.ident).collect();
+    let field_idents = &field_idents;
+
+    // Each field is filled in with its current value before being added
+    let filled_fields: Vec<_> = fields
+        .iter()
+        .zip(field_idents)
+        .zip(&field_names)
+        .map(|((field, ident), name)| {
+            quote! {{
+                let field = #field;
+                let value = &&&::webforms::html::__private::Value(&self.#ident);
+                let field = match value.webforms_value() {
+                    Some(value) => field.set_value(value),
+                    None => field,
+                };
+                (&&&::webforms::html::__private::Nested(&self.#ident))
+                    .webforms_add_field(&mut form, #name, field, true);
+            }}
+        })
+        .collect();
 
     let gen = quote! {
-        impl #generics ::webforms::html::HtmlForm for #name #generics {
+        impl #impl_generics ::webforms::html::HtmlForm for #name #ty_generics #where_clause {
 
             /// Creates a form builder from the fields and attributes specified
-            /// on the struct
+            /// on the struct, filled in with the current values of the fields
             fn form(&self) -> ::webforms::html::HtmlFormBuilder {
-                let mut form = self.blank_form();
+                #[allow(unused_imports)]
+                use ::webforms::html::__private::{
+                    ViaDisplay as _, ViaHtmlForm as _, ViaHtmlForms as _, ViaInput as _,
+                    ViaNoValue as _, ViaOption as _,
+                };
+
+                let mut form = ::webforms::html::HtmlFormBuilder::new();
+                #(#filled_fields)*
                 #(form.validate_field(&self.#field_idents, #validators);)*
                 form
             }
 
             /// Generates a blank (non-validated) form
             fn blank_form(&self) -> ::webforms::html::HtmlFormBuilder {
+                #[allow(unused_imports)]
+                use ::webforms::html::__private::{ViaHtmlForm as _, ViaHtmlForms as _, ViaInput as _};
+
                 let mut form = ::webforms::html::HtmlFormBuilder::new();
-                #(form.add_field(#field_names, #fields);)*
+                #((&&&::webforms::html::__private::Nested(&self.#field_idents))
+                    .webforms_add_field(&mut form, #field_names, #fields, false);)*
                 form
             }
         }
@@ -66,6 +99,98 @@ pub(crate) fn impl_html_macro(ast: syn::DeriveInput) -> TokenStream {
     gen.into()
 }
 
+/// Returns the html validation attribute equivalent to a rule in the shared
+/// #[form] attribute (e.g., `min_length` -> `minlength`), or None if the rule
+/// has no html equivalent
+///
+/// # Arguments
+///
+/// * `rule` - Name of the rule
+pub(crate) fn html_rule(rule: &syn::Ident) -> Option<&'static str> {
+    if rule == "min_length" {
+        Some("minlength")
+    } else if rule == "max_length" {
+        Some("maxlength")
+    } else if rule == "min_value" {
+        Some("min")
+    } else if rule == "max_value" {
+        Some("max")
+    } else if rule == "regex" {
+        Some("pattern")
+    } else {
+        None
+    }
+}
+
+/// Returns the html attributes implied by the limits of a bounded type from
+/// `webforms::types` (e.g., `BoundedString<3, 32>` -> `minlength='3'` and
+/// `maxlength='32'`).  Types are matched by name
+///
+/// # Arguments
+///
+/// * `ty` - Type of field
+pub(crate) fn html_type_constraints(ty: &syn::Type) -> Vec<(&'static str, String)> {
+    let ty = option_inner(ty).unwrap_or(ty);
+    let segment = match ty {
+        syn::Type::Path(ref p) => match p.path.segments.last() {
+            Some(segment) => segment.into_value(),
+            None => return vec![],
+        },
+        _ => return vec![],
+    };
+
+    let args: Vec<String> = match segment.arguments {
+        syn::PathArguments::AngleBracketed(ref brackets) => brackets
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::GenericArgument::Const(ref expr) => const_value(expr),
+                _ => None,
+            })
+            .collect(),
+        _ => return vec![],
+    };
+
+    let names = if segment.ident == "BoundedString" {
+        ["minlength", "maxlength"]
+    } else if segment.ident == "Ranged" {
+        ["min", "max"]
+    } else {
+        return vec![];
+    };
+
+    match args.len() {
+        2 => names.iter().cloned().zip(args).collect(),
+        _ => vec![],
+    }
+}
+
+/// Evaluates a const generic argument that is an integer literal, possibly
+/// negated and/or wrapped in a block (e.g., `{ -5 }`)
+///
+/// # Arguments
+///
+/// * `expr` - Expression to evaluate
+fn const_value(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(ref i),
+            ..
+        }) => Some(i.value().to_string()),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            ref expr,
+            ..
+        }) => const_value(expr).map(|v| format!("-{}", v)),
+        syn::Expr::Block(ref block) => match block.block.stmts.as_slice() {
+            [syn::Stmt::Expr(ref expr)] => const_value(expr),
+            _ => None,
+        },
+        syn::Expr::Paren(ref paren) => const_value(&paren.expr),
+        _ => None,
+    }
+}
+
 fn html_input_type_parse_opt(args: &syn::PathArguments, default: &'static str) -> &'static str {
     // Read first arg in path arguments to get type
     let mut ret: &'static str = default;
@@ -97,6 +222,13 @@ pub(crate) fn html_input_type(ty: &syn::Type) -> &'static str {
             Some(ref r) if HTML_DEFAULTS.has_input_type(&r.value().ident) => {
                 HTML_DEFAULTS.get_input_type(&r.value().ident)
             }
+            // Types from `webforms::types`, matched by name
+            Some(ref r) if r.value().ident == "Email" => "email",
+            Some(ref r) if r.value().ident == "PhoneNumber" => "tel",
+            Some(ref r) if r.value().ident == "Url" => "url",
+            Some(ref r) if r.value().ident == "Ranged" => "number",
+            Some(ref r) if r.value().ident == "UploadedFile" => "file",
+            Some(ref r) if r.value().ident == "bool" => "checkbox",
             Some(ref r) => {
                 let ty = &r.value().ident;
 
@@ -1,7 +1,8 @@
 //! Implemenation of the HtmlField container used when parsing a field in a struct with the #[derive(HtmlForm)] attribute
 
 use crate::{
-    html::{html_input_type, HtmlValidate},
+    file::FileRule,
+    html::{html_input_type, html_rule, html_type_constraints, HtmlValidate},
     is_option, parse_attribute_list,
 };
 use quote::{quote, ToTokens};
@@ -13,6 +14,7 @@ pub(crate) struct HtmlField<'a> {
     pub name: Option<String>,
     pub pair_attrs: HashMap<String, String>,
     pub value_attrs: HashSet<String>,
+    pub options: Vec<String>,
     pub validators: Vec<HtmlValidate<'a>>,
     pub optional: bool,
 }
@@ -25,26 +27,30 @@ impl<'a> HtmlField<'a> {
     /// # Arguments
     ///
     /// * `tag` - HTML tag to use for this field
-    /// * `name` - Name of this field
-    /// * `attrs` - Vector of HtmlFieldAttributes
-    pub fn with_name<S: Into<String>>(tag: S, field: &syn::Field) -> HtmlField {
-        let name = field.ident.as_ref().map(|i| i.to_string());
-
+    /// * `field` - Field this element is generated for
+    /// * `name` - Name of this field (see `form::field_name`)
+    pub fn with_name<S: Into<String>>(tag: S, field: &syn::Field, name: String) -> HtmlField {
         HtmlField {
             ident: &field.ident,
             tag: tag.into(),
-            name: name,
+            name: Some(name),
             pair_attrs: HashMap::new(),
             value_attrs: HashSet::new(),
+            options: Vec::new(),
             validators: Vec::new(),
             optional: is_option(&field.ty),
         }
     }
 
-    pub fn input(field: &syn::Field) -> HtmlField {
-        let mut html_field = HtmlField::with_name("input", field);
-        html_field.add_pair_attribute("type", html_input_type(&field.ty));
-        if !html_field.optional {
+    pub fn input(field: &syn::Field, name: String) -> HtmlField {
+        let mut html_field = HtmlField::with_name("input", field, name);
+        let input_type = html_input_type(&field.ty);
+        html_field.add_pair_attribute("type", input_type);
+        for (attr, value) in html_type_constraints(&field.ty) {
+            html_field.add_pair_attribute(attr, value);
+        }
+        // An unchecked checkbox submits nothing, so it can't be required
+        if !html_field.optional && input_type != "checkbox" {
             html_field.add_value_attribute("required");
         }
 
@@ -96,12 +102,79 @@ impl<'a> HtmlField<'a> {
         self.value_attrs.insert(value.into());
     }
 
+    /// Makes this field a file input accepting the files allowed by a
+    /// `file(...)` rule
+    ///
+    /// # Arguments
+    ///
+    /// * `list` - The `file(...)` list
+    fn add_file_rule(&mut self, list: &syn::MetaList) {
+        self.add_pair_attribute("type", "file");
+        if let Some(accept) = FileRule::parse(list).accept() {
+            self.add_pair_attribute("accept", accept);
+        }
+    }
+
+    /// Makes this field a select offering the values allowed by a
+    /// `one_of(...)` rule, with a blank choice first if the field is optional
+    ///
+    /// # Arguments
+    ///
+    /// * `list` - The `one_of(...)` list
+    fn add_choices(&mut self, list: &syn::MetaList) {
+        self.tag = "select".to_owned();
+        self.pair_attrs.remove("type");
+        self.options.clear();
+        if self.optional {
+            self.options.push(String::new());
+        }
+        for nested in list.nested.iter() {
+            match nested {
+                syn::NestedMeta::Literal(syn::Lit::Str(ref s)) => self.options.push(s.value()),
+                _ => panic!("HtmlForm: one_of requires a list of strings"),
+            }
+        }
+    }
+
     /// Creates a new HtmlField by parsing all attributes attached to the field
-    pub fn parse(field: &syn::Field) -> HtmlField {
-        let mut f = HtmlField::input(field);
+    pub fn parse(field: &syn::Field, name: String) -> HtmlField {
+        let mut f = HtmlField::input(field, name);
 
         for attr in &field.attrs {
-            if attr.path.is_ident("html_attrs") {
+            if attr.path.is_ident("form") {
+                // Shared validation rules.  Those with an html equivalent are
+                // applied to the tag, the rest are left to ValidateForm
+                parse_attribute_list(attr, |meta| match meta {
+                    syn::Meta::Word(ref ident) if ident == "email" => {
+                        f.add_pair_attribute("type", "email")
+                    }
+                    syn::Meta::Word(ref ident) if ident == "phone" => {
+                        f.add_pair_attribute("type", "tel")
+                    }
+                    syn::Meta::Word(ref ident) if ident == "url" => {
+                        f.add_pair_attribute("type", "url")
+                    }
+                    syn::Meta::Word(ref ident) if ident == "required" => {
+                        f.add_value_attribute("required")
+                    }
+                    syn::Meta::List(ref list) if list.ident == "file" => f.add_file_rule(list),
+                    syn::Meta::List(ref list) if list.ident == "one_of" => f.add_choices(list),
+                    syn::Meta::NameValue(ref nv) => {
+                        if let Some(attr) = html_rule(&nv.ident) {
+                            f.parse_pair_attribute(attr.to_owned(), &nv.lit);
+                        }
+                    }
+                    _ => {}
+                });
+            } else if attr.path.is_ident("validate") {
+                // Only `file` and `one_of` change the tag, the other rules are
+                // checked by ValidateForm or read from #[form]
+                parse_attribute_list(attr, |meta| match meta {
+                    syn::Meta::List(ref list) if list.ident == "file" => f.add_file_rule(list),
+                    syn::Meta::List(ref list) if list.ident == "one_of" => f.add_choices(list),
+                    _ => {}
+                });
+            } else if attr.path.is_ident("html_attrs") {
                 // Applies the list of attributes to this tag
                 parse_attribute_list(attr, |meta| match meta {
                     syn::Meta::Word(ref ident) => f.add_value_attribute(ident.to_string()),
@@ -213,11 +286,13 @@ impl<'a> ToTokens for HtmlField<'a> {
             .collect();
 
         let values: Vec<_> = self.value_attrs.iter().collect();
+        let options = &self.options;
 
         tokens.extend(quote! {{
             let mut attrs = ::webforms::attrs!(#(#pairs),*);
             #(attrs.insert(::webforms::html::HtmlAttribute::new_single(#values));)*
             ::webforms::html::HtmlFieldBuilder::with_attrs(#tag, #name, attrs)
+                #(.option(#options))*
         }})
     }
 }
@@ -1,11 +1,14 @@
 //! Implemenation of the HtmlStruct container used when parsing a struct with the #[derive(HtmlForm)] attribute
 
+use crate::form::{field_name, rename_rule, RenameRule};
 use crate::html::{HtmlField, HtmlValidate};
+use crate::type_params;
 
 pub(crate) struct HtmlStruct<'a> {
     pub name: String,
     pub fields: Vec<HtmlField<'a>>,
     pub validators: Vec<HtmlValidate<'a>>,
+    pub rename: Option<RenameRule>,
 }
 
 impl<'a> HtmlStruct<'a> {
@@ -15,6 +18,7 @@ impl<'a> HtmlStruct<'a> {
             name: name.clone(),
             fields: Vec::new(),
             validators: Vec::new(),
+            rename: None,
         }
     }
 
@@ -42,6 +46,8 @@ impl<'a> HtmlStruct<'a> {
         for attr in &ast.attrs {
             if attr.path.is_ident("html_regex") {}
         }
+
+        self.rename = rename_rule(&ast.attrs);
     }
 
     /// Parses all attributes applied to fields on the struct
@@ -61,7 +67,8 @@ impl<'a> HtmlStruct<'a> {
 
         self.fields = fields
             .iter()
-            .map(|field| HtmlField::parse(&field))
+            .enumerate()
+            .map(|(idx, field)| HtmlField::parse(field, field_name(field, idx, self.rename)))
             .collect();
     }
 
@@ -80,9 +87,25 @@ impl<'a> HtmlStruct<'a> {
             _ => panic!("HtmlForm only defined on data structs!"),
         };
 
+        let params = type_params(&ast.generics);
         self.validators = fields
             .iter()
-            .map(|field| HtmlValidate::parse(&field))
+            .enumerate()
+            .map(|(idx, field)| {
+                HtmlValidate::parse(field, field_name(field, idx, self.rename), &params)
+            })
             .collect();
     }
+
+    /// Returns the where clause predicates required by validators on
+    /// generic fields
+    pub fn bounds(&self) -> Vec<proc_macro2::TokenStream> {
+        let mut bounds: Vec<proc_macro2::TokenStream> = vec![];
+        for bound in self.validators.iter().flat_map(|v| v.bounds()) {
+            if !bounds.iter().any(|b| b.to_string() == bound.to_string()) {
+                bounds.push(bound);
+            }
+        }
+        bounds
+    }
 }
@@ -1,6 +1,7 @@
 //! Handles the html validation attribute
 
-use crate::{is_option, parse_attribute_list};
+use crate::html::html_rule;
+use crate::{compare_literal, is_option, option_inner, parse_attribute_list, uses_type_params};
 use quote::{quote, ToTokens};
 use std::collections::HashMap;
 
@@ -17,11 +18,12 @@ enum Validator {
 
 #[derive(Clone)]
 pub(crate) struct HtmlValidate<'a> {
-    name: Option<proc_macro2::Ident>,
+    name: String,
     errors: HashMap<&'static str, String>,
     ty: &'a syn::Type,
     validators: Vec<Validator>,
     optional: bool,
+    generic: bool,
 }
 
 impl<'a> HtmlValidate<'a> {
@@ -30,19 +32,57 @@ impl<'a> HtmlValidate<'a> {
     /// Arguments
     ///
     /// * `field` - Field to parse validators from
-    pub fn parse(field: &'a syn::Field) -> HtmlValidate<'a> {
+    /// * `name` - Name of the field (see `form::field_name`)
+    /// * `params` - Type parameters declared on the struct
+    pub fn parse(field: &'a syn::Field, name: String, params: &[syn::Ident]) -> HtmlValidate<'a> {
+        let optional = is_option(&field.ty);
         let mut validator = HtmlValidate {
-            name: field.ident.clone(),
+            name,
             errors: HashMap::new(),
-            ty: &field.ty,
+            ty: match optional {
+                true => option_inner(&field.ty).unwrap_or(&field.ty),
+                false => &field.ty,
+            },
             validators: Vec::new(),
-            optional: is_option(&field.ty),
+            optional,
+            generic: uses_type_params(&field.ty, params),
         };
 
         // Parse the attribute list on this field, looking for the following attributes:
         // * #[html_validate] - Validation criterea for this field
         for attr in &field.attrs {
-            if attr.path.is_ident("html_validate") {
+            if attr.path.is_ident("form") {
+                // Shared validation rules, run with the same semantics as the
+                // html attribute they map to
+                let start = validator.validators.len();
+                let mut message = None;
+                parse_attribute_list(attr, |meta| {
+                    if let syn::Meta::NameValue(ref nv) = meta {
+                        if nv.ident == "message" {
+                            if let syn::Lit::Str(ref s) = nv.lit {
+                                message = Some(s.value());
+                            }
+                        }
+
+                        match (html_rule(&nv.ident), &nv.lit) {
+                            (Some("minlength"), syn::Lit::Int(ref i)) => validator.add_validator(Validator::MinLength(i.clone())),
+                            (Some("maxlength"), syn::Lit::Int(ref i)) => validator.add_validator(Validator::MaxLength(i.clone())),
+                            (Some("min"), syn::Lit::Int(ref i)) => validator.add_validator(Validator::MinValue(i.clone())),
+                            (Some("max"), syn::Lit::Int(ref i)) => validator.add_validator(Validator::MaxValue(i.clone())),
+                            (Some("pattern"), syn::Lit::Str(ref s)) => validator.add_validator(Validator::Pattern(s.clone())),
+                            (Some(rule), _) => panic!("WebForms - #[form] invalid argument for `{}`", rule),
+                            (None, _) => {}
+                        }
+                    }
+                });
+
+                if let Some(message) = message {
+                    let names: Vec<_> = validator.validators[start..].iter().map(|v| v.get_name()).collect();
+                    for name in names {
+                        validator.add_error_msg(name, message.clone());
+                    }
+                }
+            } else if attr.path.is_ident("html_validate") {
                 parse_attribute_list(attr, |meta| match meta {
                     syn::Meta::Word(_) => {}
                     syn::Meta::List(_) => {}
@@ -131,6 +171,31 @@ impl<'a> HtmlValidate<'a> {
         validator
     }
 
+    /// Builds the where clause predicates needed to validate a field whose
+    /// type is (or contains) a type parameter
+    pub fn bounds(&self) -> Vec<proc_macro2::TokenStream> {
+        if !self.generic {
+            return vec![];
+        }
+
+        let ty = self.ty;
+        self.validators
+            .iter()
+            .filter_map(|v| match v {
+                Validator::MinValue(_) | Validator::MaxValue(_) => Some(quote! {
+                    #ty: ::std::cmp::PartialOrd + ::std::convert::TryFrom<i128>
+                }),
+                Validator::MinFloat(_) | Validator::MaxFloat(_) => {
+                    Some(quote! { #ty: ::std::cmp::PartialOrd + ::std::convert::From<f64> })
+                }
+                Validator::MinLength(_) | Validator::MaxLength(_) => {
+                    Some(quote! { #ty: ::std::convert::AsRef<str> })
+                }
+                Validator::Pattern(_) => None,
+            })
+            .collect()
+    }
+
     /// Adds a validator to this Validation container
     fn add_validator(&mut self, v: Validator) {
         self.validators.push(v);
@@ -150,10 +215,13 @@ impl Validator {
     /// # Arguments
     /// * `name` - Currently unused
     /// * `optional` - True if this is an optional type, false otherwise
+    /// * `generic` - Some(type) if the field's type is a type parameter, so
+    ///   comparisons go through the traits added by `HtmlValidate::bounds`
     pub fn write(
         &self,
-        _name: &Option<proc_macro2::Ident>,
+        _name: &str,
         optional: bool,
+        generic: Option<&syn::Type>,
         errors: &HashMap<&'static str, String>,
     ) -> proc_macro2::TokenStream {
         // If the type is optional, validate as such
@@ -162,22 +230,50 @@ impl Validator {
             false => quote! {x},
         };
 
+        let len = match generic {
+            Some(_) => quote! { ::std::convert::AsRef::<str>::as_ref(#field).len() },
+            None => quote! { #field.len() },
+        };
+
+        let float = |lit: proc_macro2::TokenStream| match generic {
+            Some(ty) => quote! { <#ty as ::std::convert::From<f64>>::from(#lit) },
+            None => lit,
+        };
+
         let err_msg = self.get_error(errors);
+        let code = self.get_code();
+        let params: Vec<_> = self
+            .get_params()
+            .into_iter()
+            .map(|(k, v)| quote! { (#k, #v) })
+            .collect();
 
         let cond = match self {
-            Validator::MinValue(i) => quote! { #field >= &#i },
-            Validator::MinFloat(f) => quote! { #field >= &#f },
-            Validator::MaxValue(i) => quote! { #field <= &#i },
-            Validator::MaxFloat(f) => quote! { #field <= &#f },
-            Validator::MinLength(i) => quote! {#field.len() >= #i},
-            Validator::MaxLength(i) => quote! {#field.len() <= #i},
+            Validator::MinValue(i) => match generic {
+                Some(ty) => compare_literal(ty, i, quote! { #field >= &bound }, false),
+                None => quote! { #field >= &#i },
+            },
+            Validator::MinFloat(f) => {
+                let v = float(quote! {#f});
+                quote! { #field >= &#v }
+            }
+            Validator::MaxValue(i) => match generic {
+                Some(ty) => compare_literal(ty, i, quote! { #field <= &bound }, true),
+                None => quote! { #field <= &#i },
+            },
+            Validator::MaxFloat(f) => {
+                let v = float(quote! {#f});
+                quote! { #field <= &#v }
+            }
+            Validator::MinLength(i) => quote! {#len >= #i},
+            Validator::MaxLength(i) => quote! {#len <= #i},
             Validator::Pattern(s) => quote! { true },
         };
 
         let check = quote! {
             match #cond {
                 true => Ok(()),
-                false => Err(#err_msg),
+                false => Err(::webforms::html::FieldError::new(#code, #err_msg, &[#(#params),*])),
             }
         };
 
@@ -204,6 +300,34 @@ impl Validator {
         }
     }
 
+    /// Error code reported when this validator fails.  These match the
+    /// codes used by `ValidateError` so both can share a message catalog
+    fn get_code(&self) -> &'static str {
+        match self {
+            Validator::MinValue(_) => "too_small",
+            Validator::MinFloat(_) => "too_small",
+            Validator::MaxValue(_) => "too_large",
+            Validator::MaxFloat(_) => "too_large",
+            Validator::MinLength(_) => "input_too_short",
+            Validator::MaxLength(_) => "input_too_long",
+            Validator::Pattern(_) => "invalid_regex",
+        }
+    }
+
+    /// Parameters describing this validator, used when interpolating or
+    /// localizing error messages
+    fn get_params(&self) -> Vec<(&'static str, String)> {
+        match self {
+            Validator::MinValue(i) => vec![("min", i.value().to_string())],
+            Validator::MinFloat(f) => vec![("min", f.value().to_string())],
+            Validator::MaxValue(i) => vec![("max", i.value().to_string())],
+            Validator::MaxFloat(f) => vec![("max", f.value().to_string())],
+            Validator::MinLength(i) => vec![("min", i.value().to_string())],
+            Validator::MaxLength(i) => vec![("max", i.value().to_string())],
+            Validator::Pattern(s) => vec![("pattern", s.value())],
+        }
+    }
+
     fn get_error(&self, map: &HashMap<&'static str, String>) -> String {
         match map.get(self.get_name()) {
             Some(v) => v.clone(),
@@ -229,14 +353,17 @@ impl Validator {
 impl<'a> ToTokens for HtmlValidate<'a> {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
         let name = &self.name;
-        //let ty = self.ty;
+        let generic = match self.generic {
+            true => Some(self.ty),
+            false => None,
+        };
         let v: Vec<_> = self
             .validators
             .iter()
-            .map(|v| v.write(name, self.optional, &self.errors))
+            .map(|v| v.write(name, self.optional, generic, &self.errors))
             .collect();
 
-        let ts = quote! { ::webforms::html::FieldValidator::new(stringify!(#name), vec![#(Box::new(&|x| #v)),*]) };
+        let ts = quote! { ::webforms::html::FieldValidator::new(#name, vec![#(Box::new(&|x| #v)),*]) };
         //println!("{}", ts);
         tokens.extend(ts);
     }
@@ -1,11 +1,16 @@
 //! Macro implementations for WebForms
 #![recursion_limit = "128"]
 
+mod file;
+mod form;
 mod html;
+mod meta;
+mod parse;
 mod validate;
 extern crate proc_macro;
 
 use crate::proc_macro::TokenStream;
+use quote::quote;
 use syn;
 
 /// Derives the ValidateForm trait from for a given struct
@@ -28,6 +33,30 @@ use syn;
 /// * `min_value` - Minimum value of this int
 /// * `max_value` - Maxium value of this int
 ///
+/// Generic structs (lifetimes, type and const parameters, where clauses) are
+/// supported.  Rules on fields whose type is a type parameter add the bounds
+/// they need: `AsRef<str>` for string rules, `PartialOrd + TryFrom<i128>` for
+/// value rules (so any integer type can be used) and `PartialEq<_>` for
+/// `validate_match`
+///
+/// Tuple structs report errors using the field's index (e.g. `"0"`) as its
+/// name.  On enums, only the fields of the active variant are validated
+///
+/// `#[form(...)]` accepts the same rules as `#[validate(...)]` and is shared
+/// with HtmlForm.  `#[form(rename = "...")]` on a field, or
+/// `#[form(rename_all = "...")]` on the struct, changes the name errors are
+/// reported under
+///
+/// The struct attribute `#[validate_config(mode = "...")]` controls how many
+/// errors are collected: `all` (default), `first_per_field` or `fail_fast`
+///
+/// `#[validate_config(arbitrary)]` also implements proptest's `Arbitrary` and
+/// `webforms::testing::ArbitraryForm` for the form (requires the `testing`
+/// feature)
+///
+/// Also implements `webforms::meta::FormMeta`, describing each field and the
+/// rules applied to it
+///
 /// # Example
 ///
 /// ```compile_fail
@@ -43,12 +72,37 @@ use syn;
 ///     pub email: String,
 /// }
 /// ```
-#[proc_macro_derive(ValidateForm, attributes(validate, validate_regex, validate_match))]
+#[proc_macro_derive(
+    ValidateForm,
+    attributes(form, validate, validate_regex, validate_match, validate_config)
+)]
 pub fn validate_macro_derive(input: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput =
         syn::parse(input).expect("failed to parse ValidateForm macro input");
 
-    validate::impl_validate_macro(ast)
+    let meta: TokenStream = meta::impl_form_meta(&ast, "ValidateForm").into();
+    let mut gen = validate::impl_validate_macro(ast);
+    gen.extend(meta);
+    gen
+}
+
+/// Derives the Validate trait for a value type, usually a newtype wrapping a
+/// single field.  Accepts the same attributes as ValidateForm
+///
+/// # Example
+///
+/// ```compile_fail
+/// #[derive(Validate)]
+/// struct Username(#[validate(min_length = 3, max_length = 16)] String);
+/// ```
+#[proc_macro_derive(
+    Validate,
+    attributes(form, validate, validate_regex, validate_match, validate_config)
+)]
+pub fn validate_value_macro_derive(input: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(input).expect("failed to parse Validate macro input");
+
+    validate::impl_validate_value_macro(ast)
 }
 
 /// Derives the HtmlForm trait for a given struct
@@ -56,11 +110,61 @@ pub fn validate_macro_derive(input: TokenStream) -> TokenStream {
 /// Will generate valid and complient HTML for a struct that can be used
 /// with various templating languages (Tera, Askama, etc) to render forms
 /// onto webpages
-#[proc_macro_derive(HtmlForm, attributes(html_attrs, html_input, html_validate, html_error))]
+///
+/// Rules in the shared `#[form(...)]` attribute are mapped onto their html
+/// equivalents, and `rename`/`rename_all` change the generated `name` attributes
+///
+/// Fields whose type implements HtmlForm (or a `Vec` or `Option` of one) are
+/// rendered as the nested form's fields, named `address[city]` or
+/// `items[0][sku]` to match FromForm
+///
+/// `form()` fills in each field with its current value (escaped), checks the
+/// checkbox of a `bool` field and selects the matching option of a field
+/// with a `one_of(...)` rule, rendered as a select.  Password and file inputs
+/// are never filled in
+///
+/// Also implements `webforms::meta::FormMeta`.  A struct deriving both HtmlForm
+/// and ValidateForm implements it once, describing the rules of both (see
+/// `webforms::meta` for how the two derives decide which one implements it)
+#[proc_macro_derive(
+    HtmlForm,
+    attributes(form, html_attrs, html_input, html_validate, html_error)
+)]
 pub fn html_macro_derive(input: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = syn::parse(input).expect("failed to parse HtmlForm macro input");
 
-    html::impl_html_macro(ast)
+    let meta: TokenStream = meta::impl_form_meta(&ast, "HtmlForm").into();
+    let mut gen = html::impl_html_macro(ast);
+    gen.extend(meta);
+    gen
+}
+
+/// Derives the FromForm trait for a given struct
+///
+/// Reads each field from the submitted value with the field's name (honoring
+/// `#[form(rename = "...")]` and `#[form(rename_all = "...")]`) using
+/// `webforms::parse::FromFormField`, or `FromStr` for types that don't
+/// implement it.  `#[form(empty_as_none)]` and `#[form(keep_empty)]`, on a
+/// field or the struct, control whether empty values count as submitted
+///
+/// Also implements `webforms::parse::FromFormField`, so the struct can be
+/// nested in another form (read from names like `address[city]`, or
+/// `items[0][sku]` for a `Vec`)
+///
+/// # Example
+///
+/// ```compile_fail
+/// #[derive(FromForm)]
+/// struct LoginForm {
+///     pub username: String,
+///     pub remember_me: bool,
+/// }
+/// ```
+#[proc_macro_derive(FromForm, attributes(form))]
+pub fn from_form_macro_derive(input: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(input).expect("failed to parse FromForm macro input");
+
+    parse::impl_from_form_macro(ast)
 }
 
 /// Parses an attribute list in the form #[attribute(list)] and applies the given
@@ -115,3 +219,150 @@ pub(crate) fn is_option(ty: &syn::Type) -> bool {
         _ => false,
     }
 }
+
+/// Returns the type wrapped by an `Option<...>`, or None if the type
+/// is not an option
+///
+/// # Arguments
+///
+/// * `ty` - Type to unwrap
+pub(crate) fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+    if let syn::Type::Path(ref p) = ty {
+        if let Some(segment) = p.path.segments.last() {
+            let segment = segment.value();
+            if segment.ident == "Option" {
+                if let syn::PathArguments::AngleBracketed(ref args) = segment.arguments {
+                    if let Some(arg) = args.args.first() {
+                        if let syn::GenericArgument::Type(ref inner) = arg.value() {
+                            return Some(inner);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Detects whether a type is a string (`String`, `str`, `Cow<str>` or a
+/// reference to one), i.e. whether it can be checked for emptiness
+///
+/// # Arguments
+///
+/// * `ty` - Type to check
+pub(crate) fn is_string(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(ref p) => match p.path.segments.last() {
+            Some(segment) => {
+                let ident = &segment.value().ident;
+                ident == "String" || ident == "str" || ident == "Cow"
+            }
+            None => false,
+        },
+        syn::Type::Reference(ref r) => is_string(&r.elem),
+        _ => false,
+    }
+}
+
+/// Returns the identifiers of all type parameters (not lifetimes or const
+/// parameters) declared on a struct
+///
+/// # Arguments
+///
+/// * `generics` - Generics declared on the struct
+pub(crate) fn type_params(generics: &syn::Generics) -> Vec<syn::Ident> {
+    generics.type_params().map(|p| p.ident.clone()).collect()
+}
+
+/// Detects whether a type refers to any of the given type parameters
+/// (e.g., `T`, `Vec<T>` or `&'a T`)
+///
+/// # Arguments
+///
+/// * `ty` - Type to inspect
+/// * `params` - Type parameters declared on the struct
+pub(crate) fn uses_type_params(ty: &syn::Type, params: &[syn::Ident]) -> bool {
+    match ty {
+        syn::Type::Path(ref p) => {
+            if let Some(ref qself) = p.qself {
+                if uses_type_params(&qself.ty, params) {
+                    return true;
+                }
+            }
+
+            if let Some(first) = p.path.segments.first() {
+                if params.contains(&first.value().ident) {
+                    return true;
+                }
+            }
+
+            p.path.segments.iter().any(|segment| match segment.arguments {
+                syn::PathArguments::AngleBracketed(ref args) => args.args.iter().any(|arg| match arg {
+                    syn::GenericArgument::Type(ref t) => uses_type_params(t, params),
+                    _ => false,
+                }),
+                _ => false,
+            })
+        }
+        syn::Type::Reference(ref r) => uses_type_params(&r.elem, params),
+        syn::Type::Slice(ref s) => uses_type_params(&s.elem, params),
+        syn::Type::Array(ref a) => uses_type_params(&a.elem, params),
+        syn::Type::Ptr(ref p) => uses_type_params(&p.elem, params),
+        syn::Type::Paren(ref p) => uses_type_params(&p.elem, params),
+        syn::Type::Group(ref g) => uses_type_params(&g.elem, params),
+        syn::Type::Tuple(ref t) => t.elems.iter().any(|t| uses_type_params(t, params)),
+        _ => false,
+    }
+}
+
+/// Returns a copy of `generics` with the additional where clause predicates
+/// appended, suitable for use with `split_for_impl`
+///
+/// # Arguments
+///
+/// * `generics` - Generics declared on the struct
+/// * `bounds` - Predicates to add (e.g., `T: AsRef<str>`)
+pub(crate) fn with_bounds(
+    generics: &syn::Generics,
+    bounds: &[proc_macro2::TokenStream],
+) -> syn::Generics {
+    let mut generics = generics.clone();
+    if !bounds.is_empty() {
+        let where_clause = generics.make_where_clause();
+        for bound in bounds {
+            let predicate: syn::WherePredicate =
+                syn::parse2(bound.clone()).expect("WebForms - failed to build where clause");
+            where_clause.predicates.push(predicate);
+        }
+    }
+
+    generics
+}
+
+/// Writes the comparison of a field whose type is a type parameter against
+/// an integer literal from a rule.  The literal is converted into the field's
+/// type with `TryFrom<i128>`, so signed and unsigned types of any width can be
+/// used.  A literal the type can't hold is larger than any of its values, in
+/// which case `out_of_range` is the result
+///
+/// # Arguments
+///
+/// * `ty` - Type of the field (without any `Option`)
+/// * `lit` - Literal from the attribute
+/// * `compare` - Comparison against the converted literal, named `bound`
+/// * `out_of_range` - Result if the literal doesn't fit in the type
+pub(crate) fn compare_literal(
+    ty: &syn::Type,
+    lit: &syn::LitInt,
+    compare: proc_macro2::TokenStream,
+    out_of_range: bool,
+) -> proc_macro2::TokenStream {
+    let lit = proc_macro2::Literal::i128_unsuffixed(i128::from(lit.value()));
+    quote! {
+        match <#ty as ::std::convert::TryFrom<i128>>::try_from(#lit) {
+            Ok(bound) => #compare,
+            Err(_) => #out_of_range,
+        }
+    }
+}
@@ -0,0 +1,479 @@
+//! FormMeta implementation, shared by the ValidateForm and HtmlForm derives
+
+use crate::html::{html_type_constraints, HtmlField};
+use crate::option_inner;
+use crate::parse_attribute_list;
+use crate::validate::{ValidateGroup, ValidateStruct, ValidateType};
+use proc_macro2::{Literal, Span};
+use quote::{quote, ToTokens};
+use std::collections::HashMap;
+
+/// A rule applied to a field, converted to a `webforms::meta::Constraint`
+struct MetaConstraint {
+    rule: proc_macro2::TokenStream,
+    message: Option<String>,
+    code: Option<String>,
+}
+
+impl ToTokens for MetaConstraint {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let rule = &self.rule;
+        let message = optional_str(&self.message);
+        let code = optional_str(&self.code);
+
+        tokens.extend(quote! {
+            ::webforms::meta::Constraint {
+                rule: #rule,
+                message: #message,
+                code: #code,
+            }
+        });
+    }
+}
+
+/// Description of a single field, converted to a `webforms::meta::FieldMeta`
+struct MetaField {
+    name: String,
+    variant: Option<String>,
+    rust_type: String,
+    input_type: String,
+    optional: bool,
+    constraints: Vec<MetaConstraint>,
+    nested: proc_macro2::TokenStream,
+}
+
+impl ToTokens for MetaField {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let name = &self.name;
+        let variant = optional_str(&self.variant);
+        let rust_type = &self.rust_type;
+        let input_type = &self.input_type;
+        let optional = self.optional;
+        let constraints = &self.constraints;
+        let nested = &self.nested;
+
+        tokens.extend(quote! {
+            ::webforms::meta::FieldMeta {
+                name: #name,
+                variant: #variant,
+                rust_type: #rust_type,
+                input_type: #input_type,
+                optional: #optional,
+                constraints: &[#(#constraints),*],
+                nested: #nested,
+            }
+        });
+    }
+}
+
+impl MetaField {
+    /// Describes a field, without any constraints
+    ///
+    /// # Arguments
+    /// * `field` - Field to describe
+    /// * `name` - Name of the field (see `form::field_name`)
+    /// * `html` - The field as HtmlForm renders it
+    /// * `variant` - Enum variant containing the field, if any
+    /// * `generics` - Generics declared on the struct
+    fn new(
+        field: &syn::Field,
+        name: String,
+        html: &HtmlField,
+        variant: Option<&syn::Ident>,
+        generics: &syn::Generics,
+    ) -> MetaField {
+        let ty = &field.ty;
+        let inner = option_inner(ty).unwrap_or(ty);
+
+        // The list of fields is a constant, which can't refer to the struct's
+        // generic parameters
+        let nested = match uses_generics(inner, generics) {
+            true => quote! { || None },
+            false => quote! {
+                || {
+                    #[allow(unused_imports)]
+                    use ::webforms::meta::__private::{ViaFormMeta as _, ViaNothing as _};
+                    (&&::webforms::meta::__private::Probe::<#inner>(::std::marker::PhantomData))
+                        .webforms_form_info()
+                }
+            },
+        };
+
+        MetaField {
+            input_type: html
+                .pair_attrs
+                .get("type")
+                .cloned()
+                .unwrap_or_else(|| "text".to_owned()),
+            name,
+            variant: variant.map(|v| v.to_string()),
+            rust_type: type_name(quote! { #ty }),
+            optional: option_inner(&field.ty).is_some(),
+            constraints: vec![],
+            nested,
+        }
+    }
+
+    /// Adds a constraint to this field, unless the same rule was already
+    /// added by another attribute (e.g., `min_length` and `minlength`).  A
+    /// duplicate still supplies the message or code if the first had none
+    ///
+    /// # Arguments
+    /// * `rule` - Expression building the `webforms::meta::Rule`
+    /// * `message` - Custom error message, if any
+    /// * `code` - Custom error code, if any
+    fn add(
+        &mut self,
+        rule: proc_macro2::TokenStream,
+        message: Option<String>,
+        code: Option<String>,
+    ) {
+        let key = rule.to_string();
+        if let Some(c) = self
+            .constraints
+            .iter_mut()
+            .find(|c| c.rule.to_string() == key)
+        {
+            c.message = c.message.take().or(message);
+            c.code = c.code.take().or(code);
+            return;
+        }
+
+        self.constraints.push(MetaConstraint {
+            rule,
+            message,
+            code,
+        });
+    }
+
+    /// Adds the constraints from the `#[validate]`, `#[form]`,
+    /// `#[validate_match]` and `#[validate_regex]` attributes on this field
+    ///
+    /// # Arguments
+    /// * `index` - Position of the field in `group`
+    /// * `group` - Parsed fields of the struct or enum variant
+    /// * `st` - Parsed struct, holding the regular expressions
+    fn add_validate_rules(&mut self, index: usize, group: &ValidateGroup, st: &ValidateStruct) {
+        for rule in &group.fields[index].attrs {
+            if let Some(tokens) = validate_rule(&rule.ty, group, st) {
+                self.add(tokens, rule.message.clone(), rule.code.clone());
+            }
+        }
+
+        // Rules with an html equivalent imply the matching input type
+        let implied = group.fields[index]
+            .attrs
+            .iter()
+            .find_map(|rule| match rule.ty {
+                ValidateType::Email(_) => Some("email"),
+                ValidateType::Phone(_) => Some("tel"),
+                ValidateType::Url(_) => Some("url"),
+                _ => None,
+            });
+
+        if let (Some(input_type), "text") = (implied, self.input_type.as_str()) {
+            self.input_type = input_type.to_owned();
+        }
+    }
+
+    /// Adds the constraints from the `#[html_validate]` attributes on this
+    /// field, using the messages from `#[html_error]`, followed by those
+    /// implied by the field's type (e.g., `BoundedString<3, 32>`)
+    ///
+    /// # Arguments
+    /// * `field` - Field to parse
+    fn add_html_rules(&mut self, field: &syn::Field) {
+        let mut rules = vec![];
+        let mut errors = HashMap::new();
+
+        for attr in &field.attrs {
+            if attr.path.is_ident("html_validate") {
+                parse_attribute_list(attr, |meta| {
+                    if let syn::Meta::NameValue(ref nv) = meta {
+                        let rule = match (nv.ident.to_string().as_str(), &nv.lit) {
+                            ("min", syn::Lit::Int(ref i)) => {
+                                value_rule("MinValue", i.value() as f64)
+                            }
+                            ("min", syn::Lit::Float(ref f)) => value_rule("MinValue", f.value()),
+                            ("max", syn::Lit::Int(ref i)) => {
+                                value_rule("MaxValue", i.value() as f64)
+                            }
+                            ("max", syn::Lit::Float(ref f)) => value_rule("MaxValue", f.value()),
+                            ("minlength", syn::Lit::Int(ref i)) => {
+                                length_rule("MinLength", i.value())
+                            }
+                            ("maxlength", syn::Lit::Int(ref i)) => {
+                                length_rule("MaxLength", i.value())
+                            }
+                            ("pattern", syn::Lit::Str(ref s)) => {
+                                quote! { ::webforms::meta::Rule::Pattern(#s) }
+                            }
+                            _ => return,
+                        };
+                        rules.push((nv.ident.to_string(), rule));
+                    }
+                });
+            } else if attr.path.is_ident("html_error") {
+                parse_attribute_list(attr, |meta| {
+                    if let syn::Meta::NameValue(syn::MetaNameValue {
+                        ref ident,
+                        lit: syn::Lit::Str(ref s),
+                        ..
+                    }) = meta
+                    {
+                        errors.insert(ident.to_string(), s.value());
+                    }
+                });
+            }
+        }
+
+        for (name, rule) in rules {
+            let message = errors.get(&name).cloned();
+            self.add(rule, message, None);
+        }
+
+        for (attr, value) in html_type_constraints(&field.ty) {
+            let rule = match attr {
+                "minlength" => length_rule("MinLength", value.parse().unwrap_or(0)),
+                "maxlength" => length_rule("MaxLength", value.parse().unwrap_or(0)),
+                "min" => value_rule("MinValue", value.parse().unwrap_or(0.0)),
+                _ => value_rule("MaxValue", value.parse().unwrap_or(0.0)),
+            };
+            self.add(rule, None, None);
+        }
+    }
+}
+
+/// Converts a rule parsed by ValidateForm into an expression building the
+/// matching `webforms::meta::Rule`, or None for `file` rules, which are
+/// checked against the uploaded file rather than a submitted value
+///
+/// # Arguments
+/// * `ty` - Rule to convert
+/// * `group` - Parsed fields of the struct or enum variant containing the rule
+/// * `st` - Parsed struct, holding the regular expressions
+pub(crate) fn validate_rule(
+    ty: &ValidateType,
+    group: &ValidateGroup,
+    st: &ValidateStruct,
+) -> Option<proc_macro2::TokenStream> {
+    let regex = |id: &str| {
+        st.regex_tokens
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| panic!("WebForms: no regex with id `{}`", id))
+    };
+
+    Some(match *ty {
+        ValidateType::StringMin(ref i) => length_rule("MinLength", i.value()),
+        ValidateType::StringMax(ref i) => length_rule("MaxLength", i.value()),
+        ValidateType::ValueMin(ref i) => value_rule("MinValue", i.value() as f64),
+        ValidateType::ValueMax(ref i) => value_rule("MaxValue", i.value() as f64),
+        ValidateType::Regex(ref id) => {
+            let regex = regex(id);
+            quote! { ::webforms::meta::Rule::Pattern(#regex) }
+        }
+        ValidateType::CompiledRegex(ref id) => {
+            let regex = regex(&id.to_string());
+            quote! { ::webforms::meta::Rule::Pattern(#regex) }
+        }
+        ValidateType::Email(_) => quote! { ::webforms::meta::Rule::Email },
+        ValidateType::Phone(_) => quote! { ::webforms::meta::Rule::Phone },
+        ValidateType::Url(_) => quote! { ::webforms::meta::Rule::Url },
+        ValidateType::Match(ref ident) => {
+            let other = group
+                .fields
+                .iter()
+                .find(|f| f.field.ident.as_ref() == Some(ident))
+                .unwrap_or_else(|| panic!("ValidateForm: no field named `{}`", ident));
+            let name = &other.name;
+            quote! { ::webforms::meta::Rule::Matches(#name) }
+        }
+        ValidateType::Required => quote! { ::webforms::meta::Rule::Required },
+        ValidateType::NotBlank => quote! { ::webforms::meta::Rule::NotBlank },
+        ValidateType::OneOf(ref values) => {
+            quote! { ::webforms::meta::Rule::OneOf(&[#(#values),*]) }
+        }
+        ValidateType::File(_) => return None,
+    })
+}
+
+/// Builds a `Rule` holding a length (e.g., `Rule::MinLength(3usize)`)
+fn length_rule(rule: &str, len: u64) -> proc_macro2::TokenStream {
+    let rule = syn::Ident::new(rule, Span::call_site());
+    let len = Literal::usize_suffixed(len as usize);
+    quote! { ::webforms::meta::Rule::#rule(#len) }
+}
+
+/// Builds a `Rule` holding a value (e.g., `Rule::MinValue(18f64)`)
+fn value_rule(rule: &str, value: f64) -> proc_macro2::TokenStream {
+    let rule = syn::Ident::new(rule, Span::call_site());
+    let lit = Literal::f64_suffixed(value.abs());
+    match value < 0.0 {
+        true => quote! { ::webforms::meta::Rule::#rule(-#lit) },
+        false => quote! { ::webforms::meta::Rule::#rule(#lit) },
+    }
+}
+
+/// Converts an optional string into a `Some("...")` or `None` expression
+fn optional_str(value: &Option<String>) -> proc_macro2::TokenStream {
+    match value {
+        Some(ref v) => quote! { Some(#v) },
+        None => quote! { None },
+    }
+}
+
+/// Formats a type the way it would be written in source, removing the
+/// spacing added when converting tokens to a string (`Option < String >`)
+fn type_name(ty: proc_macro2::TokenStream) -> String {
+    let mut name = ty.to_string();
+    for (from, to) in &[
+        (" <", "<"),
+        ("< ", "<"),
+        (" >", ">"),
+        (" ::", "::"),
+        (":: ", "::"),
+        (" ,", ","),
+        (" ;", ";"),
+        ("& ", "&"),
+    ] {
+        name = name.replace(from, to);
+    }
+    name
+}
+
+/// Detects whether a type refers to any of the generic parameters (lifetimes,
+/// types or consts) declared on a struct
+///
+/// # Arguments
+/// * `ty` - Type to inspect
+/// * `generics` - Generics declared on the struct
+fn uses_generics(ty: &syn::Type, generics: &syn::Generics) -> bool {
+    let names: Vec<String> = generics
+        .params
+        .iter()
+        .map(|param| match param {
+            syn::GenericParam::Type(ref t) => t.ident.to_string(),
+            syn::GenericParam::Lifetime(ref l) => l.lifetime.ident.to_string(),
+            syn::GenericParam::Const(ref c) => c.ident.to_string(),
+        })
+        .collect();
+
+    fn walk(tokens: proc_macro2::TokenStream, names: &[String]) -> bool {
+        tokens.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Ident(ref ident) => names.contains(&ident.to_string()),
+            proc_macro2::TokenTree::Group(ref group) => walk(group.stream(), names),
+            _ => false,
+        })
+    }
+
+    !names.is_empty() && walk(quote! { #ty }, &names)
+}
+
+/// Attributes only ValidateForm accepts
+const VALIDATE_ATTRS: &[&str] = &[
+    "validate",
+    "validate_match",
+    "validate_regex",
+    "validate_config",
+];
+
+/// Attributes only HtmlForm accepts
+const HTML_ATTRS: &[&str] = &["html_attrs", "html_input", "html_validate", "html_error"];
+
+/// Detects whether the struct, or any of its fields, has one of the given
+/// attributes
+///
+/// # Arguments
+/// * `ast` - Syntax tree of the struct or enum
+/// * `names` - Names of the attributes to look for
+fn has_attribute(ast: &syn::DeriveInput, names: &[&str]) -> bool {
+    let named = |attrs: &[syn::Attribute]| {
+        attrs
+            .iter()
+            .any(|attr| names.iter().any(|name| attr.path.is_ident(name)))
+    };
+
+    let fields: Vec<&syn::Field> = match ast.data {
+        syn::Data::Struct(ref data) => data.fields.iter().collect(),
+        syn::Data::Enum(ref data) => data.variants.iter().flat_map(|v| v.fields.iter()).collect(),
+        syn::Data::Union(_) => vec![],
+    };
+
+    named(&ast.attrs) || fields.iter().any(|field| named(&field.attrs))
+}
+
+/// Decides whether `derive` implements FormMeta.  A derive can't see the
+/// other derives applied to the same struct, so a struct deriving both
+/// ValidateForm and HtmlForm is recognized by its attributes, which only
+/// compile if the derive accepting them is present.  ValidateForm implements
+/// it when the struct uses a ValidateForm attribute or no HtmlForm attribute,
+/// and HtmlForm only when the struct uses no ValidateForm attribute.  A
+/// struct deriving both that uses neither marks itself with a bare
+/// `#[validate_config]`
+///
+/// # Arguments
+/// * `ast` - Syntax tree of the struct or enum
+/// * `derive` - Name of the derive macro
+fn implements_meta(ast: &syn::DeriveInput, derive: &str) -> bool {
+    let validate = has_attribute(ast, VALIDATE_ATTRS);
+    match derive {
+        "ValidateForm" => validate || !has_attribute(ast, HTML_ATTRS),
+        _ => !validate,
+    }
+}
+
+/// Implementation of the FormMeta trait, emitted by either the ValidateForm or
+/// HtmlForm derive (see `implements_meta`).  Reads the attributes of both,
+/// parsing each field once
+///
+/// # Arguments
+/// * `ast` - Syntax tree of the struct or enum
+/// * `derive` - Name of the derive macro emitting the implementation
+pub(crate) fn impl_form_meta(ast: &syn::DeriveInput, derive: &str) -> proc_macro2::TokenStream {
+    if !implements_meta(ast, derive) {
+        return proc_macro2::TokenStream::new();
+    }
+
+    let mut st = ValidateStruct::new(&ast.ident);
+    st.parse(ast);
+
+    let sets: Vec<(Option<&syn::Ident>, &syn::Fields)> = match ast.data {
+        syn::Data::Struct(ref data) => vec![(None, &data.fields)],
+        syn::Data::Enum(ref data) => data
+            .variants
+            .iter()
+            .map(|v| (Some(&v.ident), &v.fields))
+            .collect(),
+        syn::Data::Union(_) => panic!("WebForms: FormMeta is not defined on unions!"),
+    };
+
+    let mut fields = vec![];
+    for ((variant, set), group) in sets.into_iter().zip(st.groups.iter()) {
+        for (idx, field) in set.iter().enumerate() {
+            let name = group.fields[idx].name.clone();
+            let html = HtmlField::parse(field, name.clone());
+            let mut meta = MetaField::new(field, name, &html, variant, &ast.generics);
+            meta.add_validate_rules(idx, group, &st);
+            meta.add_html_rules(field);
+            fields.push(meta);
+        }
+    }
+
+    let name = &ast.ident;
+    let form_name = name.to_string();
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::webforms::meta::FormMeta for #name #ty_generics #where_clause {
+            fn form_name() -> &'static str {
+                #form_name
+            }
+
+            fn fields() -> &'static [::webforms::meta::FieldMeta] {
+                const FIELDS: &[::webforms::meta::FieldMeta] = &[#(#fields),*];
+                FIELDS
+            }
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! FromForm macro implementation
+
+use crate::form::{field_name, rename_rule};
+use crate::proc_macro::TokenStream;
+use crate::{parse_attribute_list, type_params, uses_type_params, with_bounds};
+use quote::quote;
+
+/// Parses `#[form(empty_as_none)]` or `#[form(keep_empty)]` from the
+/// attributes of a struct or field, returning the matching
+/// `webforms::parse::Empty` (or None if neither is present)
+///
+/// # Arguments
+/// * `attrs` - Attributes attached to the struct or field
+fn empty_policy(attrs: &[syn::Attribute]) -> Option<proc_macro2::TokenStream> {
+    let mut policy = None;
+    for attr in attrs.iter().filter(|a| a.path.is_ident("form")) {
+        parse_attribute_list(attr, |meta| match meta {
+            syn::Meta::Word(ref w) if w == "empty_as_none" => {
+                policy = Some(quote! { ::webforms::parse::Empty::AsNone })
+            }
+            syn::Meta::Word(ref w) if w == "keep_empty" => {
+                policy = Some(quote! { ::webforms::parse::Empty::Keep })
+            }
+            _ => {}
+        });
+    }
+    policy
+}
+
+/// Implementation for the FromForm macro
+pub(crate) fn impl_from_form_macro(ast: syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let fields = match ast.data {
+        syn::Data::Struct(ref data) => &data.fields,
+        _ => panic!("FromForm is only defined on structs!"),
+    };
+
+    let params = type_params(&ast.generics);
+    let rename = rename_rule(&ast.attrs);
+    let default_empty = empty_policy(&ast.attrs);
+
+    let mut bounds = vec![];
+    let mut parsers = vec![];
+    let mut bindings = vec![];
+
+    for (idx, field) in fields.iter().enumerate() {
+        let ty = &field.ty;
+        let field_name = field_name(field, idx, rename);
+        let binding = syn::Ident::new(
+            &format!("__webforms_{}", idx),
+            proc_macro2::Span::call_site(),
+        );
+
+        let empty = match empty_policy(&field.attrs).or_else(|| default_empty.clone()) {
+            Some(policy) => quote! { Some(#policy) },
+            None => quote! { None },
+        };
+
+        // Fields holding a type parameter can't pick a parser until the type
+        // is known, so require FromFormField instead
+        if uses_type_params(ty, &params) {
+            let bound = quote! { #ty: ::webforms::parse::FromFormField };
+            if !bounds
+                .iter()
+                .any(|b: &proc_macro2::TokenStream| b.to_string() == bound.to_string())
+            {
+                bounds.push(bound);
+            }
+        }
+
+        parsers.push(quote! {
+            let field = ::webforms::parse::Field::new(data, #field_name, #empty);
+            let #binding = match (&&&::webforms::parse::__private::Probe::<#ty>(::std::marker::PhantomData))
+                .webforms_from_field(&field)
+            {
+                Ok(value) => Some(value),
+                Err(error) => {
+                    errors.extend(error);
+                    None
+                }
+            };
+        });
+        bindings.push(binding);
+    }
+
+    let members = fields
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| match field.ident {
+            Some(ref ident) => syn::Member::Named(ident.clone()),
+            None => syn::Member::Unnamed(syn::Index::from(idx)),
+        });
+    let patterns: Vec<_> = bindings.iter().map(|b| quote! { Some(#b) }).collect();
+    let values = &bindings;
+
+    let generics = with_bounds(&ast.generics, &bounds);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let gen = quote! {
+        impl #impl_generics ::webforms::parse::FromForm for #name #ty_generics #where_clause {
+            fn from_form(
+                data: &::webforms::parse::FormData,
+            ) -> Result<Self, Vec<::webforms::validate::ValidateError>> {
+                #[allow(unused_imports)]
+                use ::webforms::parse::__private::{
+                    ViaFromFormField as _, ViaFromStr as _, ViaValidatedFromStr as _,
+                };
+
+                #[allow(unused_mut)]
+                let mut errors = vec![];
+                #(#parsers)*
+
+                match (#(#values,)*) {
+                    (#(#patterns,)*) => Ok(#name { #(#members: #values),* }),
+                    #[allow(unreachable_patterns)]
+                    _ => Err(errors),
+                }
+            }
+        }
+
+        // Nested forms (e.g., `address[city]` or `items[0][sku]`) report
+        // their errors under their path from the field holding them, as
+        // ValidateForm does with nested Validate types
+        impl #impl_generics ::webforms::parse::FromFormField for #name #ty_generics #where_clause {
+            fn from_field(
+                field: &::webforms::parse::Field,
+            ) -> Result<Self, ::webforms::validate::ValidateError> {
+                Self::from_field_all(field).map_err(|errors| field.first_error(errors))
+            }
+
+            fn from_field_all(
+                field: &::webforms::parse::Field,
+            ) -> Result<Self, Vec<::webforms::validate::ValidateError>> {
+                <Self as ::webforms::parse::FromForm>::from_form(&field.nested()).map_err(|errors| {
+                    let path = field.path();
+                    errors.into_iter().map(|error| error.with_parent(&path)).collect()
+                })
+            }
+        }
+    };
+
+    gen.into()
+}
@@ -1,12 +1,15 @@
 //! Validate macro implementation
 
+use crate::file::FileRule;
+use crate::form::{field_name, is_form_attribute, rename_rule, RenameRule};
 use crate::proc_macro::TokenStream;
+use crate::{option_inner, type_params, uses_type_params, with_bounds};
 use proc_macro2::Span;
 use quote::{quote, ToTokens};
-use rand::Rng;
 use std::collections::HashMap;
 use syn;
 
+mod arbitrary;
 mod validators;
 
 /// Various kinds of validation types we support along with
@@ -19,16 +22,49 @@ pub(crate) enum ValidateType {
     Regex(String),
     Email(String),
     Phone(String),
+    Url(String),
     CompiledRegex(syn::Ident),
     Match(syn::Ident),
+    Required,
+    NotBlank,
+    OneOf(Vec<syn::LitStr>),
+    File(FileRule),
+}
+
+impl ValidateType {
+    /// Returns true if this rule checks the field as a whole (including
+    /// `None`) rather than the value inside an optional field
+    pub fn whole_value(&self) -> bool {
+        matches!(self, ValidateType::Required | ValidateType::Match(_))
+    }
+}
+
+/// A single validation rule along with the custom message
+/// and/or error code supplied in the same attribute
+pub(crate) struct ValidateRule {
+    pub ty: ValidateType,
+    pub message: Option<String>,
+    pub code: Option<String>,
 }
 
 /// Container for a given validation field and all
 /// #[validate] attributes applied to it
 pub(crate) struct ValidateField<'a> {
     pub field: &'a syn::Field,
-    pub attrs: Vec<ValidateType>,
+    pub key: String,
+    pub name: String,
+    pub access: proc_macro2::TokenStream,
+    pub bound: bool,
+    pub attrs: Vec<ValidateRule>,
     pub optional: bool,
+    pub generic: bool,
+}
+
+/// A set of fields validated together: either the fields of a struct, or
+/// the fields of a single enum variant along with the pattern matching it
+pub(crate) struct ValidateGroup<'a> {
+    pub pattern: Option<proc_macro2::TokenStream>,
+    pub fields: Vec<ValidateField<'a>>,
 }
 
 /// ToTokens implementation for ValidateField
@@ -44,8 +80,13 @@ impl<'a> ToTokens for ValidateField<'a> {
 /// ValidateFrom
 pub(crate) struct ValidateStruct<'a> {
     pub ident: &'a syn::Ident,
-    pub regex_tokens: HashMap<String, String>,
-    pub fields: Vec<ValidateField<'a>>,
+    pub regex_tokens: HashMap<String, proc_macro2::TokenStream>,
+    pub groups: Vec<ValidateGroup<'a>>,
+    pub is_enum: bool,
+    pub mode: syn::Ident,
+    pub rename: Option<RenameRule>,
+    pub arbitrary: bool,
+    pub unknown: Vec<String>,
 }
 
 /// ToTokens implementation for ValidateStruct
@@ -53,8 +94,6 @@ pub(crate) struct ValidateStruct<'a> {
 /// Allows the struct to be used inside a quote! macro
 impl<'a> ToTokens for ValidateStruct<'a> {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
-        let fields = &self.fields;
-
         // If we're using a regex matcher, expand the regex using lazy_static
         // to ensure it only compiles once
         if self.regex_tokens.len() > 0 {
@@ -72,18 +111,39 @@ impl<'a> ToTokens for ValidateStruct<'a> {
             });
         }
 
-        tokens.extend(quote! {
-            #(#fields)*
+        // Enums only validate the fields of the active variant
+        let groups = self.groups.iter().map(|group| {
+            let fields = &group.fields;
+            match group.pattern {
+                Some(ref pattern) => quote! { #pattern => { #(#fields)* } },
+                None => quote! { #(#fields)* },
+            }
+        });
+
+        tokens.extend(match self.is_enum {
+            true => quote! {
+                match self {
+                    #(#groups)*
+                }
+            },
+            false => quote! {
+                #(#groups)*
+            },
         });
     }
 }
 
 impl<'a> ValidateStruct<'a> {
-    fn new(ident: &'a syn::Ident) -> ValidateStruct<'a> {
+    pub fn new(ident: &'a syn::Ident) -> ValidateStruct<'a> {
         ValidateStruct {
             ident: ident,
             regex_tokens: HashMap::new(),
-            fields: vec![],
+            groups: vec![],
+            is_enum: false,
+            rename: None,
+            mode: syn::Ident::new("All", Span::call_site()),
+            arbitrary: false,
+            unknown: vec![],
         }
     }
 
@@ -94,11 +154,20 @@ impl<'a> ValidateStruct<'a> {
     ///
     /// Arguments:
     /// * `ast` - Syntax Tree obtained from parsing input with syn
-    fn parse(&mut self, ast: &'a syn::DeriveInput) {
+    pub fn parse(&mut self, ast: &'a syn::DeriveInput) {
         self.parse_struct_attributes(ast);
         self.parse_field_attributes(ast);
     }
 
+    /// Prints the attributes `parse` didn't recognize.  Parsing itself is
+    /// silent, since the FormMeta implementation parses the same attributes
+    /// again
+    pub fn report_unknown(&self) {
+        for attr in &self.unknown {
+            println!("Unknown attribute: {}", attr);
+        }
+    }
+
     /// Parses all attributes attached to a struct that derives ValidateForm
     /// Examples include: #[validate_regex]
     ///
@@ -113,29 +182,136 @@ impl<'a> ValidateStruct<'a> {
                     .expect("Failed to parse validate_regex attribute");
 
                 self.parse_validate_regex_attr(&meta);
+            } else if attr.path.is_ident("validate_config") {
+                let meta = &attr
+                    .parse_meta()
+                    .expect("Failed to parse validate_config attribute");
+
+                self.parse_validate_config_attr(meta);
             }
         }
     }
 
-    /// Parses attributes on files attached to this struct.  Examples
-    /// include: #[validate]
+    /// Parses the #[validate_config] attribute applied to structs.  Currently
+    /// supports `mode = "all" | "first_per_field" | "fail_fast"` and `arbitrary`.
+    /// A bare `#[validate_config]` changes nothing, but marks a struct also
+    /// deriving HtmlForm (see `meta::impl_form_meta`)
+    ///
+    /// # Arguments
+    /// * `meta` - The parsed meta argument to extract the configuration from
+    fn parse_validate_config_attr(&mut self, meta: &syn::Meta) {
+        match meta {
+            syn::Meta::List(ref list) => {
+                for nested in list.nested.iter() {
+                    match nested {
+                        syn::NestedMeta::Meta(m) => self.parse_validate_config_attr(m),
+                        _ => panic!("ValidateForm: Unsupported validate_config attribute"),
+                    }
+                }
+            }
+            syn::Meta::Word(ref w) if w == "validate_config" => {}
+            syn::Meta::Word(ref w) if w == "arbitrary" => self.arbitrary = true,
+            syn::Meta::NameValue(ref nv) if nv.ident == "mode" => {
+                let mode = match nv.lit {
+                    syn::Lit::Str(ref s) => match s.value().as_str() {
+                        "all" => "All",
+                        "first_per_field" => "FirstPerField",
+                        "fail_fast" => "FailFast",
+                        m => panic!("ValidateForm: unknown validation mode `{}`, expected one of `all`, `first_per_field` or `fail_fast`", m),
+                    },
+                    _ => panic!("ValidateForm: validate_config mode requires a string argument"),
+                };
+
+                self.mode = syn::Ident::new(mode, Span::call_site());
+            }
+            _ => panic!("ValidateForm: Unsupported validate_config attribute"),
+        }
+    }
+
+    /// Parses attributes on fields attached to this struct, or to each
+    /// variant of this enum.  Examples include: #[validate]
     ///
     /// # Arguments
     /// * `ast` - Syntax Tree obtained from parsing input with syn
     fn parse_field_attributes(&mut self, ast: &'a syn::DeriveInput) {
-        let fields = match ast.data {
-            syn::Data::Struct(syn::DataStruct {
-                fields: syn::Fields::Named(ref fields),
-                ..
-            }) => &fields.named,
-            _ => panic!("ValidateForm only defined on data structs!"),
-        };
+        let params = type_params(&ast.generics);
+        self.rename = rename_rule(&ast.attrs);
+
+        match ast.data {
+            syn::Data::Struct(ref data) => {
+                let fields = self.parse_fields(&data.fields, false, &params);
+                self.groups.push(ValidateGroup {
+                    pattern: None,
+                    fields,
+                });
+            }
+            syn::Data::Enum(ref data) => {
+                self.is_enum = true;
+                for variant in data.variants.iter() {
+                    let fields = self.parse_fields(&variant.fields, true, &params);
+                    let name = self.ident;
+                    let var = &variant.ident;
+                    let bindings: Vec<_> = fields.iter().map(|f| binding(&f.key)).collect();
+
+                    let pattern = match variant.fields {
+                        syn::Fields::Named(_) => {
+                            let idents = variant.fields.iter().map(|f| &f.ident);
+                            quote! { #name::#var { #(#idents: #bindings),* } }
+                        }
+                        syn::Fields::Unnamed(_) => quote! { #name::#var(#(#bindings),*) },
+                        syn::Fields::Unit => quote! { #name::#var },
+                    };
+
+                    self.groups.push(ValidateGroup {
+                        pattern: Some(pattern),
+                        fields,
+                    });
+                }
+            }
+            syn::Data::Union(_) => panic!("ValidateForm is not defined on unions!"),
+        }
+    }
 
-        for field in fields.iter() {
-            let mut info = ValidateField::new(field);
+    /// Parses the attributes on each field of a struct or enum variant
+    ///
+    /// # Arguments
+    /// * `fields` - Fields to parse
+    /// * `bound` - True if the fields are bound by a pattern (enum variants),
+    ///   false if they are accessed through `self`
+    /// * `params` - Type parameters declared on the struct
+    fn parse_fields(
+        &mut self,
+        fields: &'a syn::Fields,
+        bound: bool,
+        params: &[syn::Ident],
+    ) -> Vec<ValidateField<'a>> {
+        let mut infos = vec![];
+
+        for (idx, field) in fields.iter().enumerate() {
+            let key = match field.ident {
+                Some(ref ident) => ident.to_string().trim_start_matches("r#").to_owned(),
+                None => idx.to_string(),
+            };
+            let name = field_name(field, idx, self.rename);
+
+            let access = match field.ident {
+                _ if bound => {
+                    let b = binding(&key);
+                    quote! { (*#b) }
+                }
+                Some(ref ident) => quote! { self.#ident },
+                None => {
+                    let index = syn::Index::from(idx);
+                    quote! { self.#index }
+                }
+            };
+
+            let mut info = ValidateField::new(field, key, name, access, bound);
+            info.generic = uses_type_params(&field.ty, params);
+            info.optional = option_inner(&field.ty).is_some();
 
             for attr in &field.attrs {
-                if attr.path.is_ident("validate") {
+                if attr.path.is_ident("validate") || attr.path.is_ident("form") {
                     let meta = &attr
                         .parse_meta()
                         .expect("Failed to parse webform validate attribute");
@@ -153,8 +329,57 @@ impl<'a> ValidateStruct<'a> {
                 }
             }
 
-            self.fields.push(info);
+            infos.push(info);
+        }
+
+        infos
+    }
+
+    /// Builds the where clause predicates required by rules applied to fields
+    /// whose type is (or contains) a type parameter.  For example, a
+    /// `min_length` rule on a field of type `T` requires `T: AsRef<str>`
+    fn bounds(&self) -> Vec<proc_macro2::TokenStream> {
+        let mut bounds: Vec<proc_macro2::TokenStream> = vec![];
+
+        for (group, info) in self
+            .groups
+            .iter()
+            .flat_map(|g| g.fields.iter().map(move |f| (g, f)))
+        {
+            let ty = info.value_type();
+
+            for rule in &info.attrs {
+                let bound = match rule.ty {
+                    ValidateType::Match(ref ident) => {
+                        let other = group
+                            .fields
+                            .iter()
+                            .find(|f| f.field.ident.as_ref() == Some(ident))
+                            .unwrap_or_else(|| panic!("ValidateForm: no field named `{}`", ident));
+
+                        match info.generic || other.generic {
+                            true => {
+                                let (ty, other_ty) = (&info.field.ty, &other.field.ty);
+                                quote! { #ty: ::std::cmp::PartialEq<#other_ty> }
+                            }
+                            false => continue,
+                        }
+                    }
+                    _ if !info.generic => continue,
+                    ValidateType::Required => continue,
+                    ValidateType::ValueMin(_) | ValidateType::ValueMax(_) => quote! {
+                        #ty: ::std::cmp::PartialOrd + ::std::convert::TryFrom<i128>
+                    },
+                    _ => quote! { #ty: ::std::convert::AsRef<str> },
+                };
+
+                if !bounds.iter().any(|b| b.to_string() == bound.to_string()) {
+                    bounds.push(bound);
+                }
+            }
         }
+
+        bounds
     }
 
     /// Parses the #[validate_regex] attribute applied to structs
@@ -178,7 +403,7 @@ impl<'a> ValidateStruct<'a> {
                     syn::Lit::Str(ref s) => {
                         let k = nv.ident.to_string();
                         if !self.regex_tokens.contains_key(&k) {
-                            self.regex_tokens.insert(k, s.value());
+                            self.regex_tokens.insert(k, quote! { #s });
                         } else {
                             panic!("ValidateForm: regex with id `{}` already defined!", k);
                         }
@@ -200,27 +425,115 @@ impl<'a> ValidateField<'a> {
     ///
     /// # Arguments
     /// * `field` - The field (member in struct) for this validator
-    fn new(field: &'a syn::Field) -> ValidateField<'a> {
+    /// * `key` - The field's ident, or its index for tuple fields
+    /// * `name` - Name reported in errors (see `form::field_name`)
+    /// * `access` - Expression evaluating to the field's value
+    /// * `bound` - True if sibling fields are bound by an enum variant pattern
+    fn new(
+        field: &'a syn::Field,
+        key: String,
+        name: String,
+        access: proc_macro2::TokenStream,
+        bound: bool,
+    ) -> ValidateField<'a> {
         ValidateField {
             field: field,
+            key,
+            name,
+            access,
+            bound,
             attrs: vec![],
             optional: false,
+            generic: false,
+        }
+    }
+
+    /// Returns an expression evaluating to the value of the sibling field
+    /// `ident` (used by `#[validate_match]`)
+    ///
+    /// # Arguments
+    /// * `ident` - Name of the sibling field
+    pub fn sibling(&self, ident: &syn::Ident) -> proc_macro2::TokenStream {
+        match self.bound {
+            true => {
+                let b = binding(ident.to_string().trim_start_matches("r#"));
+                quote! { (*#b) }
+            }
+            false => quote! { self.#ident },
+        }
+    }
+
+    /// Returns the type of the value being validated, unwrapping `Option<T>`
+    /// for optional fields
+    pub fn value_type(&self) -> &'a syn::Type {
+        match self.optional {
+            true => option_inner(&self.field.ty).unwrap_or(&self.field.ty),
+            false => &self.field.ty,
         }
     }
 
+    /// Adds a new validation rule (without a custom message or code) to this field
+    ///
+    /// # Arguments
+    /// * `ty` - Type of validation to perform
+    fn add_rule(&mut self, ty: ValidateType) {
+        self.attrs.push(ValidateRule {
+            ty,
+            message: None,
+            code: None,
+        });
+    }
+
+    /// Applies any `message = "..."` or `code = "..."` arguments found in an
+    /// attribute list to every rule added since `start`
+    ///
+    /// # Arguments
+    /// * `list` - Attribute list that may contain a message or code
+    /// * `start` - Index of the first rule parsed from this list
+    fn parse_rule_messages(&mut self, list: &syn::MetaList, start: usize) {
+        for nested in list.nested.iter() {
+            if let syn::NestedMeta::Meta(syn::Meta::NameValue(ref nv)) = nested {
+                if !ValidateField::is_message_attribute(nv) {
+                    continue;
+                }
+
+                let value = match nv.lit {
+                    syn::Lit::Str(ref s) => s.value(),
+                    _ => panic!("ValidateForm: `{}` requires a string argument", nv.ident),
+                };
+
+                for rule in self.attrs.iter_mut().skip(start) {
+                    if nv.ident == "message" {
+                        rule.message = Some(value.clone());
+                    } else if nv.ident == "code" {
+                        rule.code = Some(value.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns true if the name/value attribute is a custom message or code
+    fn is_message_attribute(nv: &syn::MetaNameValue) -> bool {
+        nv.ident == "message" || nv.ident == "code"
+    }
+
     fn parse_validate_match_attribute(&mut self, meta: &syn::Meta) {
         match meta {
             syn::Meta::Word(ref w) => {
-                self.attrs.push(ValidateType::Match(w.clone()));
+                self.add_rule(ValidateType::Match(w.clone()));
             },
             syn::Meta::List(ref list) => {
+                let start = self.attrs.len();
                 for nested in list.nested.iter() {
                     match nested {
                         syn::NestedMeta::Meta(m) => self.parse_validate_match_attribute(m),
                         _ => panic!(""),
                     }
                 }
+                self.parse_rule_messages(list, start);
             },
+            syn::Meta::NameValue(ref nv) if ValidateField::is_message_attribute(nv) => {}
             _ => panic!("")
         }
     }
@@ -229,19 +542,22 @@ impl<'a> ValidateField<'a> {
         match meta {
             syn::Meta::Word(ref w) => {
                 if struct_info.regex_tokens.contains_key(&w.to_string()) {
-                    self.attrs.push(ValidateType::CompiledRegex(w.clone()));
+                    self.add_rule(ValidateType::CompiledRegex(w.clone()));
                 } else {
                     panic!("#[validate_regex] (field) requires a corresponding #[validate_regex] on the struct");
                 }
             },
             syn::Meta::List(ref list) => {
+                let start = self.attrs.len();
                 for nested in list.nested.iter() {
                     match nested {
                         syn::NestedMeta::Meta(m) => self.parse_validate_regex_attribute(m, struct_info),
                         _ => panic!(""),
                     }
                 }
+                self.parse_rule_messages(list, start);
             },
+            syn::Meta::NameValue(ref nv) if ValidateField::is_message_attribute(nv) => {}
             _ => panic!("")
         }
     }
@@ -254,66 +570,101 @@ impl<'a> ValidateField<'a> {
     fn parse_validate_attribute(&mut self, meta: &syn::Meta, struct_info: &mut ValidateStruct<'a>) {
         match meta {
             syn::Meta::Word(ref w) => {
+                // The built-in patterns are shared with the types in `webforms::types`
                 if w == "email" {
                     let id = "form_regex_email".to_owned();
-                    let regex = r"^[a-zA-Z0-9_.+-]+@[a-zA-Z0-9-]+\.[a-zA-Z0-9-.]+$".to_owned();
+                    let regex = quote! { ::webforms::validate::EMAIL_PATTERN };
 
                     if !struct_info.regex_tokens.contains_key(&id) {
                         struct_info.regex_tokens.insert(id.clone(), regex);
                     }
 
-                    self.attrs.push(ValidateType::Email(id));
+                    self.add_rule(ValidateType::Email(id));
                 } else if w == "phone" {
                     let id = "form_regex_us_phone".to_owned();
-                    let regex = r"^(\+\d{1,2}\s)?\(?\d{3}\)?[\s.-]?\d{3}[\s.-]?\d{4}$".to_owned();
+                    let regex = quote! { ::webforms::validate::PHONE_PATTERN };
+
+                    if !struct_info.regex_tokens.contains_key(&id) {
+                        struct_info.regex_tokens.insert(id.clone(), regex);
+                    }
+
+                    self.add_rule(ValidateType::Phone(id));
+                } else if w == "url" {
+                    let id = "form_regex_url".to_owned();
+                    let regex = quote! { ::webforms::validate::URL_PATTERN };
 
                     if !struct_info.regex_tokens.contains_key(&id) {
                         struct_info.regex_tokens.insert(id.clone(), regex);
                     }
 
-                    self.attrs.push(ValidateType::Phone(id));
+                    self.add_rule(ValidateType::Url(id));
+                } else if w == "required" {
+                    self.add_rule(ValidateType::Required);
+                } else if w == "not_blank" {
+                    self.add_rule(ValidateType::NotBlank);
                 } else if w == "optional" {
-                    self.optional = true;
+                    // Option<T> fields are detected automatically, kept for compatibility
+                }
+            }
+            syn::Meta::List(ref list) if list.ident == "file" => {
+                if self.generic {
+                    panic!(
+                        "ValidateForm: `file` on field `{}` requires an UploadedFile field",
+                        self.name
+                    );
                 }
+                self.add_rule(ValidateType::File(FileRule::parse(list)));
+            }
+            syn::Meta::List(ref list) if list.ident == "one_of" => {
+                let values = list
+                    .nested
+                    .iter()
+                    .map(|nested| match nested {
+                        syn::NestedMeta::Literal(syn::Lit::Str(ref s)) => s.clone(),
+                        _ => panic!("ValidateForm: one_of requires a list of strings"),
+                    })
+                    .collect();
+                self.add_rule(ValidateType::OneOf(values));
             }
             syn::Meta::List(ref list) => {
+                let start = self.attrs.len();
                 for nested in list.nested.iter() {
                     match nested {
                         syn::NestedMeta::Meta(m) => self.parse_validate_attribute(m, struct_info),
                         _ => panic!("ValidateForm: Unsupported validate attribute"),
                     }
                 }
+                self.parse_rule_messages(list, start);
             }
             syn::Meta::NameValue(ref nv) => {
                 if nv.ident == "min_length" {
                     match nv.lit {
-                        syn::Lit::Int(ref i) => self.attrs.push(ValidateType::StringMin(i.clone())),
+                        syn::Lit::Int(ref i) => self.add_rule(ValidateType::StringMin(i.clone())),
                         _ => panic!("min_length requires an integer argument"),
                     }
                 } else if nv.ident == "max_length" {
                     match nv.lit {
-                        syn::Lit::Int(ref i) => self.attrs.push(ValidateType::StringMax(i.clone())),
+                        syn::Lit::Int(ref i) => self.add_rule(ValidateType::StringMax(i.clone())),
                         _ => panic!("max_length requires an integer argument"),
                     }
                 } else if nv.ident == "min_value" {
                     match nv.lit {
-                        syn::Lit::Int(ref i) => self.attrs.push(ValidateType::ValueMin(i.clone())),
+                        syn::Lit::Int(ref i) => self.add_rule(ValidateType::ValueMin(i.clone())),
                         _ => panic!("min_value requires an integer argument"),
                     }
                 } else if nv.ident == "max_value" {
                     match nv.lit {
-                        syn::Lit::Int(ref i) => self.attrs.push(ValidateType::ValueMax(i.clone())),
+                        syn::Lit::Int(ref i) => self.add_rule(ValidateType::ValueMax(i.clone())),
                         _ => panic!("max_value requires an integer argument"),
                     }
                 } else if nv.ident == "regex" {
                     match nv.lit {
                         syn::Lit::Str(ref s) => {
-                            let regex = s.value();
-                            let mut rng = rand::thread_rng();
+                            let regex = quote! { #s };
                             let id = format!(
                                 "form_regex_{}_{}",
-                                self.field.ident.as_ref().expect("").to_string(),
-                                rng.gen::<u32>()
+                                self.key,
+                                struct_info.regex_tokens.len()
                             );
 
                             if !struct_info.regex_tokens.contains_key(&id) {
@@ -322,7 +673,7 @@ impl<'a> ValidateField<'a> {
                                 panic!("ValidateForm: regex `{}` already defined!", id);
                             }
 
-                            self.attrs.push(ValidateType::Regex(id));
+                            self.add_rule(ValidateType::Regex(id));
                         }
                         _ => panic!("regex requires a string argument"),
                     }
@@ -331,31 +682,118 @@ impl<'a> ValidateField<'a> {
                         syn::Lit::Str(ref s) => {
                             let regex = s.value();
                             if struct_info.regex_tokens.contains_key(&regex) {
-                                self.attrs.push(ValidateType::Regex(regex));
+                                self.add_rule(ValidateType::Regex(regex));
                             } else {
                                 panic!("compiled_regex requires a pre-compiled regex via a `validate_regex` struct attribute");
                             }
                         }
                         _ => panic!("compiled_regex requires a string argumente"),
                     }
+                } else if ValidateField::is_message_attribute(nv) {
+                    // Handled by `parse_rule_messages` once the whole list is parsed
+                } else if is_form_attribute(nv) {
+                    // Handled by `form::field_name` when the field is created
                 } else {
-                    println!("Unknown attribute: {}", nv.ident.to_string());
+                    struct_info.unknown.push(nv.ident.to_string());
                 }
             }
         }
     }
 }
 
-pub(crate) fn impl_validate_macro(ast: syn::DeriveInput) -> TokenStream {
+/// Returns the identifier a field is bound to when matching an enum variant.
+/// Prefixed to avoid shadowing locals in the generated code
+///
+/// # Arguments
+/// * `name` - Name of the field (or its index for tuple variants)
+pub(crate) fn binding(name: &str) -> syn::Ident {
+    syn::Ident::new(&format!("__webforms_{}", name), Span::call_site())
+}
+
+/// Implementation for the Validate macro
+pub(crate) fn impl_validate_value_macro(ast: syn::DeriveInput) -> TokenStream {
     let name = &ast.ident;
-    let generics = &ast.generics;
 
     let mut validate_info = ValidateStruct::new(name);
     validate_info.parse(&ast);
+    validate_info.report_unknown();
+
+    let generics = with_bounds(&ast.generics, &validate_info.bounds());
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mode = &validate_info.mode;
+
+    // A newtype is the value of the field holding it, so its errors are
+    // reported under that field rather than `field.0`
+    let newtype = match ast.data {
+        syn::Data::Struct(ref data) => match data.fields {
+            syn::Fields::Unnamed(ref fields) => fields.unnamed.len() == 1,
+            _ => false,
+        },
+        _ => false,
+    };
+    let validate_field = match newtype {
+        true => quote! {
+            fn validate_field(
+                &self,
+                field: &'static str,
+                mode: ::webforms::validate::ValidationMode,
+            ) -> Result<(), Vec<ValidateError>> {
+                self.validate_with(mode)
+                    .map_err(|errs| errs.into_iter().map(|e| e.with_field(field)).collect())
+            }
+        },
+        false => quote! {},
+    };
 
     let gen = quote! {
-        impl #generics ValidateForm for #name #generics {
+        impl #impl_generics ::webforms::validate::Validate for #name #ty_generics #where_clause {
+            fn validate(&self) -> Result<(), Vec<ValidateError>> {
+                self.validate_with(::webforms::validate::ValidationMode::#mode)
+            }
+
+            fn validate_with(
+                &self,
+                mode: ::webforms::validate::ValidationMode,
+            ) -> Result<(), Vec<ValidateError>> {
+                let mut v: Vec<ValidateError> = Vec::new();
+
+                #validate_info
+
+                match v.len() {
+                    0 => Ok(()),
+                    _ => Err(v),
+                }
+            }
+
+            #validate_field
+        }
+    };
+
+    gen.into()
+}
+
+/// Implementation for the ValidateForm macro
+pub(crate) fn impl_validate_macro(ast: syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+
+    let mut validate_info = ValidateStruct::new(name);
+    validate_info.parse(&ast);
+    validate_info.report_unknown();
+
+    let generics = with_bounds(&ast.generics, &validate_info.bounds());
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mode = &validate_info.mode;
+
+    let mut gen = quote! {
+        impl #impl_generics ValidateForm for #name #ty_generics #where_clause {
             fn validate(&self) -> Result<(), Vec<ValidateError>> {
+                self.validate_with(::webforms::validate::ValidationMode::#mode)
+            }
+
+            fn validate_with(
+                &self,
+                mode: ::webforms::validate::ValidationMode,
+            ) -> Result<(), Vec<ValidateError>> {
 
                 let mut v: Vec<ValidateError> = Vec::new();
 
@@ -369,5 +807,9 @@ pub(crate) fn impl_validate_macro(ast: syn::DeriveInput) -> TokenStream {
         }
     };
 
+    if validate_info.arbitrary {
+        gen.extend(arbitrary::impl_arbitrary(&ast, &validate_info));
+    }
+
     gen.into()
 }
@@ -0,0 +1,283 @@
+//! Arbitrary implementation for forms with `#[validate_config(arbitrary)]`
+
+use crate::meta::validate_rule;
+use crate::validate::{binding, ValidateGroup, ValidateStruct, ValidateType};
+use quote::quote;
+
+/// A field along with the path and member used to construct or match it
+struct ArbitraryField<'a> {
+    path: proc_macro2::TokenStream,
+    member: syn::Member,
+    index: usize,
+    ty: &'a syn::Type,
+}
+
+impl<'a> ArbitraryField<'a> {
+    /// Returns the strategy generating valid values for this field, based on
+    /// its `FieldMeta`
+    fn valid(&self) -> proc_macro2::TokenStream {
+        let ty = self.ty;
+        let index = self.index;
+        quote! {
+            (&&::webforms::testing::__private::Probe::<#ty>(::std::marker::PhantomData))
+                .webforms_valid(&fields[#index])
+        }
+    }
+
+    /// Returns the statement adding the invalid case for a rule on this field
+    /// to `cases`
+    ///
+    /// # Arguments
+    /// * `name` - Name errors for the field are reported under
+    /// * `rule` - Expression building the `webforms::meta::Rule` to break
+    /// * `code` - Custom code set on the rule, if any
+    fn invalid(
+        &self,
+        name: &str,
+        rule: proc_macro2::TokenStream,
+        code: &Option<String>,
+    ) -> proc_macro2::TokenStream {
+        let ty = self.ty;
+        let index = self.index;
+        let path = &self.path;
+        let member = &self.member;
+        let code = match code {
+            Some(ref code) => quote! { #code },
+            None => quote! { rule.code() },
+        };
+
+        quote! {
+            let rule = #rule;
+            let invalid = (&&::webforms::testing::__private::Probe::<#ty>(::std::marker::PhantomData))
+                .webforms_invalid(&fields[#index], &rule);
+            if let Some(invalid) = invalid {
+                let code = #code;
+                cases.push(
+                    (<Self as ::webforms::testing::proptest::arbitrary::Arbitrary>::arbitrary(), invalid)
+                        .prop_filter_map("form holds another variant", move |(mut form, value)| {
+                            match form {
+                                #path { #member: ref mut slot, .. } => *slot = value,
+                                #[allow(unreachable_patterns)]
+                                _ => return None,
+                            }
+                            Some(::webforms::testing::Invalid { form, field: #name, code })
+                        })
+                        .boxed(),
+                );
+            }
+        }
+    }
+
+    /// Returns the statement adding the invalid case for a `validate_match`
+    /// rule to `cases`: the field is given a valid value that differs from
+    /// the field it must match
+    ///
+    /// # Arguments
+    /// * `name` - Name errors for the field are reported under
+    /// * `other` - Member holding the field to match
+    /// * `code` - Custom code set on the rule, if any
+    fn mismatch(
+        &self,
+        name: &str,
+        other: &syn::Member,
+        code: &Option<String>,
+    ) -> proc_macro2::TokenStream {
+        let valid = self.valid();
+        let path = &self.path;
+        let member = &self.member;
+        let code = match code {
+            Some(ref code) => quote! { #code },
+            None => quote! { "field_mismatch" },
+        };
+
+        quote! {
+            cases.push(
+                (<Self as ::webforms::testing::proptest::arbitrary::Arbitrary>::arbitrary(), #valid)
+                    .prop_filter_map("field still matches", move |(mut form, value)| {
+                        match form {
+                            #path { #member: ref mut slot, #other: ref other, .. } => {
+                                *slot = value;
+                                if *slot == *other {
+                                    return None;
+                                }
+                            }
+                            #[allow(unreachable_patterns)]
+                            _ => return None,
+                        }
+                        Some(::webforms::testing::Invalid { form, field: #name, code: #code })
+                    })
+                    .boxed(),
+            );
+        }
+    }
+}
+
+/// Returns the member used to access a field (its ident, or its index in a
+/// tuple struct)
+///
+/// # Arguments
+/// * `field` - Field to access
+/// * `idx` - Position of the field in its struct or variant
+fn member(field: &syn::Field, idx: usize) -> syn::Member {
+    match field.ident {
+        Some(ref ident) => syn::Member::Named(ident.clone()),
+        None => syn::Member::Unnamed(syn::Index::from(idx)),
+    }
+}
+
+/// Returns the strategy generating a single struct or enum variant with every
+/// field valid.  Fields with a `validate_match` rule are copied from the
+/// field they must match
+///
+/// # Arguments
+/// * `path` - Path of the struct or variant
+/// * `fields` - Fields of the struct or variant
+/// * `group` - Parsed fields of the struct or variant
+fn variant_strategy(
+    path: &proc_macro2::TokenStream,
+    fields: &[ArbitraryField],
+    group: &ValidateGroup,
+) -> proc_macro2::TokenStream {
+    let mut strategies = quote! { ::webforms::testing::proptest::strategy::Just(()) };
+    let mut pattern = quote! { () };
+    let mut copies = vec![];
+
+    for (field, info) in fields.iter().zip(group.fields.iter()).rev() {
+        let valid = field.valid();
+        let b = binding(&info.key);
+
+        let matched = info.attrs.iter().find_map(|rule| match rule.ty {
+            ValidateType::Match(ref ident) => Some(ident),
+            _ => None,
+        });
+
+        let bound = match matched {
+            Some(ident) => {
+                let other = binding(ident.to_string().trim_start_matches("r#"));
+                copies.push(quote! { let #b = ::std::clone::Clone::clone(&#other); });
+                quote! { _ }
+            }
+            None => quote! { #b },
+        };
+
+        strategies = quote! { (#valid, #strategies) };
+        pattern = quote! { (#bound, #pattern) };
+    }
+
+    let members = fields.iter().map(|f| &f.member);
+    let bindings = group.fields.iter().map(|f| binding(&f.key));
+
+    quote! {
+        #strategies
+            .prop_map(|#pattern| {
+                #(#copies)*
+                #path { #(#members: #bindings),* }
+            })
+            .boxed()
+    }
+}
+
+/// Implementation of proptest's `Arbitrary` and `webforms::testing::ArbitraryForm`
+/// for a form
+///
+/// # Arguments
+/// * `ast` - Syntax tree of the struct or enum
+/// * `st` - Parsed struct
+pub(crate) fn impl_arbitrary(
+    ast: &syn::DeriveInput,
+    st: &ValidateStruct,
+) -> proc_macro2::TokenStream {
+    if !ast.generics.params.is_empty() {
+        panic!("ValidateForm: `arbitrary` is not supported on generic forms");
+    }
+
+    let name = &ast.ident;
+    let sets: Vec<(proc_macro2::TokenStream, &syn::Fields)> = match ast.data {
+        syn::Data::Struct(ref data) => vec![(quote! { #name }, &data.fields)],
+        syn::Data::Enum(ref data) => data
+            .variants
+            .iter()
+            .map(|v| {
+                let var = &v.ident;
+                (quote! { #name::#var }, &v.fields)
+            })
+            .collect(),
+        syn::Data::Union(_) => panic!("ValidateForm is not defined on unions!"),
+    };
+
+    let mut index = 0;
+    let mut variants = vec![];
+    let mut cases = vec![];
+
+    for ((path, set), group) in sets.into_iter().zip(st.groups.iter()) {
+        let fields: Vec<_> = set
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| ArbitraryField {
+                path: path.clone(),
+                member: member(field, idx),
+                index: index + idx,
+                ty: &field.ty,
+            })
+            .collect();
+
+        variants.push(variant_strategy(&path, &fields, group));
+
+        for (field, info) in fields.iter().zip(group.fields.iter()) {
+            for rule in &info.attrs {
+                cases.push(match rule.ty {
+                    ValidateType::Match(ref ident) => {
+                        let (idx, other) = set
+                            .iter()
+                            .enumerate()
+                            .find(|(_, f)| f.ident.as_ref() == Some(ident))
+                            .unwrap_or_else(|| panic!("ValidateForm: no field named `{}`", ident));
+                        field.mismatch(&info.name, &member(other, idx), &rule.code)
+                    }
+                    ref ty => {
+                        let rule_tokens = validate_rule(ty, group, st).unwrap_or_else(|| {
+                            panic!("ValidateForm: `arbitrary` does not support `file` rules")
+                        });
+                        field.invalid(&info.name, rule_tokens, &rule.code)
+                    }
+                });
+            }
+        }
+
+        index += set.iter().count();
+    }
+
+    quote! {
+        impl ::webforms::testing::proptest::arbitrary::Arbitrary for #name {
+            type Parameters = ();
+            type Strategy = ::webforms::testing::proptest::strategy::BoxedStrategy<Self>;
+
+            fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+                #[allow(unused_imports)]
+                use ::webforms::testing::__private::{ViaArbitrary as _, ViaFieldValue as _};
+                use ::webforms::testing::proptest::strategy::Strategy as _;
+
+                #[allow(unused_variables)]
+                let fields = <Self as ::webforms::meta::FormMeta>::fields();
+                ::webforms::testing::proptest::strategy::Union::new(vec![#(#variants),*]).boxed()
+            }
+        }
+
+        impl ::webforms::testing::ArbitraryForm for #name {
+            fn invalid_cases() -> Vec<
+                ::webforms::testing::proptest::strategy::BoxedStrategy<::webforms::testing::Invalid<Self>>,
+            > {
+                #[allow(unused_imports)]
+                use ::webforms::testing::__private::{ViaArbitrary as _, ViaFieldValue as _};
+                use ::webforms::testing::proptest::strategy::Strategy as _;
+
+                #[allow(unused_variables)]
+                let fields = <Self as ::webforms::meta::FormMeta>::fields();
+                #[allow(unused_mut)]
+                let mut cases = vec![];
+                #({ #cases })*
+                cases
+            }
+        }
+    }
+}
@@ -1,105 +1,281 @@
 //! All validation code goes here
 
-use crate::validate::{ValidateField, ValidateType};
+use crate::{compare_literal, is_string};
+use crate::validate::{ValidateField, ValidateRule, ValidateType};
 use proc_macro2::Span;
 use quote::quote;
+use std::convert::TryFrom;
 use syn;
 
 pub(crate) fn write(info: &ValidateField, tokens: &mut proc_macro2::TokenStream) {
-    let name = &info.field.ident;
+    let name = &info.name;
+    let access = &info.access;
+    let label = syn::Lifetime::new("'webforms_field", Span::call_site());
     let mut stream = proc_macro2::TokenStream::new();
-    for attr in &info.attrs {
-        let field = match info.optional {
+    for rule in &info.attrs {
+        let field = match info.optional && !rule.ty.whole_value() {
             true => quote! {
                 opt
             },
             false => quote! {
-                self.#name
+                #access
             },
         };
 
-        let refs = match info.optional {
+        let refs = match info.optional && !rule.ty.whole_value() {
             true => quote! {&},
             false => quote! {},
         };
 
-        stream.extend(match attr {
-            ValidateType::StringMin(min) => {
-                quote! {
-                    if #field.len() < #min {
-                        v.push(ValidateError::InputTooShort { field: stringify!(#name), min: #min });
-                    }
-                }
-            },
-            ValidateType::StringMax(max) => {
-                quote! {
-                    if #field.len() > #max {
-                        v.push(ValidateError::InputTooLong { field: stringify!(#name), max: #max });
-                    }
-                }
-            },
+        // Generic fields are accessed through the traits added to the where
+        // clause (see `ValidateStruct::bounds`) rather than inherent methods
+        let (text, len) = match info.generic {
+            true => (
+                quote! { ::std::convert::AsRef::<str>::as_ref(&#field) },
+                quote! { ::std::convert::AsRef::<str>::as_ref(&#field).len() },
+            ),
+            false => (quote! { &#field }, quote! { #field.len() }),
+        };
+
+        let (failed, error) = match &rule.ty {
+            ValidateType::StringMin(min) => (
+                quote! { #len < #min },
+                quote! { ValidateError::InputTooShort { field: #name, min: #min } },
+            ),
+            ValidateType::StringMax(max) => (
+                quote! { #len > #max },
+                quote! { ValidateError::InputTooLong { field: #name, max: #max } },
+            ),
             ValidateType::ValueMin(min) => {
-                quote! {
-                    if #field < #refs #min {
-                        v.push(ValidateError::TooSmall { field: stringify!(#name), min: #min });
-                    }
-                }
-            },
+                let failed = match info.generic {
+                    true => compare_literal(
+                        info.value_type(),
+                        min,
+                        quote! { #field < #refs bound },
+                        true,
+                    ),
+                    false => quote! { #field < #refs #min },
+                };
+                let min = unsuffixed(min);
+                (
+                    failed,
+                    quote! { ValidateError::TooSmall { field: #name, min: #min } },
+                )
+            }
             ValidateType::ValueMax(max) => {
-                quote! {
-                    if #field > #refs #max {
-                        v.push(ValidateError::TooLarge { field: stringify!(#name), max: #max });
-                    }
-                }
-            },
+                let failed = match info.generic {
+                    true => compare_literal(
+                        info.value_type(),
+                        max,
+                        quote! { #field > #refs bound },
+                        false,
+                    ),
+                    false => quote! { #field > #refs #max },
+                };
+                let max = unsuffixed(max);
+                (
+                    failed,
+                    quote! { ValidateError::TooLarge { field: #name, max: #max } },
+                )
+            }
             ValidateType::Regex(id) => {
-                let rid = syn::Ident::new(&id, Span::call_site());
-                quote! {
-                    if !#rid.is_match(&#field) {
-                        v.push(ValidateError::InvalidRegex { field: stringify!(#name) })
-                    }
-                }
-            },
+                let rid = syn::Ident::new(id, Span::call_site());
+                (
+                    quote! { !#rid.is_match(#text) },
+                    quote! { ValidateError::InvalidRegex { field: #name } },
+                )
+            }
             ValidateType::Email(id) => {
-                let rid = syn::Ident::new(&id, Span::call_site());
-                quote! {
-                    if !#rid.is_match(&#field) {
-                        v.push(ValidateError::InvalidEmail { field: stringify!(#name) })
-                    }
-                }
-            },
+                let rid = syn::Ident::new(id, Span::call_site());
+                (
+                    quote! { !#rid.is_match(#text) },
+                    quote! { ValidateError::InvalidEmail { field: #name } },
+                )
+            }
             ValidateType::Phone(id) => {
-                let rid = syn::Ident::new(&id, Span::call_site());
-                quote! {
-                    if !#rid.is_match(&#field) {
-                        v.push(ValidateError::InvalidPhoneNumber { field: stringify!(#name) })
-                    }
-                }
-            },
+                let rid = syn::Ident::new(id, Span::call_site());
+                (
+                    quote! { !#rid.is_match(#text) },
+                    quote! { ValidateError::InvalidPhoneNumber { field: #name } },
+                )
+            }
+            ValidateType::Url(id) => {
+                let rid = syn::Ident::new(id, Span::call_site());
+                (
+                    quote! { !#rid.is_match(#text) },
+                    quote! { ValidateError::InvalidUrl { field: #name } },
+                )
+            }
             ValidateType::Match(ident) => {
-                quote! {
-                    if #field != self.#ident {
-                        v.push(ValidateError::FieldMismatch { field: stringify!(#name) })
-                    }
-                }
+                let other = info.sibling(ident);
+                (
+                    quote! { #field != #other },
+                    quote! { ValidateError::FieldMismatch { field: #name } },
+                )
+            }
+            ValidateType::CompiledRegex(ident) => (
+                quote! { !#ident.is_match(#text) },
+                quote! { ValidateError::InvalidRegex { field: #name } },
+            ),
+            ValidateType::Required => (
+                write_required(info),
+                quote! { ValidateError::Required { field: #name } },
+            ),
+            ValidateType::NotBlank => (
+                quote! { <str>::trim(#text).is_empty() },
+                quote! { ValidateError::Blank { field: #name } },
+            ),
+            ValidateType::OneOf(ref values) => (
+                quote! {{
+                    let value: &str = #text;
+                    ![#(#values),*].contains(&value)
+                }},
+                quote! { ValidateError::NotOneOf { field: #name } },
+            ),
+            // The check reports which of the file's limits failed, so the
+            // condition binds the error it returned
+            ValidateType::File(ref file) => (
+                quote! { let Err(file_error) = (#file).check(&#field, #name) },
+                quote! { file_error },
+            ),
+        };
+
+        // A missing value has nothing left to validate, so `required` always
+        // skips the remaining rules for the field
+        let next = match rule.ty {
+            ValidateType::Required => quote! { _ => break #label, },
+            _ => quote! {
+                ::webforms::validate::ValidationMode::All => {}
+                ::webforms::validate::ValidationMode::FirstPerField => break #label,
             },
-            ValidateType::CompiledRegex(ident) => {
-                quote! {
-                    if !#ident.is_match(&#field) {
-                        v.push(ValidateError::InvalidRegex { field: stringify!(#name) })
-                    }
+        };
+
+        let error = match rule.ty {
+            ValidateType::Required => write_message(rule, &quote! { "" }, error),
+            _ => write_message(rule, &field, error),
+        };
+
+        let check = quote! {
+            if #failed {
+                v.push(#error);
+                match mode {
+                    ::webforms::validate::ValidationMode::FailFast => return Err(v),
+                    #next
                 }
             }
+        };
+
+        // Rules on optional fields only apply when a value is present
+        stream.extend(match info.optional && !rule.ty.whole_value() {
+            true => quote! {
+                if let Some(opt) = #access.as_ref() {
+                    #check
+                }
+            },
+            false => check,
         });
     }
 
-    tokens.extend(match info.optional {
-        true => quote! {
-            match self.#name.as_ref() {
-                Some(opt) => {#stream},
-                None => {},
+    // Fields whose type implements `Validate` are checked as a whole, with
+    // errors reported under their path from this field (e.g., `address.city`)
+    stream.extend(quote! {
+        let nested = {
+            #[allow(unused_imports)]
+            use ::webforms::validate::__private::{ViaNothing as _, ViaValidate as _};
+            (&&::webforms::validate::__private::Nested(&#access)).webforms_validate(#name, mode)
+        };
+
+        if let Err(errs) = nested {
+            v.extend(errs);
+            if let ::webforms::validate::ValidationMode::FailFast = mode {
+                return Err(v);
             }
-        },
-        false => stream,
+        }
     });
+
+    // Each field is wrapped in a labeled block so the remaining rules for
+    // the field can be skipped once one fails (`first_per_field` mode)
+    tokens.extend(quote! {
+        #label: {
+            #stream
+        }
+    });
+}
+
+/// Writes the condition for the `required` rule: true when an optional field
+/// is `None`, or when a string field is empty
+///
+/// # Arguments
+///
+/// * `info` - Field being validated
+fn write_required(info: &ValidateField) -> proc_macro2::TokenStream {
+    let access = &info.access;
+    match (info.optional, is_string(info.value_type())) {
+        (true, true) => quote! {
+            match #access.as_ref() {
+                Some(opt) => opt.is_empty(),
+                None => true,
+            }
+        },
+        (true, false) => quote! { #access.is_none() },
+        (false, true) => quote! { #access.is_empty() },
+        (false, false) => panic!(
+            "ValidateForm: `required` on field `{}` requires an Option or string type",
+            info.name
+        ),
+    }
+}
+
+/// Strips any suffix (e.g., `18u8`) from a literal so it can be stored
+/// in the `i64` fields of `ValidateError`.  Panics if the value doesn't fit
+///
+/// # Arguments
+///
+/// * `lit` - Literal from the attribute
+fn unsuffixed(lit: &syn::LitInt) -> proc_macro2::Literal {
+    match i64::try_from(lit.value()) {
+        Ok(value) => proc_macro2::Literal::i64_unsuffixed(value),
+        Err(_) => panic!(
+            "ValidateForm: bound `{}` is too large, bounds must fit in an i64",
+            lit.value()
+        ),
+    }
+}
+
+/// Wraps the error produced by a failed rule with its custom message and/or
+/// code, if either was supplied.  The field's value is only captured when the
+/// message references `{value}`, so fields need not implement `Display` otherwise
+///
+/// # Arguments
+///
+/// * `rule` - Rule that produced the error
+/// * `field` - Tokens used to access the field's value
+/// * `error` - Tokens constructing the default error
+fn write_message(
+    rule: &ValidateRule,
+    field: &proc_macro2::TokenStream,
+    error: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if rule.message.is_none() && rule.code.is_none() {
+        return error;
+    }
+
+    let code = match rule.code {
+        Some(ref code) => quote! { Some(#code) },
+        None => quote! { None::<&'static str> },
+    };
+
+    let message = match rule.message {
+        Some(ref message) => quote! { Some(#message) },
+        None => quote! { None },
+    };
+
+    let value = match rule.message {
+        Some(ref message) if message.contains("{value}") => {
+            quote! { Some(format!("{}", #field)) }
+        }
+        _ => quote! { None },
+    };
+
+    quote! { #error.with_message(#code, #message, #value) }
 }